@@ -1,25 +1,31 @@
 //! Application state machine for CODEX//XTREME TUI
 
+use crate::config::{BuildChoices, Config};
 use crate::core;
 use crate::tui::screens::BuildPhase;
 use crate::tui::screens::*;
 use crossterm::event::KeyCode;
 use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
 use std::path::PathBuf;
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Current screen
 pub enum Screen {
     Boot(BootScreen),
     RepoSelect(RepoSelectScreen),
-    CloneInput(InputScreen),
+    CloneOptions(CloneOptionsScreen),
+    /// Custom repo path entry
+    PathInput(InputScreen),
     Cloning(CloneScreen),
     VersionSelect(VersionSelectScreen),
     CherryPick(CherryPickScreen),
     PatchSelect(PatchSelectScreen),
     BuildConfig(BuildConfigScreen),
+    StashConfirm(StashConfirmScreen),
+    CleanupConfirm(CleanupConfirmScreen),
     Build(BuildScreen),
 }
 
@@ -28,12 +34,15 @@ impl Screen {
         match self {
             Screen::Boot(s) => s.tick(),
             Screen::RepoSelect(s) => s.tick(),
-            Screen::CloneInput(s) => s.tick(),
+            Screen::CloneOptions(s) => s.tick(),
+            Screen::PathInput(s) => s.tick(),
             Screen::Cloning(s) => s.tick(),
             Screen::VersionSelect(s) => s.tick(),
             Screen::CherryPick(s) => s.tick(),
             Screen::PatchSelect(s) => s.tick(),
             Screen::BuildConfig(s) => s.tick(),
+            Screen::StashConfirm(s) => s.tick(),
+            Screen::CleanupConfirm(s) => s.tick(),
             Screen::Build(s) => s.tick(),
         }
     }
@@ -44,12 +53,15 @@ impl Widget for &Screen {
         match self {
             Screen::Boot(s) => s.render(area, buf),
             Screen::RepoSelect(s) => s.render(area, buf),
-            Screen::CloneInput(s) => s.render(area, buf),
+            Screen::CloneOptions(s) => s.render(area, buf),
+            Screen::PathInput(s) => s.render(area, buf),
             Screen::Cloning(s) => s.render(area, buf),
             Screen::VersionSelect(s) => s.render(area, buf),
             Screen::CherryPick(s) => s.render(area, buf),
             Screen::PatchSelect(s) => s.render(area, buf),
             Screen::BuildConfig(s) => s.render(area, buf),
+            Screen::StashConfirm(s) => s.render(area, buf),
+            Screen::CleanupConfirm(s) => s.render(area, buf),
             Screen::Build(s) => s.render(area, buf),
         }
     }
@@ -63,6 +75,12 @@ pub enum BuildMessage {
     Log(String),
     PatchApplied(String),
     PatchSkipped(String, String), // (name, reason)
+    PatchPartial {
+        name: String,
+        applied: usize,
+        total: usize,
+    },
+    RequiredPatchFailed(crate::workflow::RequiredPatchFailure),
     Version(String),
     InstallPath(String),
     Complete {
@@ -72,26 +90,70 @@ pub enum BuildMessage {
     Error(String),
 }
 
+/// Clone progress message from the clone worker thread
+pub enum CloneMessage {
+    Progress(core::CloneProgress),
+    Done(Result<(), String>),
+}
+
 /// Application state
 pub struct App {
     pub screen: Screen,
     pub should_quit: bool,
     pub dev_mode: bool,
     pub cargo_jobs: Option<usize>,
+    /// Build in a dedicated git worktree instead of the selected clone (`--worktree`)
+    pub use_worktree: bool,
+    /// No tag fetch or history deepening, `cargo --offline` (`--offline`)
+    pub offline: bool,
+    /// Patch conflicts must be resolved before building (`--strict`)
+    pub strict: bool,
+    /// Every selected patch must apply, not just required files (`--require-all-patches`)
+    pub require_all: bool,
+    /// Stash a dirty clone before the in-place checkout (confirmed on the stash screen)
+    pub allow_stash: bool,
+    /// Restore the previous branch and pop the stash after the build
+    pub restore_checkout: bool,
     // Collected data
     pub selected_repo: Option<PathBuf>,
     pub selected_version: Option<String>,
     pub cherry_pick_shas: Vec<String>,
+    pub cherry_pick_options: core::CherryPickOptions,
     pub selected_patches: Vec<PathBuf>, // Now stores patch file paths
+    /// Patch ids switched off inside the selected patch files
+    pub disabled_patches: crate::workflow::DisabledPatches,
     pub build_options: Option<crate::workflow::BuildOptions>,
     pub run_tests: bool,
     pub setup_alias: bool,
+    /// Restore the files an in-place build touched once it is installed
+    pub cleanup: bool,
+    pub optimization_mode: crate::workflow::OptimizationMode,
+    /// Options for "+ CLONE FRESH" (kept for retries and when going back to the form)
+    pub clone_options: core::CloneOptions,
+    /// User config: pre-fills the screens, updated after a successful build
+    pub config: Config,
+    /// `--events=json --events-file PATH`: the build's events are also written here
+    pub event_stream: Option<crate::events::EventWriter>,
     // Background task channels
     build_rx: Option<mpsc::Receiver<BuildMessage>>,
+    clone_rx: Option<mpsc::Receiver<CloneMessage>>,
+    /// Background `git fetch --tags` for the version list
+    fetch_rx: Option<mpsc::Receiver<Result<(), String>>>,
+    /// Repo whose tags were already fetched this session (going back does not refetch)
+    fetched_repo: Option<PathBuf>,
+    /// Set to stop the running clone (git is killed and the partial clone removed)
+    clone_cancel: Arc<AtomicBool>,
 }
 
 impl App {
-    pub fn new(dev_mode: bool, cargo_jobs: Option<usize>) -> Self {
+    pub fn new(
+        dev_mode: bool,
+        cargo_jobs: Option<usize>,
+        use_worktree: bool,
+        offline: bool,
+        strict: bool,
+        require_all: bool,
+    ) -> Self {
         let mut boot = BootScreen::new(dev_mode);
 
         // Real system checks
@@ -118,8 +180,9 @@ impl App {
         );
 
         // Check patches
-        let patches_status = match core::find_patches_dir() {
-            Ok(dir) => format!("{}", dir.display()),
+        let patches_status = match core::patch_dirs(None).as_deref() {
+            Ok([dir]) => format!("{}", dir.path.display()),
+            Ok(dirs) => format!("{} directories", dirs.len()),
             Err(_) => "not found".to_string(),
         };
         boot.add_check_with_detail("Patch definitions", patches_status);
@@ -128,19 +191,40 @@ impl App {
         let repos = core::find_codex_repos().unwrap_or_default();
         boot.add_check_with_detail("Codex repositories", format!("{} found", repos.len()));
 
+        let config = Config::load().unwrap_or_default();
+
         Self {
             screen: Screen::Boot(boot),
             should_quit: false,
             dev_mode,
-            cargo_jobs,
+            cargo_jobs: config.cargo_jobs(cargo_jobs),
+            use_worktree,
+            offline,
+            strict,
+            require_all,
+            allow_stash: false,
+            restore_checkout: false,
             selected_repo: None,
             selected_version: None,
             cherry_pick_shas: Vec::new(),
+            cherry_pick_options: core::CherryPickOptions::default(),
             selected_patches: Vec::new(),
+            disabled_patches: config.last.disabled_patches.clone(),
             build_options: None,
-            run_tests: true,
-            setup_alias: true,
+            run_tests: config.defaults.run_tests,
+            setup_alias: config.defaults.setup_alias,
+            cleanup: config.defaults.cleanup,
+            optimization_mode: config
+                .defaults
+                .optimization_mode(core::has_mold(), core::has_bolt()),
+            clone_options: core::CloneOptions::default(),
+            config,
+            event_stream: None,
             build_rx: None,
+            clone_rx: None,
+            fetch_rx: None,
+            fetched_repo: None,
+            clone_cancel: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -154,23 +238,44 @@ impl App {
             }
         }
 
-        // Handle clone progress
-        if let Screen::Cloning(ref mut screen) = self.screen {
-            if screen.frame() == 5 && !screen.is_complete() && !screen.is_error() {
-                let dest = PathBuf::from(screen.destination());
-                screen.set_progress("Cloning repository...");
-
-                // Use core::clone_codex for real cloning
-                match core::clone_codex(&dest) {
-                    Ok(_) => {
-                        screen.set_complete();
+        // Handle clone progress from the worker thread
+        if let Some(rx) = self.clone_rx.take() {
+            let mut finished = None;
+            while let Ok(msg) = rx.try_recv() {
+                match msg {
+                    CloneMessage::Progress(progress) => {
+                        if let Screen::Cloning(ref mut screen) = self.screen {
+                            if !screen.is_cancelling() {
+                                screen.set_fraction(progress.fraction);
+                                screen.set_progress(format!(
+                                    "{}: {}",
+                                    progress.phase, progress.detail
+                                ));
+                            }
+                        }
                     }
-                    Err(e) => {
-                        screen.set_error(format!("{}", e));
+                    CloneMessage::Done(result) => finished = Some(result),
+                }
+            }
+            match finished {
+                None => self.clone_rx = Some(rx),
+                Some(result) => {
+                    if let Screen::Cloning(ref mut screen) = self.screen {
+                        match result {
+                            Ok(()) => screen.set_complete(),
+                            Err(_) if screen.is_cancelling() => {
+                                let dest = screen.destination().to_string();
+                                self.transition_to_clone_options(Some(dest));
+                                return;
+                            }
+                            Err(e) => screen.set_error(e),
+                        }
                     }
                 }
             }
+        }
 
+        if let Screen::Cloning(ref mut screen) = self.screen {
             // Auto-advance after clone completes
             if screen.should_auto_advance() {
                 let dest = screen.destination().to_string();
@@ -179,6 +284,53 @@ impl App {
             }
         }
 
+        // Refresh the version list once the background tag fetch finishes
+        if let Some(rx) = self.fetch_rx.take() {
+            match rx.try_recv() {
+                Err(mpsc::TryRecvError::Empty) => self.fetch_rx = Some(rx),
+                Err(mpsc::TryRecvError::Disconnected) => {}
+                Ok(result) => {
+                    if let (Screen::VersionSelect(_), Some(repo)) =
+                        (&self.screen, self.selected_repo.clone())
+                    {
+                        let versions = Self::load_versions(&repo);
+                        let label = core::last_fetch_label(&repo);
+                        if let Screen::VersionSelect(ref mut screen) = self.screen {
+                            match result {
+                                Ok(()) => {
+                                    screen.replace_versions(versions);
+                                    screen.set_fetch_state(FetchState::Fetched(label));
+                                }
+                                Err(error) => {
+                                    screen.set_fetch_state(FetchState::Failed { error, label })
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Load the changelog for the highlighted release
+        if let Screen::VersionSelect(ref mut screen) = self.screen {
+            if let (Some((index, tag, previous)), Some(repo)) =
+                (screen.changelog_needed(), &self.selected_repo)
+            {
+                let changelog = core::release_changelog(repo, &tag, &previous)
+                    .map(|entries| core::format_changelog(&entries))
+                    .unwrap_or_else(|e| vec![format!("- {}", e)]);
+                screen.set_changelog(index, changelog);
+            }
+        }
+
+        // Load the commit browser's diff preview for the highlighted commit
+        if let Screen::CherryPick(ref mut screen) = self.screen {
+            if let (Some(sha), Some(repo)) = (screen.preview_needed(), &self.selected_repo) {
+                let diff = core::commit_diff(repo, &sha).unwrap_or_else(|e| format!("{}", e));
+                screen.set_preview(sha, &diff);
+            }
+        }
+
         // Handle build progress from background thread
         if let Some(rx) = self.build_rx.take() {
             // Collect all available messages first
@@ -195,6 +347,12 @@ impl App {
                     break;
                 }
             }
+            if messages
+                .iter()
+                .any(|msg| matches!(msg, BuildMessage::Complete { .. }))
+            {
+                self.remember_build();
+            }
 
             // Process messages
             if let Screen::Build(ref mut screen) = self.screen {
@@ -208,6 +366,14 @@ impl App {
                         BuildMessage::PatchSkipped(name, reason) => {
                             screen.add_skipped_patch(name, reason)
                         }
+                        BuildMessage::PatchPartial {
+                            name,
+                            applied,
+                            total,
+                        } => screen.add_partial_patch(name, applied, total),
+                        BuildMessage::RequiredPatchFailed(failure) => {
+                            screen.add_required_failure(failure.to_string())
+                        }
                         BuildMessage::Version(v) => screen.set_version(v),
                         BuildMessage::InstallPath(p) => screen.set_install_path(p),
                         BuildMessage::Complete {
@@ -231,7 +397,17 @@ impl App {
     }
 
     pub fn handle_key(&mut self, key: KeyCode) {
+        // Text fields get every character (including 'q'); an edit in progress handles Esc.
+        let editing = match &self.screen {
+            Screen::CherryPick(s) => s.is_editing(),
+            Screen::VersionSelect(s) => s.is_searching(),
+            _ => false,
+        };
+        let text_input =
+            editing || matches!(self.screen, Screen::CloneOptions(_) | Screen::PathInput(_));
         match key {
+            KeyCode::Char(_) if text_input => self.handle_screen_key(key),
+            KeyCode::Esc if editing => self.handle_screen_key(key),
             KeyCode::Char('q') | KeyCode::Char('Q') => {
                 self.should_quit = true;
             }
@@ -247,11 +423,22 @@ impl App {
     fn handle_back(&mut self) {
         match &self.screen {
             Screen::Boot(_) | Screen::RepoSelect(_) => {}
-            Screen::CloneInput(_) => self.transition_to_repo_select(),
+            Screen::CloneOptions(_) | Screen::PathInput(_) => self.transition_to_repo_select(),
             Screen::Cloning(s) if s.is_error() => self.transition_to_repo_select(),
+            Screen::Cloning(s) if s.is_cloning() => self.cancel_clone(),
             Screen::Cloning(_) => {}
             Screen::VersionSelect(_) => self.transition_to_repo_select(),
             Screen::CherryPick(_) => self.transition_to_version_select(),
+            Screen::PatchSelect(s) if s.is_previewing() => {
+                if let Screen::PatchSelect(s) = &mut self.screen {
+                    s.close_preview();
+                }
+            }
+            Screen::PatchSelect(s) if s.is_resolving_conflicts() => {
+                if let Screen::PatchSelect(s) = &mut self.screen {
+                    s.close_conflicts();
+                }
+            }
             Screen::PatchSelect(_) => {
                 if self.dev_mode {
                     self.transition_to_cherry_pick();
@@ -262,6 +449,8 @@ impl App {
             Screen::BuildConfig(_) => {
                 self.transition_to_patch_select();
             }
+            Screen::StashConfirm(_) => self.transition_to_build_config(),
+            Screen::CleanupConfirm(_) => self.transition_to_repo_select(),
             Screen::Build(s) if s.is_complete() || s.is_error() => {
                 self.should_quit = true;
             }
@@ -282,16 +471,37 @@ impl App {
                 KeyCode::Down => screen.select_next(),
                 KeyCode::Enter => {
                     if screen.is_clone_selected() {
-                        self.transition_to_clone_input();
+                        self.transition_to_clone_options(None);
+                    } else if screen.is_path_selected() {
+                        self.transition_to_path_input();
                     } else if let Some(repo) = screen.selected_repo() {
-                        self.selected_repo = Some(repo.path.clone());
-                        self.transition_to_version_select();
+                        let path = repo.path.clone();
+                        self.open_repo(path);
                     }
                 }
                 _ => {}
             },
 
-            Screen::CloneInput(screen) => match key {
+            Screen::CloneOptions(screen) => match key {
+                KeyCode::Up => screen.select_prev(),
+                KeyCode::Down | KeyCode::Tab => screen.select_next(),
+                KeyCode::Left | KeyCode::Right if screen.is_selector_focused() => {
+                    screen.toggle_current()
+                }
+                KeyCode::Char(c) => screen.insert_char(c),
+                KeyCode::Backspace => screen.delete_char(),
+                KeyCode::Enter => match screen.options() {
+                    Ok(options) => {
+                        let dest = screen.destination().to_string();
+                        self.clone_options = options;
+                        self.start_clone(dest);
+                    }
+                    Err(e) => screen.set_error(Some(format!("{:#}", e))),
+                },
+                _ => {}
+            },
+
+            Screen::PathInput(screen) => match key {
                 KeyCode::Char(c) => screen.insert_char(c),
                 KeyCode::Backspace => screen.delete_char(),
                 KeyCode::Delete => screen.delete_forward(),
@@ -299,12 +509,10 @@ impl App {
                 KeyCode::Right => screen.move_right(),
                 KeyCode::Home => screen.move_home(),
                 KeyCode::End => screen.move_end(),
-                KeyCode::Enter => {
-                    let dest = screen.value().to_string();
-                    if !dest.is_empty() {
-                        self.start_clone(dest);
-                    }
-                }
+                KeyCode::Enter => match core::open_codex_repo(screen.value()) {
+                    Ok(repo) => self.open_repo(repo.path),
+                    Err(e) => screen.set_error(Some(format!("{:#}", e))),
+                },
                 _ => {}
             },
 
@@ -322,9 +530,23 @@ impl App {
                 _ => {}
             },
 
+            Screen::VersionSelect(screen) if screen.is_searching() => match key {
+                KeyCode::Char(c) => screen.insert_char(c),
+                KeyCode::Backspace => screen.delete_char(),
+                KeyCode::Enter | KeyCode::Esc => screen.finish_search(key == KeyCode::Enter),
+                KeyCode::Up => screen.select_prev(),
+                KeyCode::Down => screen.select_next(),
+                _ => {}
+            },
+
             Screen::VersionSelect(screen) => match key {
                 KeyCode::Up => screen.select_prev(),
+                KeyCode::Char('/') => screen.start_search(),
+                KeyCode::Char('p') | KeyCode::Char('P') => screen.toggle_prereleases(),
+                KeyCode::Char('l') | KeyCode::Char('L') => screen.toggle_latest_per_minor(),
                 KeyCode::Down => screen.select_next(),
+                KeyCode::PageUp => screen.scroll_changelog(-8),
+                KeyCode::PageDown => screen.scroll_changelog(8),
                 KeyCode::Enter => {
                     if let Some(ver) = screen.selected_version() {
                         self.selected_version = Some(ver.tag.clone());
@@ -340,61 +562,150 @@ impl App {
                 _ => {}
             },
 
-            Screen::CherryPick(screen) => match key {
+            Screen::CherryPick(screen) if screen.is_editing() => match key {
                 KeyCode::Char(c) => screen.insert_char(c),
                 KeyCode::Backspace => screen.delete_char(),
-                KeyCode::Delete => screen.delete_forward(),
-                KeyCode::Left => screen.move_left(),
-                KeyCode::Right => screen.move_right(),
-                KeyCode::Home => screen.move_home(),
-                KeyCode::End => screen.move_end(),
-                KeyCode::Enter => {
-                    let input = screen.value().to_string();
-                    let mut shas = Vec::new();
-                    let mut invalid = Vec::new();
-
-                    for part in input.split(',') {
-                        let s = part.trim();
-                        if s.is_empty() {
-                            continue;
-                        }
-                        let valid = s.len() >= 7
-                            && s.len() <= 40
-                            && s.chars().all(|c| c.is_ascii_hexdigit());
-                        if valid {
-                            shas.push(s.to_string());
-                        } else {
-                            invalid.push(s.to_string());
-                        }
+                KeyCode::Enter | KeyCode::Esc => {
+                    let new_filter = screen.finish_edit(key == KeyCode::Enter);
+                    if new_filter.is_some() {
+                        load_commits(self.selected_repo.as_deref(), screen);
                     }
+                }
+                _ => {}
+            },
 
-                    if !invalid.is_empty() && screen.status().is_none() {
+            Screen::CherryPick(screen) => match key {
+                KeyCode::Up => screen.select_prev(),
+                KeyCode::Down => screen.select_next(),
+                KeyCode::Char(' ') => screen.toggle_current(),
+                KeyCode::Char('d') | KeyCode::Char('D') => screen.toggle_preview(),
+                KeyCode::PageUp => screen.scroll_preview(-10),
+                KeyCode::PageDown => screen.scroll_preview(10),
+                KeyCode::Char('/') => screen.start_filter_edit(),
+                KeyCode::Char('m') | KeyCode::Char('M') => screen.start_manual_edit(),
+                KeyCode::Char('r') | KeyCode::Char('R') => screen.cycle_retry(),
+                KeyCode::Enter => {
+                    let Some(repo) = self.selected_repo.clone() else {
+                        return;
+                    };
+                    let (specs, invalid) = core::parse_commit_specs(screen.manual());
+                    if !invalid.is_empty() {
                         screen.set_status(Some(format!(
-                            "Ignored invalid SHA(s): {}",
-                            invalid
-                                .iter()
-                                .map(|s| s.as_str())
-                                .collect::<Vec<_>>()
-                                .join(", ")
+                            "Invalid SHA(s)/range(s): {}",
+                            invalid.join(", ")
                         )));
                         return;
                     }
-
-                    self.cherry_pick_shas = shas;
-                    self.transition_to_patch_select();
+                    match core::resolve_cherry_picks(&repo, &screen.selected_shas(), &specs) {
+                        Ok(shas) => {
+                            self.cherry_pick_shas = shas;
+                            self.cherry_pick_options = screen.cherry_pick_options().clone();
+                            self.transition_to_patch_select();
+                        }
+                        Err(e) => screen.set_status(Some(e.to_string())),
+                    }
                 }
                 _ => {}
             },
 
+            Screen::PatchSelect(screen) if screen.is_previewing() => match key {
+                KeyCode::Up => screen.scroll_preview(-1),
+                KeyCode::Down => screen.scroll_preview(1),
+                KeyCode::PageUp => screen.scroll_preview(-10),
+                KeyCode::PageDown => screen.scroll_preview(10),
+                KeyCode::Char('p') | KeyCode::Char('P') => screen.close_preview(),
+                _ => {}
+            },
+
+            Screen::PatchSelect(screen) if screen.is_resolving_conflicts() => {
+                match key {
+                    KeyCode::Char('1') => screen.drop_conflict_side(true),
+                    KeyCode::Char('2') => screen.drop_conflict_side(false),
+                    KeyCode::Char('c') | KeyCode::Char('C') if !self.strict => {
+                        screen.close_conflicts()
+                    }
+                    _ => return,
+                }
+                // Resolved (or kept): carry on to the build options, unless deselecting a
+                // required file left a requirement unmet.
+                if !screen.is_resolving_conflicts() {
+                    match screen.ordered_selection() {
+                        Ok(selected) => {
+                            self.selected_patches = selected;
+                            self.disabled_patches = screen.disabled_patches();
+                            self.transition_to_build_config();
+                        }
+                        Err(e) => screen.set_status_error(e.to_string()),
+                    }
+                }
+            }
+
             Screen::PatchSelect(screen) => match key {
                 KeyCode::Up => screen.select_prev(),
                 KeyCode::Down => screen.select_next(),
                 KeyCode::Char(' ') => screen.toggle_current(),
+                KeyCode::Right => screen.expand_current(),
+                KeyCode::Left => screen.collapse_current(),
                 KeyCode::Char('a') | KeyCode::Char('A') => screen.select_all(),
                 KeyCode::Char('n') | KeyCode::Char('N') => screen.select_none(),
+                KeyCode::Char('p') | KeyCode::Char('P') => {
+                    // Build order where the relations allow one, else selection order.
+                    let selected: Vec<PathBuf> = screen.ordered_selection().unwrap_or_else(|_| {
+                        screen.selected_patch_paths().into_iter().cloned().collect()
+                    });
+                    let Some(repo) = &self.selected_repo else {
+                        return;
+                    };
+                    if selected.is_empty() {
+                        screen.set_preview_error("Select at least one patch to preview");
+                        return;
+                    }
+                    // Preview against the target tag; cherry-picks are applied at build time.
+                    let tag = self.selected_version.as_deref();
+                    let mut source = tag.unwrap_or("working tree").to_string();
+                    if !self.cherry_pick_shas.is_empty() {
+                        source.push_str(" (before cherry-picks)");
+                    }
+                    let workspace = repo.join(core::CODEX_RS_SUBDIR);
+                    let disabled = screen.disabled_patches();
+                    match crate::workflow::preview_patches(&workspace, tag, &selected, &disabled) {
+                        Ok(previews) => screen.set_preview(&source, &previews),
+                        Err(e) => screen.set_preview_error(format!("Preview failed: {}", e)),
+                    }
+                }
                 KeyCode::Enter => {
-                    self.selected_patches =
-                        screen.selected_patch_paths().into_iter().cloned().collect();
+                    // Missing requirements, declared conflicts and cycles are fixed here.
+                    let selected = match screen.ordered_selection() {
+                        Ok(selected) => selected,
+                        Err(e) => {
+                            screen.set_status_error(e.to_string());
+                            return;
+                        }
+                    };
+                    let disabled = screen.disabled_patches();
+                    // Overlapping patches are resolved here rather than failing mid-build.
+                    if let (Some(repo), true) = (&self.selected_repo, selected.len() > 1) {
+                        let workspace = repo.join(core::CODEX_RS_SUBDIR);
+                        let tag = self.selected_version.as_deref();
+                        match crate::workflow::find_patch_conflicts(
+                            &workspace, tag, &selected, &disabled,
+                        ) {
+                            Ok(conflicts) if !conflicts.is_empty() => {
+                                self.selected_patches = selected;
+                                screen.set_conflicts(conflicts, self.strict);
+                                return;
+                            }
+                            Ok(_) => {}
+                            Err(e) if self.strict => {
+                                screen.set_preview_error(format!("Conflict check failed: {}", e));
+                                return;
+                            }
+                            // The pipeline checks again and logs the failure.
+                            Err(_) => {}
+                        }
+                    }
+                    self.selected_patches = selected;
+                    self.disabled_patches = disabled;
                     self.transition_to_build_config();
                 }
                 _ => {}
@@ -424,14 +735,60 @@ impl App {
                         optimization: screen.optimization_flags(),
                         strip_symbols: screen.strip_symbols(),
                         cargo_jobs: self.cargo_jobs,
+                        offline: self.offline,
                     });
                     self.run_tests = screen.run_tests();
                     self.setup_alias = screen.setup_alias();
+                    self.cleanup = screen.cleanup();
+                    self.optimization_mode = screen.optimization_mode();
+
+                    // In-place builds check out the tag in the user's clone: ask before
+                    // stashing their uncommitted work.
+                    let dirty = match &self.selected_repo {
+                        Some(repo) if !self.use_worktree => core::dirty_files(repo),
+                        _ => Vec::new(),
+                    };
+                    self.allow_stash = false;
+                    self.restore_checkout = false;
+                    if dirty.is_empty() {
+                        self.start_build();
+                    } else {
+                        let repo = self
+                            .selected_repo
+                            .as_ref()
+                            .map(|p| p.display().to_string())
+                            .unwrap_or_default();
+                        self.screen = Screen::StashConfirm(StashConfirmScreen::new(repo, dirty));
+                    }
+                }
+                _ => {}
+            },
+
+            Screen::StashConfirm(screen) => match key {
+                KeyCode::Char(' ') => screen.toggle_restore(),
+                KeyCode::Enter => {
+                    self.allow_stash = true;
+                    self.restore_checkout = screen.restore_after();
                     self.start_build();
                 }
                 _ => {}
             },
 
+            Screen::CleanupConfirm(screen) => match key {
+                KeyCode::Char(' ') => screen.toggle_restore(),
+                KeyCode::Char('s') | KeyCode::Char('S') => self.transition_to_version_select(),
+                KeyCode::Enter => {
+                    let Some(repo) = self.selected_repo.clone() else {
+                        return;
+                    };
+                    match clean_up_leftovers(&repo, screen) {
+                        Ok(()) => self.transition_to_version_select(),
+                        Err(e) => screen.set_error(Some(format!("Cleanup failed: {:#}", e))),
+                    }
+                }
+                _ => {}
+            },
+
             Screen::Build(screen) => match key {
                 KeyCode::Char('r') | KeyCode::Char('R') if screen.is_error() => {
                     // Retry build
@@ -447,17 +804,43 @@ impl App {
 
     // Transitions
 
-    fn transition_to_clone_input(&mut self) {
-        // Get default path
-        let default_path = dirs::home_dir()
-            .map(|h| h.join("dev/codex"))
-            .unwrap_or_else(|| PathBuf::from("~/dev/codex"));
+    /// `destination` pre-fills the form (e.g. after a cancelled clone); defaults to ~/dev/codex.
+    fn transition_to_clone_options(&mut self, destination: Option<String>) {
+        let destination = destination.unwrap_or_else(|| {
+            dirs::home_dir()
+                .map(|h| h.join("dev/codex"))
+                .unwrap_or_else(|| PathBuf::from("~/dev/codex"))
+                .to_string_lossy()
+                .to_string()
+        });
 
-        let screen = InputScreen::new("Clone destination")
-            .placeholder("Enter path (e.g., ~/dev/codex)")
-            .initial_value(default_path.to_string_lossy().to_string());
+        let screen = CloneOptionsScreen::new(destination, &self.clone_options);
 
-        self.screen = Screen::CloneInput(screen);
+        self.screen = Screen::CloneOptions(screen);
+    }
+
+    /// Use `repo`; first offer to clean up what an interrupted in-place build left in it.
+    fn open_repo(&mut self, repo: PathBuf) {
+        self.selected_repo = Some(repo.clone());
+        match crate::cleanup::detect_leftovers(&repo) {
+            Some(leftovers) => {
+                self.screen = Screen::CleanupConfirm(CleanupConfirmScreen::new(
+                    repo.display().to_string(),
+                    leftovers,
+                ));
+            }
+            _ => self.transition_to_version_select(),
+        }
+    }
+
+    fn transition_to_path_input(&mut self) {
+        let screen = InputScreen::new("Repository path")
+            .header("OPEN REPOSITORY")
+            .placeholder("Enter path (e.g., ~/src/codex)")
+            .info("The repo root, i.e. the directory containing codex-rs/")
+            .action("Open");
+
+        self.screen = Screen::PathInput(screen);
     }
 
     fn start_clone(&mut self, destination: String) {
@@ -472,54 +855,139 @@ impl App {
             destination.clone()
         };
 
-        let mut screen = CloneScreen::new(&expanded);
+        let options = &self.clone_options;
+        let mut source = options.source.clone();
+        if let Some(branch) = &options.branch {
+            source.push_str(&format!(" @ {}", branch));
+        }
+        source.push_str(&format!(" · {}", options.effective_history()));
+        if options.sparse {
+            source.push_str(&format!(" · sparse {}/", core::CODEX_RS_SUBDIR));
+        }
+        let mut screen = CloneScreen::new(&expanded).source(source);
         screen.set_progress("Starting git clone...");
-
         self.screen = Screen::Cloning(screen);
+
+        // Clone on a worker thread so the UI keeps animating and can cancel.
+        let (tx, rx) = mpsc::channel();
+        self.clone_rx = Some(rx);
+        self.clone_cancel = Arc::new(AtomicBool::new(false));
+        let cancel = Arc::clone(&self.clone_cancel);
+        let options = self.clone_options.clone();
+        let dest = PathBuf::from(expanded);
+        thread::spawn(move || {
+            let result = core::clone_codex_with_progress(&dest, &options, &cancel, |progress| {
+                let _ = tx.send(CloneMessage::Progress(progress));
+            });
+            let _ = tx.send(CloneMessage::Done(
+                result.map(|_| ()).map_err(|e| format!("{:#}", e)),
+            ));
+        });
+    }
+
+    fn cancel_clone(&mut self) {
+        self.clone_cancel.store(true, Ordering::Relaxed);
+        if let Screen::Cloning(ref mut screen) = self.screen {
+            screen.set_cancelling();
+        }
+    }
+
+    /// Stop background work before exiting: a running clone is cancelled and its partial
+    /// directory removed (waits briefly for git to exit).
+    pub fn shutdown(&mut self) {
+        if let Some(rx) = self.clone_rx.take() {
+            self.clone_cancel.store(true, Ordering::Relaxed);
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+                match rx.recv_timeout(left) {
+                    Ok(CloneMessage::Done(_)) | Err(_) => break,
+                    Ok(CloneMessage::Progress(_)) => {}
+                }
+            }
+        }
     }
 
     fn transition_to_repo_select(&mut self) {
         // Use real repo detection from core
         let core_repos = core::find_codex_repos().unwrap_or_default();
 
+        let mut core_repos = core_repos;
+        if let Some(preferred) = self.config.preferred_repo() {
+            let canonical = preferred.canonicalize().unwrap_or(preferred);
+            if !core_repos.iter().any(|r| r.path == canonical) {
+                if let Ok(repo) = core::open_codex_repo(&canonical.to_string_lossy()) {
+                    core_repos.insert(0, repo);
+                }
+            }
+        }
+
         let repos: Vec<RepoInfo> = core_repos
             .into_iter()
             .map(|r| RepoInfo {
+                is_modified: core::has_uncommitted_changes(&r.path),
                 path: r.path,
                 branch: r.branch,
                 age: r.age,
-                is_modified: false,
             })
             .collect();
 
-        self.screen = Screen::RepoSelect(RepoSelectScreen::new(repos));
+        let mut screen = RepoSelectScreen::new(repos);
+        if let Some(initial) = self.config.initial_repo() {
+            screen.select_path(&initial.canonicalize().unwrap_or(initial));
+        }
+        self.screen = Screen::RepoSelect(screen);
+    }
+
+    /// Releases from the repo's local tags.
+    fn load_versions(repo_path: &std::path::Path) -> Vec<VersionInfo> {
+        let current = core::get_current_version(repo_path);
+        let releases = core::get_releases(repo_path).unwrap_or_default();
+
+        let latest = core::latest_stable_release(&releases);
+
+        releases
+            .into_iter()
+            .enumerate()
+            .map(|(i, r)| {
+                let is_current = current.as_ref() == Some(&r.version);
+                VersionInfo {
+                    tag: r.tag,
+                    date: r.published,
+                    is_latest: latest == Some(i),
+                    is_current,
+                    changelog: Vec::new(),
+                }
+            })
+            .collect()
     }
 
     fn transition_to_version_select(&mut self) {
-        // Fetch real releases from the repo
+        // Show local tags right away; fetch new ones in the background (unless offline)
         if let Some(ref repo_path) = self.selected_repo {
-            // Fetch tags first
-            let _ = core::fetch_repo(repo_path);
-
-            let current = core::get_current_version(repo_path);
-            let releases = core::get_releases(repo_path).unwrap_or_default();
-
-            let versions: Vec<VersionInfo> = releases
-                .into_iter()
-                .enumerate()
-                .map(|(i, r)| {
-                    let is_current = current.as_ref() == Some(&r.version);
-                    VersionInfo {
-                        tag: r.tag,
-                        date: r.published,
-                        is_latest: i == 0,
-                        is_current,
-                        changelog: Vec::new(),
-                    }
-                })
-                .collect();
+            let mut screen = VersionSelectScreen::new(Self::load_versions(repo_path));
+            if let Some(tag) = &self.config.last.tag {
+                screen.select_tag(tag);
+            }
 
-            self.screen = Screen::VersionSelect(VersionSelectScreen::new(versions));
+            let label = core::last_fetch_label(repo_path);
+            if self.offline {
+                self.fetch_rx = None;
+                screen.set_fetch_state(FetchState::Offline(label));
+            } else if self.fetched_repo.as_ref() == Some(repo_path) && self.fetch_rx.is_none() {
+                screen.set_fetch_state(FetchState::Fetched(label));
+            } else {
+                self.fetched_repo = Some(repo_path.clone());
+                let (tx, rx) = mpsc::channel();
+                self.fetch_rx = Some(rx);
+                let repo = repo_path.clone();
+                thread::spawn(move || {
+                    let result = core::fetch_repo(&repo, core::FETCH_TIMEOUT)
+                        .map_err(|e| format!("{:#}", e));
+                    let _ = tx.send(result);
+                });
+                screen.set_fetch_state(FetchState::Fetching);
+            }
+            self.screen = Screen::VersionSelect(screen);
         }
     }
 
@@ -527,37 +995,102 @@ impl App {
         let version = self.selected_version.clone().unwrap_or_default();
 
         // Load real patches from codex-patcher
-        let available = core::get_available_patches().unwrap_or_default();
+        let catalog =
+            core::get_available_patches(self.selected_repo.as_deref()).unwrap_or_else(|e| {
+                core::PatchCatalog {
+                    warnings: vec![format!("{:#}", e)],
+                    ..Default::default()
+                }
+            });
+        let available = &catalog.patches;
+
+        // The last selection where still available, else every compatible patch
+        let paths: Vec<PathBuf> = available.iter().map(|(p, _)| p.clone()).collect();
+        let compatible_paths: Vec<PathBuf> = available
+            .iter()
+            .filter(|(_, c)| core::is_patch_compatible(c.meta.version_range.as_deref(), &version))
+            .map(|(p, _)| p.clone())
+            .collect();
+        let initial = self.config.initial_patches(&paths, compatible_paths);
+        // One compatible variant per selected family (privacy-v0.99 → privacy-v0.105, ...)
+        let ranges: Vec<(&std::path::Path, Option<&str>)> = available
+            .iter()
+            .map(|(p, c)| (p.as_path(), c.meta.version_range.as_deref()))
+            .collect();
+        let (initial, notes) = core::pick_patch_variants(&ranges, &initial, &version);
 
-        let patches: Vec<PatchInfo> = available
-            .into_iter()
+        let mut patches: Vec<PatchInfo> = available
+            .iter()
             .map(|(path, config)| {
-                let compatible =
-                    core::is_patch_compatible(config.meta.version_range.as_deref(), &version);
+                let incompatible_reason =
+                    core::incompatibility(config.meta.version_range.as_deref(), &version);
+                let selected = initial.contains(path);
+                let entries: Vec<PatchEntry> = config
+                    .patches
+                    .iter()
+                    .map(|patch| PatchEntry {
+                        id: patch.id.clone(),
+                        file: patch.file.clone(),
+                    })
+                    .collect();
+                // Ids switched off last time, if the file still has them. Required files
+                // (every file with `require_all`) have to apply in full.
+                let required = self.require_all || catalog.order.is_required(path);
+                let disabled = self
+                    .disabled_patches
+                    .get(path)
+                    .into_iter()
+                    .flatten()
+                    .filter(|id| !required && entries.iter().any(|e| &e.id == *id))
+                    .cloned()
+                    .collect();
 
                 PatchInfo {
-                    path,
+                    path: path.clone(),
                     name: config.meta.name.clone(),
                     description: config
                         .meta
                         .description
+                        .clone()
                         .unwrap_or_else(|| config.meta.name.clone()),
                     patch_count: config.patches.len(),
-                    selected: compatible,
-                    compatible,
+                    selected,
+                    compatible: incompatible_reason.is_none(),
+                    version_range: config.meta.version_range.clone(),
+                    incompatible_reason,
+                    family: core::patch_family(path),
+                    source: catalog
+                        .source_of(path)
+                        .map(|dir| dir.to_string())
+                        .unwrap_or_default(),
+                    entries,
+                    disabled,
                 }
             })
             .collect();
-
-        self.screen = Screen::PatchSelect(PatchSelectScreen::new(patches, version));
+        // Keep the variants of a family next to each other.
+        patches.sort_by(|a, b| a.family.cmp(&b.family));
+
+        let mut warnings = catalog.warnings.clone();
+        warnings.extend(notes);
+        self.screen = Screen::PatchSelect(
+            PatchSelectScreen::new(patches, version)
+                .with_warnings(warnings)
+                .with_order(catalog.order)
+                .with_require_all(self.require_all),
+        );
     }
 
     fn transition_to_cherry_pick(&mut self) {
         let version = self.selected_version.clone().unwrap_or_default();
-        let mut screen = CherryPickScreen::new(version);
-        if !self.cherry_pick_shas.is_empty() {
-            screen.set_value(self.cherry_pick_shas.join(", "));
-        }
+        let upstream = self
+            .selected_repo
+            .as_deref()
+            .and_then(core::default_upstream);
+        let mut screen = CherryPickScreen::new(version, upstream);
+        load_commits(self.selected_repo.as_deref(), &mut screen);
+        screen.set_selected(&self.cherry_pick_shas);
+        screen.set_cherry_pick_options(self.cherry_pick_options.clone());
         self.screen = Screen::CherryPick(screen);
     }
 
@@ -566,12 +1099,38 @@ impl App {
         let has_mold = core::has_mold();
         let has_bolt = core::has_bolt();
 
-        self.screen = Screen::BuildConfig(BuildConfigScreen::new(
-            cpu.display_name(),
-            format!("{:?}", cpu.detected_by),
-            has_mold,
-            has_bolt,
-        ));
+        self.screen = Screen::BuildConfig(
+            BuildConfigScreen::new(
+                cpu.display_name(),
+                format!("{:?}", cpu.detected_by),
+                has_mold,
+                has_bolt,
+            )
+            .with_defaults(&self.config.defaults),
+        );
+    }
+
+    /// Save this build's choices as the new defaults (best effort).
+    fn remember_build(&mut self) {
+        let (Some(repo), Some(tag), Some(options)) = (
+            &self.selected_repo,
+            &self.selected_version,
+            &self.build_options,
+        ) else {
+            return;
+        };
+        self.config.record_build(&BuildChoices {
+            repo,
+            tag,
+            patches: &self.selected_patches,
+            disabled_patches: &self.disabled_patches,
+            options,
+            optimization: self.optimization_mode,
+            run_tests: self.run_tests,
+            setup_alias: self.setup_alias,
+            cleanup: self.cleanup,
+        });
+        let _ = self.config.save();
     }
 
     fn start_build(&mut self) {
@@ -607,9 +1166,6 @@ impl App {
             }
         };
 
-        let patches = self.selected_patches.clone();
-        let cherry_pick_shas = self.cherry_pick_shas.clone();
-        let workspace = repo_path.join(core::CODEX_RS_SUBDIR);
         let build_options = match &self.build_options {
             Some(o) => o.clone(),
             None => crate::workflow::BuildOptions {
@@ -621,55 +1177,79 @@ impl App {
                 },
                 strip_symbols: true,
                 cargo_jobs: self.cargo_jobs,
+                offline: self.offline,
             },
         };
-        let run_tests = self.run_tests;
-        let setup_alias = self.setup_alias;
-        let params = RunBuildParams {
+        let params = crate::workflow::PipelineParams {
             repo_path,
-            workspace,
             version,
-            cherry_pick_shas,
-            patches,
+            cherry_pick_shas: self.cherry_pick_shas.clone(),
+            cherry_pick_options: self.cherry_pick_options.clone(),
+            patches: self.selected_patches.clone(),
+            disabled_patches: self.disabled_patches.clone(),
             build_options,
-            run_tests,
-            setup_alias,
+            run_tests: self.run_tests,
+            setup_alias: self.setup_alias,
+            cache_dir: crate::cache::default_dir(),
+            use_worktree: self.use_worktree,
+            allow_stash: self.allow_stash,
+            restore_checkout: self.restore_checkout,
+            cleanup: self.cleanup,
+            strict: self.strict,
+            require_all: self.require_all,
         };
 
         // Create channel for progress updates
         let (tx, rx) = mpsc::channel();
         self.build_rx = Some(rx);
 
-        // Spawn background build thread
+        // Spawn background build thread (the event stream covers the first build only)
+        let event_stream = self.event_stream.take();
         thread::spawn(move || {
-            run_build(tx, params);
+            run_build(tx, params, event_stream);
         });
     }
 }
 
-struct RunBuildParams {
-    repo_path: PathBuf,
-    workspace: PathBuf,
-    version: String,
-    cherry_pick_shas: Vec<String>,
-    patches: Vec<PathBuf>,
-    build_options: crate::workflow::BuildOptions,
-    run_tests: bool,
-    setup_alias: bool,
+/// Undo an interrupted build's changes, then optionally restore the pre-build branch/stash.
+fn clean_up_leftovers(repo: &std::path::Path, screen: &CleanupConfirmScreen) -> anyhow::Result<()> {
+    let leftovers = screen.leftovers();
+    crate::cleanup::clean_leftovers(repo, leftovers)?;
+    if let Some(journal) = leftovers
+        .journal
+        .as_ref()
+        .filter(|_| screen.restore_after())
+    {
+        core::restore_checkout(repo, &journal.restore_point())?;
+    }
+    Ok(())
+}
+
+/// (Re)load the commit browser's list for its tag, upstream and path filter.
+fn load_commits(repo: Option<&std::path::Path>, screen: &mut CherryPickScreen) {
+    let (Some(repo), Some(upstream)) = (repo, screen.upstream().map(String::from)) else {
+        screen.set_status(Some(
+            "No origin/main or main branch: press [M] to type SHAs or ranges".to_string(),
+        ));
+        return;
+    };
+    let filter = screen.path_filter().to_string();
+    match core::list_commits(repo, screen.target_tag(), &upstream, Some(filter.as_str())) {
+        Ok(commits) => {
+            screen.set_commits(commits);
+            screen.set_status(None);
+        }
+        Err(e) => screen.set_status(Some(format!("{}", e))),
+    }
 }
 
 /// Background build process
-fn run_build(tx: mpsc::Sender<BuildMessage>, params: RunBuildParams) {
-    let RunBuildParams {
-        repo_path,
-        workspace,
-        version,
-        cherry_pick_shas,
-        patches,
-        build_options,
-        run_tests,
-        setup_alias,
-    } = params;
+fn run_build(
+    tx: mpsc::Sender<BuildMessage>,
+    params: crate::workflow::PipelineParams,
+    mut event_stream: Option<crate::events::EventWriter>,
+) {
+    use crate::workflow::{Event, Phase};
 
     let start_time = Instant::now();
 
@@ -678,199 +1258,89 @@ fn run_build(tx: mpsc::Sender<BuildMessage>, params: RunBuildParams) {
         let _ = tx.send(msg);
     };
 
-    // CLI-equivalent workflow:
-    // checkout -> optional cherry-pick -> apply patches -> build -> optional BOLT -> optional strip
-    // -> optional tests -> optional alias setup.
-    send(BuildMessage::Version(version.clone()));
-    send(BuildMessage::InstallPath("shell alias".to_string()));
-
-    // Phase 1: Checkout version
-    send(BuildMessage::Phase(BuildPhase::Patching));
-    send(BuildMessage::CurrentItem(format!(
-        "Checking out {}",
-        version
-    )));
-    send(BuildMessage::Log(format!("git checkout {}", version)));
-    if let Err(e) = core::checkout_version(&repo_path, &version) {
-        send(BuildMessage::Error(format!("Checkout failed: {}", e)));
-        return;
-    }
-
-    // Optional: cherry-pick commits (dev mode)
-    if !cherry_pick_shas.is_empty() {
-        send(BuildMessage::CurrentItem(format!(
-            "Cherry-picking {} commits...",
-            cherry_pick_shas.len()
-        )));
-        match core::cherry_pick_commits(&repo_path, &cherry_pick_shas) {
-            Ok(outcome) => {
-                if !outcome.skipped.is_empty() {
-                    send(BuildMessage::Log(format!(
-                        "  ⚠ skipped {} conflicting commit(s): {}",
-                        outcome.skipped.len(),
-                        outcome
-                            .skipped
-                            .iter()
-                            .map(|s| &s[..7.min(s.len())])
-                            .collect::<Vec<_>>()
-                            .join(", ")
-                    )));
-                }
-            }
-            Err(e) => send(BuildMessage::Log(format!(
-                "  ⚠ cherry-pick errored: {} (continuing)",
-                e
-            ))),
+    send(BuildMessage::Version(params.version.clone()));
+    send(BuildMessage::InstallPath(
+        crate::install::InstallStore::open_default()
+            .map(|store| store.current_binary().display().to_string())
+            .unwrap_or_else(|_| "shell alias".to_string()),
+    ));
+
+    // The screen has fewer phases than the workflow; only forward actual changes so the
+    // progress bar is not reset when e.g. compiling hands over to BOLT.
+    let mut last_phase: Option<BuildPhase> = None;
+    let result = crate::workflow::run_pipeline(&params, |ev| {
+        if let Some(writer) = event_stream.as_mut() {
+            writer.event(&ev);
         }
-    }
-
-    // Phase 2: Apply patches
-    if !patches.is_empty() {
-        if let Err(e) = crate::workflow::apply_patches(&workspace, &patches, |ev| match ev {
-            crate::workflow::Event::Phase(_) => {}
-            crate::workflow::Event::Progress(p) => send(BuildMessage::Progress(0.02 + 0.08 * p)),
-            crate::workflow::Event::CurrentItem(s) => send(BuildMessage::CurrentItem(s)),
-            crate::workflow::Event::Log(s) => send(BuildMessage::Log(s)),
-            crate::workflow::Event::PatchFileApplied(name) => {
-                send(BuildMessage::PatchApplied(name))
-            }
-            crate::workflow::Event::PatchFileSkipped { name, reason } => {
+        match ev {
+            Event::Phase(phase) => {
+                let phase = match phase {
+                    Phase::Patching => BuildPhase::Patching,
+                    Phase::Compiling | Phase::Optimizing | Phase::Testing => BuildPhase::Compiling,
+                    Phase::Installing => BuildPhase::Installing,
+                };
+                if last_phase != Some(phase) {
+                    last_phase = Some(phase);
+                    send(BuildMessage::Phase(phase));
+                }
+            }
+            Event::Progress(p) => send(BuildMessage::Progress(p)),
+            Event::CurrentItem(s) => send(BuildMessage::CurrentItem(s)),
+            Event::Log(s) => send(BuildMessage::Log(s)),
+            Event::PatchFileApplied(name) => send(BuildMessage::PatchApplied(name)),
+            Event::PatchFileSkipped { name, reason } => {
                 send(BuildMessage::PatchSkipped(name, reason))
             }
-        }) {
-            send(BuildMessage::Error(format!(
-                "Patch application failed: {}",
-                e
-            )));
-            return;
-        }
-    }
-
-    // Phase 3: Compile (with autofix)
-    if build_options.profile == "xtreme" {
-        if let Err(e) = crate::workflow::inject_xtreme_profile(&workspace) {
-            send(BuildMessage::Log(format!(
-                "  ⚠ Failed to inject xtreme profile: {} (continuing)",
-                e
-            )));
+            Event::PatchFilePartial {
+                name,
+                applied,
+                total,
+                reason,
+            } => {
+                send(BuildMessage::Log(format!(
+                    "  ◐ {}: {} of {} patches applied ({})",
+                    name, applied, total, reason
+                )));
+                send(BuildMessage::PatchPartial {
+                    name,
+                    applied,
+                    total,
+                })
+            }
+            Event::RequiredPatchFailed(failure) => send(BuildMessage::RequiredPatchFailed(failure)),
         }
-    }
+    });
 
-    send(BuildMessage::Phase(BuildPhase::Compiling));
-    send(BuildMessage::CurrentItem(format!(
-        "Building codex-cli (profile {})...",
-        build_options.profile
-    )));
-
-    let mut binary_path = match crate::workflow::build_with_autofix(
-        &workspace,
-        &build_options.profile,
-        build_options.cpu_target.as_deref(),
-        &build_options.optimization,
-        build_options.cargo_jobs,
-        |ev| match ev {
-            crate::workflow::Event::Phase(_) => {}
-            crate::workflow::Event::Progress(p) => send(BuildMessage::Progress(0.10 + 0.75 * p)),
-            crate::workflow::Event::CurrentItem(s) => send(BuildMessage::CurrentItem(s)),
-            crate::workflow::Event::Log(s) => send(BuildMessage::Log(s)),
-            crate::workflow::Event::PatchFileApplied(_) => {}
-            crate::workflow::Event::PatchFileSkipped { .. } => {}
-        },
-    ) {
-        Ok(p) => p,
+    let outcome = match result {
+        Ok(outcome) => outcome,
         Err(e) => {
-            send(BuildMessage::Error(format!("Build failed: {}", e)));
+            if let Some(writer) = event_stream.as_mut() {
+                writer.result(Err(format!("{e:#}")), start_time.elapsed());
+            }
+            send(BuildMessage::Error(e.to_string()));
             return;
         }
     };
-    send(BuildMessage::Progress(0.85));
-
-    // Optional: BOLT
-    if build_options.optimization.use_bolt {
-        match crate::workflow::run_bolt_optimization(&binary_path, |ev| match ev {
-            crate::workflow::Event::Phase(_) => {}
-            crate::workflow::Event::Progress(p) => send(BuildMessage::Progress(0.85 + 0.07 * p)),
-            crate::workflow::Event::CurrentItem(s) => send(BuildMessage::CurrentItem(s)),
-            crate::workflow::Event::Log(s) => send(BuildMessage::Log(s)),
-            crate::workflow::Event::PatchFileApplied(_) => {}
-            crate::workflow::Event::PatchFileSkipped { .. } => {}
-        }) {
-            Ok(bolted) => {
-                binary_path = bolted;
-                send(BuildMessage::Log("BOLT optimization complete".to_string()));
-            }
-            Err(e) => send(BuildMessage::Log(format!(
-                "BOLT failed: {} (continuing)",
-                e
-            ))),
-        }
-    }
 
-    // Optional: strip
-    if build_options.strip_symbols {
-        send(BuildMessage::CurrentItem(
-            "Stripping symbols...".to_string(),
+    if params.setup_alias {
+        send(BuildMessage::InstallPath(
+            outcome
+                .alias_rc_file
+                .unwrap_or_else(|| "manual".to_string()),
         ));
-        if let Err(e) = crate::workflow::strip_binary(&binary_path) {
-            send(BuildMessage::Log(format!(
-                "  ⚠ strip failed: {} (continuing)",
-                e
-            )));
-        }
-    }
-
-    // Optional: tests
-    if run_tests {
-        if let Err(e) =
-            crate::workflow::run_verification_tests(&workspace, build_options.cargo_jobs, |ev| {
-                match ev {
-                    crate::workflow::Event::Phase(_) => {}
-                    crate::workflow::Event::Progress(p) => {
-                        send(BuildMessage::Progress(0.92 + 0.05 * p))
-                    }
-                    crate::workflow::Event::CurrentItem(s) => send(BuildMessage::CurrentItem(s)),
-                    crate::workflow::Event::Log(s) => send(BuildMessage::Log(s)),
-                    crate::workflow::Event::PatchFileApplied(_) => {}
-                    crate::workflow::Event::PatchFileSkipped { .. } => {}
-                }
-            })
-        {
-            send(BuildMessage::Log(format!(
-                "  ⚠ tests errored: {} (continuing)",
-                e
-            )));
-        }
-    }
-
-    // Optional: alias setup
-    if setup_alias {
-        send(BuildMessage::Phase(BuildPhase::Installing));
-        send(BuildMessage::CurrentItem(
-            "Setting up shell alias...".to_string(),
-        ));
-        match crate::workflow::setup_alias(&binary_path) {
-            Ok(Some(rc_file)) => {
-                send(BuildMessage::InstallPath(rc_file.clone()));
-                send(BuildMessage::Log(format!(
-                    "  ✓ Added/updated alias in {}",
-                    rc_file
-                )));
-            }
-            Ok(None) => {
-                send(BuildMessage::InstallPath("manual".to_string()));
-                send(BuildMessage::Log(format!(
-                    "  ⚠ Fish shell detected: add alias manually: alias codex=\"{}\"",
-                    binary_path.display()
-                )));
-            }
-            Err(e) => send(BuildMessage::Log(format!("  ⚠ alias setup failed: {}", e))),
-        }
     }
 
     let elapsed = start_time.elapsed();
     let build_time = format!("{:.1}s", elapsed.as_secs_f64());
     send(BuildMessage::Phase(BuildPhase::Complete));
     send(BuildMessage::Progress(1.0));
+    // Prefer the store copy: the target/ binary is overwritten by the next cargo build.
+    let binary_path = outcome
+        .installed
+        .map_or(outcome.binary_path, |build| build.binary);
+    if let Some(writer) = event_stream.as_mut() {
+        writer.result(Ok(binary_path.as_path()), elapsed);
+    }
     send(BuildMessage::Complete {
         binary_path: binary_path.to_string_lossy().to_string(),
         build_time,
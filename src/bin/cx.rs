@@ -9,6 +9,10 @@ async fn main() -> anyhow::Result<()> {
     // Parse args
     let args: Vec<String> = std::env::args().collect();
     let dev_mode = args.iter().any(|a| a == "--dev" || a == "-d");
+    let use_worktree = args.iter().any(|a| a == "--worktree");
+    let offline = args.iter().any(|a| a == "--offline");
+    let strict = args.iter().any(|a| a == "--strict");
+    let require_all = args.iter().any(|a| a == "--require-all-patches");
 
     let cargo_jobs = {
         let mut found: Option<usize> = None;
@@ -51,5 +55,14 @@ async fn main() -> anyhow::Result<()> {
         std::process::exit(1);
     }
 
-    codex_xtreme::tui::run_app(dev_mode, cargo_jobs).await
+    codex_xtreme::tui::run_app(
+        dev_mode,
+        cargo_jobs,
+        use_worktree,
+        offline,
+        strict,
+        require_all,
+        None,
+    )
+    .await
 }
@@ -3,10 +3,15 @@
 //! This is a developer utility to visually sanity-check layout proportions
 //! without needing an interactive terminal session.
 
+use codex_xtreme::cleanup::{BuildJournal, Leftovers};
+use codex_xtreme::core::{CloneOptions, CommitInfo};
+use codex_xtreme::manifest::{PatchRecord, PatchStatus};
 use codex_xtreme::tui::screens::{
-    BuildConfigScreen, CherryPickScreen, PatchInfo, PatchSelectScreen, RepoInfo, RepoSelectScreen,
-    VersionInfo, VersionSelectScreen,
+    BuildConfigScreen, BuildScreen, CherryPickScreen, CleanupConfirmScreen, CloneOptionsScreen,
+    FetchState, PatchEntry, PatchInfo, PatchSelectScreen, RepoInfo, RepoSelectScreen, VersionInfo,
+    VersionSelectScreen,
 };
+use codex_xtreme::workflow::{PatchConflict, PatchEdit, PatchFilePreview, PatchPreview};
 use ratatui::{backend::TestBackend, buffer::Buffer, layout::Rect, prelude::Widget, Terminal};
 use std::path::PathBuf;
 
@@ -86,11 +91,33 @@ fn main() -> anyhow::Result<()> {
         },
     ]);
 
-    let version_select = VersionSelectScreen::new(vec![
+    let clone_options = CloneOptionsScreen::new(
+        "~/dev/codex",
+        &CloneOptions {
+            branch: Some("xtreme".to_string()),
+            sparse: true,
+            ..Default::default()
+        },
+    );
+
+    let mut version_select = VersionSelectScreen::new(vec![
+        VersionInfo {
+            tag: "rust-v0.99.0".to_string(),
+            date: "2026-02-04".to_string(),
+            is_latest: true,
+            is_current: false,
+            changelog: vec![
+                "Features (1)".to_string(),
+                "- core: add /review command".to_string(),
+                "Fixes (2)".to_string(),
+                "- tui: wrap long lines (#123)".to_string(),
+                "- handle 429 responses".to_string(),
+            ],
+        },
         VersionInfo {
             tag: "rust-v0.99.0-alpha.6".to_string(),
             date: "2026-02-01".to_string(),
-            is_latest: true,
+            is_latest: false,
             is_current: false,
             changelog: Vec::new(),
         },
@@ -103,10 +130,31 @@ fn main() -> anyhow::Result<()> {
         },
     ]);
 
-    let mut cherry_pick = CherryPickScreen::new("rust-v0.99.0-alpha.6");
-    cherry_pick.set_value("abc1234, deadbeef, not-a-sha");
+    version_select.set_fetch_state(FetchState::Offline("fetched 2h ago".to_string()));
 
-    let patch_select = PatchSelectScreen::new(
+    let mut cherry_pick =
+        CherryPickScreen::new("rust-v0.99.0-alpha.6", Some("origin/main".to_string()));
+    cherry_pick.set_commits(vec![
+        CommitInfo {
+            sha: "abc1234abc1234abc1234abc1234abc1234abcd".to_string(),
+            short_sha: "abc1234".to_string(),
+            subject: "core: fix retry backoff on 429".to_string(),
+            author: "Jane Dev".to_string(),
+            date: "2026-02-03".to_string(),
+            files: vec!["codex-rs/core/src/client.rs".to_string()],
+        },
+        CommitInfo {
+            sha: "def5678def5678def5678def5678def5678def5".to_string(),
+            short_sha: "def5678".to_string(),
+            subject: "tui: tweak status line".to_string(),
+            author: "Sam Dev".to_string(),
+            date: "2026-02-02".to_string(),
+            files: vec!["codex-rs/tui/src/status.rs".to_string()],
+        },
+    ]);
+    cherry_pick.set_selected(&["abc1234abc1234abc1234abc1234abc1234abcd".to_string()]);
+
+    let mut patch_select = PatchSelectScreen::new(
         vec![
             PatchInfo {
                 path: PathBuf::from("patches/foo.toml"),
@@ -115,27 +163,141 @@ fn main() -> anyhow::Result<()> {
                 patch_count: 3,
                 selected: true,
                 compatible: true,
+                version_range: Some(">=0.99.0-alpha.1, <0.100.0".to_string()),
+                incompatible_reason: None,
+                family: "foo".to_string(),
+                source: "~/.config/codex-patcher/patches (built-in)".to_string(),
+                entries: ["telemetry-off", "statsig-endpoint", "update-check"]
+                    .iter()
+                    .map(|id| PatchEntry {
+                        id: id.to_string(),
+                        file: "core/src/config.rs".to_string(),
+                    })
+                    .collect(),
+                disabled: vec!["statsig-endpoint".to_string()],
             },
             PatchInfo {
-                path: PathBuf::from("patches/bar.toml"),
-                name: "Bar Patch".to_string(),
+                path: PathBuf::from("patches/foo-v0.105-alpha13.toml"),
+                name: "Foo Patch (0.105)".to_string(),
                 description: "Incompatible demo".to_string(),
                 patch_count: 2,
                 selected: false,
                 compatible: false,
+                version_range: Some(">=0.105.0-alpha.1".to_string()),
+                incompatible_reason: Some(
+                    "0.99.0-alpha.6 is outside \">=0.105.0-alpha.1\"".to_string(),
+                ),
+                family: "foo".to_string(),
+                source: "~/team/.codex-xtreme/patches (project)".to_string(),
+                entries: Vec::new(),
+                disabled: Vec::new(),
             },
         ],
         "rust-v0.99.0-alpha.6".to_string(),
+    )
+    .with_warnings(vec![
+        "privacy.toml in ~/team/.codex-xtreme/patches (project) shadows the copy in \
+         ~/.config/codex-patcher/patches (built-in)"
+            .to_string(),
+    ]);
+    patch_select.expand_current();
+
+    let mut patch_preview = PatchSelectScreen::new(Vec::new(), "rust-v0.99.0-alpha.6".to_string());
+    patch_preview.set_preview(
+        "rust-v0.99.0-alpha.6",
+        &[PatchFilePreview {
+            name: "foo".to_string(),
+            path: PathBuf::from("patches/foo.toml"),
+            patches: vec![
+                PatchPreview {
+                    record: PatchRecord {
+                        id: "quiet-banner".to_string(),
+                        status: PatchStatus::Applied,
+                        file: Some(PathBuf::from("tui/src/banner.rs")),
+                        reason: None,
+                    },
+                    diff: "diff --git a/tui/src/banner.rs b/tui/src/banner.rs\n\
+                           --- a/tui/src/banner.rs\n\
+                           +++ b/tui/src/banner.rs\n\
+                           @@ -1,3 +1,3 @@\n \
+                           fn banner() -> bool {\n\
+                           -    true\n\
+                           +    false\n \
+                           }\n"
+                    .to_string(),
+                },
+                PatchPreview {
+                    record: PatchRecord {
+                        id: "old-hook".to_string(),
+                        status: PatchStatus::Failed,
+                        file: Some(PathBuf::from("core/src/hooks.rs")),
+                        reason: Some("query matched nothing".to_string()),
+                    },
+                    diff: String::new(),
+                },
+            ],
+        }],
+    );
+
+    let mut patch_conflicts =
+        PatchSelectScreen::new(Vec::new(), "rust-v0.99.0-alpha.6".to_string());
+    let edit = |file: &str, id: &str, lines: (usize, usize)| PatchEdit {
+        patch_file: PathBuf::from(file),
+        patch_id: id.to_string(),
+        target: PathBuf::from("core/src/otel.rs"),
+        lines,
+    };
+    patch_conflicts.set_conflicts(
+        vec![
+            PatchConflict {
+                first: edit("patches/privacy.toml", "otel-off", (10, 14)),
+                second: edit("patches/offline.toml", "otel-stub", (12, 20)),
+            },
+            PatchConflict {
+                first: edit("patches/privacy.toml", "endpoint", (40, 40)),
+                second: edit("patches/offline.toml", "endpoint-local", (38, 41)),
+            },
+        ],
+        false,
     );
 
     let build_config =
         BuildConfigScreen::new("x86-64-v3".to_string(), "Cpuid".to_string(), true, true);
 
+    let cleanup_confirm = CleanupConfirmScreen::new(
+        "/home/user/dev/codex",
+        Leftovers {
+            journal: Some(BuildJournal {
+                tag: "rust-v0.105.0".to_string(),
+                previous_ref: "main".to_string(),
+                stash: Some("4f2a9c1".to_string()),
+                step: "build".to_string(),
+                touched: vec!["codex-rs/Cargo.toml".to_string()],
+            }),
+            cherry_pick_in_progress: false,
+            profile_injected: true,
+            files: vec![
+                "codex-rs/Cargo.toml".to_string(),
+                "codex-rs/core/src/client.rs".to_string(),
+            ],
+        },
+    );
+
+    let mut build_failed = BuildScreen::new();
+    build_failed
+        .add_required_failure("privacy: statsig-endpoint failed (core/src/statsig.rs): no match");
+    build_failed.add_required_failure("privacy: otel-off skipped for version (core/src/otel.rs)");
+    build_failed.set_error("2 required patch(es) did not apply; not compiling");
+
     let mut out = String::new();
     out.push_str(&render_screen(width, height, "RepoSelect", |a, b| {
         (&repo_select).render(a, b)
     })?);
     out.push('\n');
+    out.push_str(&render_screen(width, height, "CloneOptions", |a, b| {
+        (&clone_options).render(a, b)
+    })?);
+    out.push('\n');
     out.push_str(&render_screen(width, height, "VersionSelect", |a, b| {
         (&version_select).render(a, b)
     })?);
@@ -148,9 +310,25 @@ fn main() -> anyhow::Result<()> {
         (&patch_select).render(a, b)
     })?);
     out.push('\n');
+    out.push_str(&render_screen(width, height, "PatchPreview", |a, b| {
+        (&patch_preview).render(a, b)
+    })?);
+    out.push('\n');
+    out.push_str(&render_screen(width, height, "PatchConflicts", |a, b| {
+        (&patch_conflicts).render(a, b)
+    })?);
+    out.push('\n');
     out.push_str(&render_screen(width, height, "BuildConfig", |a, b| {
         (&build_config).render(a, b)
     })?);
+    out.push('\n');
+    out.push_str(&render_screen(width, height, "CleanupConfirm", |a, b| {
+        (&cleanup_confirm).render(a, b)
+    })?);
+    out.push('\n');
+    out.push_str(&render_screen(width, height, "BuildFailed", |a, b| {
+        (&build_failed).render(a, b)
+    })?);
 
     print!("{}", out);
     Ok(())
@@ -0,0 +1,261 @@
+//! Content-addressed build cache.
+//!
+//! A fat-LTO build of codex-cli takes a long time, and rebuilding the same tag with the same
+//! cherry-picks, patch files and options produces the same binary. The cache key is a SHA-256
+//! over everything that affects the output:
+//!
+//! - the commit the tag resolved to and the cherry-picks applied on top
+//! - the content hash of every selected patch file
+//! - the build options (profile, CPU target, mold/BOLT/strip)
+//! - the toolchain (`rustc -vV` in the workspace, so a `rust-toolchain.toml` there counts) and
+//!   the codex-xtreme version (which injects the profile)
+//!
+//! Entries live in `<cache_dir>/<key>/` next to their manifest. Entries are published with an
+//! atomic rename, so the cache directory can sit on a shared team filesystem.
+
+use crate::manifest::{BuildManifest, PatchFileRecord};
+use crate::workflow::{BuildOptions, CODEX_BINARY};
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Environment variable overriding the default cache directory.
+pub const CACHE_DIR_ENV: &str = "CODEX_XTREME_CACHE_DIR";
+
+/// `$CODEX_XTREME_CACHE_DIR`, else `~/.cache/codex-xtreme/build-cache`.
+pub fn default_dir() -> Option<PathBuf> {
+    match std::env::var_os(CACHE_DIR_ENV) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(
+            shellexpand::tilde(&dir.to_string_lossy()).as_ref(),
+        )),
+        _ => dirs::cache_dir().map(|d| d.join("codex-xtreme").join("build-cache")),
+    }
+}
+
+/// `rustc -vV` output (includes the commit hash and LLVM version).
+///
+/// Runs in `workspace` so rustup picks the toolchain cargo will build with there (e.g. one
+/// pinned by `codex-rs/rust-toolchain.toml`), not the one of the current directory.
+pub fn toolchain_fingerprint(workspace: &Path) -> Option<String> {
+    let rustc = which::which("rustc").ok()?;
+    let output = Command::new(rustc)
+        .current_dir(workspace)
+        .arg("-vV")
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Compute the cache key for a build.
+///
+/// `host_cpu` disambiguates `-C target-cpu=native`, which means different things on
+/// different machines sharing a cache.
+pub fn cache_key(
+    commit: &str,
+    cherry_picks: &[String],
+    patches: &[PatchFileRecord],
+    options: &BuildOptions,
+    toolchain: &str,
+    host_cpu: &str,
+) -> String {
+    let mut input = String::new();
+    input.push_str(&format!("builder={}\n", env!("CARGO_PKG_VERSION")));
+    input.push_str(&format!("commit={commit}\n"));
+    for sha in cherry_picks {
+        input.push_str(&format!("cherry_pick={sha}\n"));
+    }
+    for patch in patches {
+        input.push_str(&format!("patch={}\n", patch.sha256));
+        for id in &patch.disabled {
+            input.push_str(&format!("patch_disabled={id}\n"));
+        }
+    }
+    input.push_str(&format!("profile={}\n", options.profile));
+    match options.cpu_target.as_deref() {
+        Some("native") => input.push_str(&format!("cpu_target=native:{host_cpu}\n")),
+        Some(cpu) => input.push_str(&format!("cpu_target={cpu}\n")),
+        None => input.push_str("cpu_target=\n"),
+    }
+    input.push_str(&format!(
+        "mold={} bolt={} strip={}\n",
+        options.optimization.use_mold, options.optimization.use_bolt, options.strip_symbols
+    ));
+    input.push_str(&format!("toolchain={toolchain}\n"));
+
+    format!("{:x}", Sha256::digest(input.as_bytes()))
+}
+
+/// A binary found in the cache.
+#[derive(Debug, Clone)]
+pub struct CachedBuild {
+    pub binary: PathBuf,
+    pub manifest: BuildManifest,
+    /// The entry's `manifest.json`.
+    pub manifest_path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct BuildCache {
+    dir: PathBuf,
+}
+
+impl BuildCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Look up a cached binary. Entries without a readable manifest are ignored.
+    pub fn lookup(&self, key: &str) -> Option<CachedBuild> {
+        let entry = self.dir.join(key);
+        let binary = entry.join(CODEX_BINARY);
+        if !binary.is_file() {
+            return None;
+        }
+        let manifest_path = entry.join("manifest.json");
+        let manifest = std::fs::read_to_string(&manifest_path)
+            .ok()
+            .and_then(|s| BuildManifest::from_json(&s).ok())?;
+        Some(CachedBuild {
+            binary,
+            manifest,
+            manifest_path,
+        })
+    }
+
+    /// Publish a finished binary under `key`. Returns the entry directory.
+    ///
+    /// If another writer published the same key first, their entry is kept.
+    pub fn store(
+        &self,
+        key: &str,
+        binary_path: &Path,
+        manifest: &BuildManifest,
+    ) -> Result<PathBuf> {
+        let entry = self.dir.join(key);
+        if entry.join(CODEX_BINARY).is_file() {
+            return Ok(entry);
+        }
+
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create cache dir {}", self.dir.display()))?;
+        let staging = self.dir.join(format!(".{key}.tmp-{}", std::process::id()));
+        std::fs::remove_dir_all(&staging).ok();
+        std::fs::create_dir_all(&staging)?;
+
+        let staged = std::fs::copy(binary_path, staging.join(CODEX_BINARY))
+            .with_context(|| format!("Failed to copy {} into cache", binary_path.display()))
+            .and_then(|_| {
+                std::fs::write(staging.join("manifest.json"), manifest.to_json()?)
+                    .context("Failed to write cached manifest")
+            });
+        if let Err(e) = staged {
+            std::fs::remove_dir_all(&staging).ok();
+            return Err(e);
+        }
+
+        if std::fs::rename(&staging, &entry).is_err() {
+            // Lost the race to a concurrent writer (or the entry appeared meanwhile).
+            std::fs::remove_dir_all(&staging).ok();
+            if !entry.join(CODEX_BINARY).is_file() {
+                anyhow::bail!("Failed to publish cache entry {}", entry.display());
+            }
+        }
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::OptimizationFlags;
+
+    fn options(cpu: Option<&str>) -> BuildOptions {
+        BuildOptions {
+            profile: "xtreme".to_string(),
+            cpu_target: cpu.map(String::from),
+            optimization: OptimizationFlags {
+                use_mold: false,
+                use_bolt: true,
+            },
+            strip_symbols: true,
+            cargo_jobs: Some(4),
+            offline: false,
+        }
+    }
+
+    #[test]
+    fn key_changes_with_inputs_but_not_job_count() {
+        let base = cache_key("abc", &[], &[], &options(None), "rustc 1.85", "znver4");
+        let mut fewer_jobs = options(None);
+        fewer_jobs.cargo_jobs = Some(1);
+        assert_eq!(
+            base,
+            cache_key("abc", &[], &[], &fewer_jobs, "rustc 1.85", "znver4")
+        );
+        assert_ne!(
+            base,
+            cache_key("abd", &[], &[], &options(None), "rustc 1.85", "znver4")
+        );
+        assert_ne!(
+            base,
+            cache_key(
+                "abc",
+                &["1234567".into()],
+                &[],
+                &options(None),
+                "rustc 1.85",
+                "znver4"
+            )
+        );
+        assert_ne!(
+            base,
+            cache_key("abc", &[], &[], &options(None), "rustc 1.86", "znver4")
+        );
+    }
+
+    #[test]
+    fn key_changes_with_the_workspace_toolchain() {
+        let stable = "rustc 1.85.0 (4d91de4e4 2025-02-17)\nhost: x86_64-unknown-linux-gnu";
+        let pinned = "rustc 1.83.0 (90b35a623 2024-11-26)\nhost: x86_64-unknown-linux-gnu";
+        assert_ne!(
+            cache_key("abc", &[], &[], &options(None), stable, "znver4"),
+            cache_key("abc", &[], &[], &options(None), pinned, "znver4")
+        );
+    }
+
+    #[test]
+    fn key_changes_when_a_patch_id_is_disabled() {
+        let mut patch = PatchFileRecord {
+            name: "privacy".to_string(),
+            path: PathBuf::from("/p/privacy.toml"),
+            sha256: "00".repeat(32),
+            disabled: Vec::new(),
+            results: Vec::new(),
+        };
+        let all = cache_key("abc", &[], &[patch.clone()], &options(None), "rustc", "x");
+        patch.disabled.push("statsig-endpoint".to_string());
+        assert_ne!(
+            all,
+            cache_key("abc", &[], &[patch], &options(None), "rustc", "x")
+        );
+    }
+
+    #[test]
+    fn native_cpu_key_depends_on_host() {
+        let native = options(Some("native"));
+        assert_ne!(
+            cache_key("abc", &[], &[], &native, "rustc", "znver4"),
+            cache_key("abc", &[], &[], &native, "rustc", "skylake")
+        );
+        let pinned = options(Some("znver4"));
+        assert_eq!(
+            cache_key("abc", &[], &[], &pinned, "rustc", "znver4"),
+            cache_key("abc", &[], &[], &pinned, "rustc", "skylake")
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Put the user's clone back to the checked-out tag after an in-place build.
+//!
+//! An in-place build leaves the clone on a detached tag with uncommitted cherry-picks,
+//! patched sources, compiler auto-fixes and an injected `[profile.xtreme]`. While it runs,
+//! a journal in the git dir (`.git/codex-xtreme-build.json`) records the tag, where the
+//! clone was before, and every file the pipeline touched, updated after each step. Once the
+//! binary is installed, [`BuildJournal::finish`] restores those files and deletes the
+//! journal.
+//!
+//! A journal that is still there at startup means a run was interrupted (or finished with
+//! cleanup turned off). [`detect_leftovers`] reports it, along with a half-done cherry-pick
+//! or an injected profile left behind without one, and [`clean_leftovers`] undoes them.
+
+use crate::core::RestorePoint;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const JOURNAL_FILE: &str = "codex-xtreme-build.json";
+
+fn resolve_command_path(name: &str) -> Result<PathBuf> {
+    which::which(name).map_err(|_| anyhow::anyhow!("Required command not found in PATH: {name}"))
+}
+
+/// What an in-place build has done to the clone so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildJournal {
+    /// Tag checked out for the build; restored files go back to it.
+    pub tag: String,
+    /// Branch (or commit) the clone was on before the build.
+    pub previous_ref: String,
+    /// Stash entry holding the user's uncommitted changes, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stash: Option<String>,
+    /// Last step that started: `checkout`, `cherry-pick`, `patches`, `profile`, `build`,
+    /// `install` or `done`.
+    pub step: String,
+    /// Repo-relative paths the pipeline modified or created.
+    pub touched: Vec<String>,
+}
+
+/// Result of restoring touched files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanupOutcome {
+    /// Tracked files checked out from the tag again.
+    pub restored: usize,
+    /// Files the build created, deleted.
+    pub removed: usize,
+}
+
+impl CleanupOutcome {
+    /// One-line summary for logs.
+    pub fn summary(&self, tag: &str) -> String {
+        match (self.restored, self.removed) {
+            (0, 0) => "Workspace already clean".to_string(),
+            (restored, 0) => format!("Restored {} file(s) to {}", restored, tag),
+            (restored, removed) => format!(
+                "Restored {} file(s) to {}, removed {} created by the build",
+                restored, tag, removed
+            ),
+        }
+    }
+}
+
+fn git_output(repo: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new(resolve_command_path("git")?)
+        .current_dir(repo)
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Path of the journal inside the repo's git dir.
+fn journal_path(repo: &Path) -> Option<PathBuf> {
+    let path = git_output(repo, &["rev-parse", "--git-path", JOURNAL_FILE]).ok()?;
+    Some(repo.join(String::from_utf8_lossy(&path).trim()))
+}
+
+/// Modified, staged, deleted and untracked paths (not ignored ones such as `target/`).
+pub fn changed_paths(repo: &Path) -> Result<Vec<String>> {
+    let stdout = git_output(
+        repo,
+        &["status", "--porcelain", "-z", "--untracked-files=all"],
+    )?;
+    let mut paths = Vec::new();
+    let mut entries = stdout.split(|b| *b == 0).filter(|e| !e.is_empty());
+    while let Some(entry) = entries.next() {
+        let entry = String::from_utf8_lossy(entry);
+        let Some(path) = entry.get(3..) else {
+            continue;
+        };
+        paths.push(path.to_string());
+        // Renames and copies are followed by their source path.
+        if entry.starts_with('R') || entry.starts_with('C') {
+            if let Some(source) = entries.next() {
+                paths.push(String::from_utf8_lossy(source).into_owned());
+            }
+        }
+    }
+    Ok(paths)
+}
+
+fn cherry_pick_in_progress(repo: &Path) -> bool {
+    git_output(repo, &["rev-parse", "-q", "--verify", "CHERRY_PICK_HEAD"]).is_ok()
+}
+
+/// Check `paths` out from HEAD again; delete the ones HEAD does not have.
+pub fn restore_paths(repo: &Path, paths: &[String]) -> Result<CleanupOutcome> {
+    let mut outcome = CleanupOutcome::default();
+    if paths.is_empty() {
+        return Ok(outcome);
+    }
+    if cherry_pick_in_progress(repo) {
+        // Keep the index/worktree; the files are restored below.
+        git_output(repo, &["cherry-pick", "--quit"])?;
+    }
+
+    let mut ls_tree = vec!["ls-tree", "-r", "-z", "--name-only", "HEAD", "--"];
+    ls_tree.extend(paths.iter().map(String::as_str));
+    let in_head = git_output(repo, &ls_tree)?;
+    let tracked: Vec<String> = in_head
+        .split(|b| *b == 0)
+        .filter(|p| !p.is_empty())
+        .map(|p| String::from_utf8_lossy(p).into_owned())
+        .collect();
+
+    // Unstage everything first so created files drop out of the index too.
+    let mut reset = vec!["reset", "-q", "--"];
+    reset.extend(paths.iter().map(String::as_str));
+    git_output(repo, &reset)?;
+
+    if !tracked.is_empty() {
+        let mut checkout = vec!["checkout", "HEAD", "--"];
+        checkout.extend(tracked.iter().map(String::as_str));
+        git_output(repo, &checkout)?;
+        outcome.restored = tracked.len();
+    }
+    for path in paths.iter().filter(|p| !tracked.contains(p)) {
+        let full = repo.join(path);
+        if full.is_file() || full.is_symlink() {
+            std::fs::remove_file(&full)
+                .with_context(|| format!("Failed to remove {}", full.display()))?;
+            outcome.removed += 1;
+        }
+    }
+    Ok(outcome)
+}
+
+impl BuildJournal {
+    /// Start a journal right after the tag was checked out in place.
+    pub fn begin(repo: &Path, tag: &str, point: &RestorePoint) -> Result<Self> {
+        let journal = Self {
+            tag: tag.to_string(),
+            previous_ref: point.previous_ref.clone(),
+            stash: point.stash.clone(),
+            step: "checkout".to_string(),
+            touched: Vec::new(),
+        };
+        journal.save(repo)?;
+        Ok(journal)
+    }
+
+    pub fn load(repo: &Path) -> Option<Self> {
+        let contents = std::fs::read_to_string(journal_path(repo)?).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    fn save(&self, repo: &Path) -> Result<()> {
+        let path = journal_path(repo).context("Not a git repository")?;
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn discard(repo: &Path) {
+        if let Some(path) = journal_path(repo) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// Add whatever the pipeline changed so far and mark `step` as started.
+    pub fn record(&mut self, repo: &Path, step: &str) -> Result<()> {
+        for path in changed_paths(repo)? {
+            if !self.touched.contains(&path) {
+                self.touched.push(path);
+            }
+        }
+        self.step = step.to_string();
+        self.save(repo)
+    }
+
+    /// Where the clone was before the build, for `core::restore_checkout`.
+    pub fn restore_point(&self) -> RestorePoint {
+        RestorePoint {
+            previous_ref: self.previous_ref.clone(),
+            stash: self.stash.clone(),
+        }
+    }
+
+    /// After a build: restore everything it touched and delete the journal.
+    ///
+    /// The tree was clean when the tag was checked out (dirty clones are stashed first), so
+    /// anything changed since then belongs to the build, including steps that ran after the
+    /// last [`record`](Self::record).
+    pub fn finish(mut self, repo: &Path) -> Result<CleanupOutcome> {
+        self.record(repo, "done")?;
+        let outcome = restore_paths(repo, &self.touched)?;
+        Self::discard(repo);
+        Ok(outcome)
+    }
+}
+
+/// State an earlier build left in a clone.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Leftovers {
+    pub journal: Option<BuildJournal>,
+    /// `CHERRY_PICK_HEAD` exists (a pick stopped on a conflict).
+    pub cherry_pick_in_progress: bool,
+    /// `codex-rs/Cargo.toml` carries an uncommitted injected `[profile.xtreme]`.
+    pub profile_injected: bool,
+    /// Journalled files that still differ from the tag.
+    pub files: Vec<String>,
+}
+
+impl Leftovers {
+    /// Human-readable description (first line is a summary).
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![match &self.journal {
+            Some(j) if j.step == "done" => format!(
+                "A build of {} left its changes in place (cleanup was off)",
+                j.tag
+            ),
+            Some(j) => format!(
+                "A build of {} was interrupted during the {} step",
+                j.tag, j.step
+            ),
+            None => "An earlier build left changes behind".to_string(),
+        }];
+        if self.cherry_pick_in_progress {
+            lines.push("half-done cherry-pick (CHERRY_PICK_HEAD)".to_string());
+        }
+        if self.profile_injected {
+            lines.push(format!(
+                "injected [profile.xtreme] in {}/Cargo.toml",
+                crate::core::CODEX_RS_SUBDIR
+            ));
+        }
+        lines.extend(self.files.iter().cloned());
+        lines
+    }
+}
+
+/// Look for an interrupted build's state in `repo`.
+pub fn detect_leftovers(repo: &Path) -> Option<Leftovers> {
+    let journal = BuildJournal::load(repo);
+    let cargo_toml = format!("{}/Cargo.toml", crate::core::CODEX_RS_SUBDIR);
+    let changed = changed_paths(repo).unwrap_or_default();
+    let profile_injected = changed.contains(&cargo_toml)
+        && std::fs::read_to_string(repo.join(&cargo_toml))
+            .is_ok_and(|c| c.contains(crate::workflow::XTREME_PROFILE_MARKER));
+    let files: Vec<String> = journal
+        .iter()
+        .flat_map(|j| &j.touched)
+        .filter(|path| changed.contains(path))
+        .cloned()
+        .collect();
+    let leftovers = Leftovers {
+        cherry_pick_in_progress: cherry_pick_in_progress(repo),
+        profile_injected,
+        files,
+        journal,
+    };
+    let found = leftovers.journal.is_some()
+        || leftovers.cherry_pick_in_progress
+        || leftovers.profile_injected;
+    found.then_some(leftovers)
+}
+
+/// Undo [`Leftovers`]: restore journalled files, or without a journal abort the cherry-pick
+/// and strip the injected profile. Deletes the journal.
+///
+/// Only the journalled paths are touched: anything else that changed since (including edits
+/// made on top of a build that kept its changes) is the user's. Ask before calling this.
+pub fn clean_leftovers(repo: &Path, leftovers: &Leftovers) -> Result<CleanupOutcome> {
+    let mut outcome = CleanupOutcome::default();
+    if leftovers.journal.is_some() {
+        outcome = restore_paths(repo, &leftovers.files)?;
+    } else if leftovers.cherry_pick_in_progress {
+        git_output(repo, &["cherry-pick", "--abort"])?;
+    }
+    if cherry_pick_in_progress(repo) {
+        git_output(repo, &["cherry-pick", "--quit"])?;
+    }
+    let workspace = repo.join(crate::core::CODEX_RS_SUBDIR);
+    if crate::workflow::remove_xtreme_profile(&workspace)? {
+        outcome.restored += 1;
+    }
+    BuildJournal::discard(repo);
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestRepo;
+
+    #[test]
+    fn journal_restores_touched_files_and_detects_interrupted_builds() {
+        let repo = TestRepo::init("cleanup", "main");
+        let workspace = repo.join(crate::core::CODEX_RS_SUBDIR);
+        std::fs::create_dir_all(workspace.join("src")).unwrap();
+        std::fs::write(workspace.join("Cargo.toml"), "[workspace]\n").unwrap();
+        std::fs::write(workspace.join("src/lib.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(workspace.join("src/main.rs"), "fn main() {}\n").unwrap();
+        let git = |args: &[&str]| repo.git(args);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "init"]);
+        git(&["tag", "v1"]);
+        assert!(detect_leftovers(&repo).is_none());
+
+        let point = RestorePoint {
+            previous_ref: "main".to_string(),
+            stash: None,
+        };
+        let mut journal = BuildJournal::begin(&repo, "v1", &point).unwrap();
+        std::fs::write(workspace.join("src/lib.rs"), "fn patched() {}\n").unwrap();
+        std::fs::write(workspace.join("src/new.rs"), "// created\n").unwrap();
+        journal.record(&repo, "patches").unwrap();
+        crate::workflow::inject_xtreme_profile(&workspace).unwrap();
+        std::fs::write(workspace.join("src/main.rs"), "fn main() { fixed() }\n").unwrap();
+
+        // Interrupted here: the journal has the patch step, not the profile or the later
+        // edit, which is left alone (the profile is stripped separately).
+        let leftovers = detect_leftovers(&repo).unwrap();
+        assert!(leftovers.profile_injected);
+        assert_eq!(leftovers.journal.as_ref().unwrap().step, "patches");
+        assert_eq!(leftovers.files.len(), 2);
+        assert!(leftovers.describe()[0].contains("interrupted during the patches step"));
+
+        let outcome = clean_leftovers(&repo, &leftovers).unwrap();
+        assert_eq!((outcome.restored, outcome.removed), (2, 1));
+        assert_eq!(changed_paths(&repo).unwrap(), ["codex-rs/src/main.rs"]);
+        assert!(detect_leftovers(&repo).is_none());
+        git(&["checkout", "--", "."]);
+
+        // A build that kept its changes: user edits made on top of it survive the cleanup.
+        let mut journal = BuildJournal::begin(&repo, "v1", &point).unwrap();
+        std::fs::write(workspace.join("src/lib.rs"), "fn patched() {}\n").unwrap();
+        journal.record(&repo, "done").unwrap();
+        std::fs::write(workspace.join("src/main.rs"), "fn main() { mine() }\n").unwrap();
+        let leftovers = detect_leftovers(&repo).unwrap();
+        assert!(leftovers.describe()[0].contains("left its changes in place"));
+        assert_eq!(leftovers.files, ["codex-rs/src/lib.rs"]);
+        clean_leftovers(&repo, &leftovers).unwrap();
+        assert_eq!(changed_paths(&repo).unwrap(), ["codex-rs/src/main.rs"]);
+        git(&["checkout", "--", "."]);
+
+        // A finished build restores everything, including steps after the last record.
+        let journal = BuildJournal::begin(&repo, "v1", &point).unwrap();
+        crate::workflow::inject_xtreme_profile(&workspace).unwrap();
+        std::fs::write(workspace.join("Cargo.lock"), "# lock\n").unwrap();
+        let outcome = journal.finish(&repo).unwrap();
+        assert_eq!((outcome.restored, outcome.removed), (1, 1));
+        assert!(changed_paths(&repo).unwrap().is_empty());
+        assert!(BuildJournal::load(&repo).is_none());
+    }
+}
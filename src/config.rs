@@ -0,0 +1,346 @@
+//! Persistent user configuration (`~/.config/codex-xtreme/config.toml`).
+//!
+//! Holds the defaults both frontends pre-fill their prompts with, plus what was chosen for
+//! the last successful build. The file is rewritten after every successful build, so the
+//! defaults follow the user's most recent choices.
+//!
+//! ```toml
+//! repo = "~/dev/codex"                 # preferred repo: always listed, selected by default
+//! patch_dirs = ["~/my-patches"]        # after CODEX_PATCHER_PATCHES, before project/built-in dirs
+//!
+//! [defaults]
+//! profile = "xtreme"
+//! optimize_for_cpu = true
+//! optimization = "run_fast"            # build_fast | run_fast | custom (unset: pick by tools)
+//! use_mold = true                      # custom mode only
+//! use_bolt = true                      # custom mode only
+//! strip_symbols = true
+//! run_tests = true
+//! setup_alias = true
+//! cleanup = true                       # restore the clone's files after in-place builds
+//! cargo_jobs = 8
+//!
+//! [last]
+//! repo = "/home/me/dev/codex"
+//! tag = "rust-v0.105.0"
+//! patches = ["/home/me/.config/codex-patcher/patches/privacy.toml"]
+//!
+//! [last.disabled_patches]              # patch ids switched off inside a patch file
+//! "/home/me/.config/codex-patcher/patches/privacy.toml" = ["statsig-endpoint"]
+//! ```
+//!
+//! `CODEX_XTREME_CONFIG` overrides the file location.
+
+use crate::workflow::{BuildOptions, DisabledPatches, OptimizationMode};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Repo that is always offered (and selected unless another one was used last).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo: Option<PathBuf>,
+    /// Extra patch directories, searched after `CODEX_PATCHER_PATCHES` and before the
+    /// project-local and built-in ones.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub patch_dirs: Vec<PathBuf>,
+    pub defaults: BuildDefaults,
+    pub last: LastUsed,
+}
+
+/// Defaults for the build configuration prompts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildDefaults {
+    pub profile: String,
+    pub optimize_for_cpu: bool,
+    /// Unset: BOLT if installed, else mold if installed, else custom.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimization: Option<OptimizationMode>,
+    pub use_mold: bool,
+    pub use_bolt: bool,
+    pub strip_symbols: bool,
+    pub run_tests: bool,
+    pub setup_alias: bool,
+    /// Restore the files an in-place build touched once the binary is installed.
+    pub cleanup: bool,
+    /// `--jobs` on the command line wins over this. Only set by hand, never remembered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cargo_jobs: Option<usize>,
+}
+
+impl Default for BuildDefaults {
+    fn default() -> Self {
+        Self {
+            profile: "xtreme".to_string(),
+            optimize_for_cpu: true,
+            optimization: None,
+            use_mold: true,
+            use_bolt: true,
+            strip_symbols: true,
+            run_tests: true,
+            setup_alias: true,
+            cleanup: true,
+            cargo_jobs: None,
+        }
+    }
+}
+
+impl BuildDefaults {
+    /// The configured optimization mode, if the tools it needs are installed.
+    pub fn optimization_mode(&self, has_mold: bool, has_bolt: bool) -> OptimizationMode {
+        match self.optimization {
+            Some(OptimizationMode::RunFast) if has_bolt => OptimizationMode::RunFast,
+            Some(OptimizationMode::BuildFast) if has_mold => OptimizationMode::BuildFast,
+            Some(OptimizationMode::Custom) => OptimizationMode::Custom,
+            _ if has_bolt => OptimizationMode::RunFast,
+            _ if has_mold => OptimizationMode::BuildFast,
+            _ => OptimizationMode::Custom,
+        }
+    }
+}
+
+/// Choices from the last successful build.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastUsed {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    pub patches: Vec<PathBuf>,
+    /// Patch ids switched off per patch file; kept for files not selected last time too.
+    #[serde(skip_serializing_if = "DisabledPatches::is_empty")]
+    pub disabled_patches: DisabledPatches,
+}
+
+/// What a successful build used, for `Config::record_build`.
+pub struct BuildChoices<'a> {
+    pub repo: &'a Path,
+    pub tag: &'a str,
+    pub patches: &'a [PathBuf],
+    /// Patch ids left out of `patches`, keyed by patch file.
+    pub disabled_patches: &'a DisabledPatches,
+    pub options: &'a BuildOptions,
+    pub optimization: OptimizationMode,
+    pub run_tests: bool,
+    pub setup_alias: bool,
+    pub cleanup: bool,
+}
+
+fn expand(path: &Path) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(&path.to_string_lossy()).as_ref())
+}
+
+impl Config {
+    /// `$CODEX_XTREME_CONFIG`, or `config.toml` in the user config dir.
+    pub fn path() -> Option<PathBuf> {
+        match std::env::var_os("CODEX_XTREME_CONFIG") {
+            Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+            _ => dirs::config_dir().map(|d| d.join("codex-xtreme").join("config.toml")),
+        }
+    }
+
+    /// Load the user config; a missing file yields the defaults.
+    pub fn load() -> Result<Self> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config: {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config: {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = Self::path().context("Could not determine the user config directory")?;
+        self.save_to(&path)?;
+        Ok(path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let contents = toml::to_string_pretty(self).context("Failed to serialize config")?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write config: {}", path.display()))
+    }
+
+    /// Preferred repo with `~` expanded.
+    pub fn preferred_repo(&self) -> Option<PathBuf> {
+        self.repo.as_deref().map(expand)
+    }
+
+    /// Repo to pre-select: the last one used, else the preferred one.
+    pub fn initial_repo(&self) -> Option<PathBuf> {
+        self.last
+            .repo
+            .as_deref()
+            .map(expand)
+            .or_else(|| self.preferred_repo())
+    }
+
+    /// Configured patch directories that exist, with `~` expanded.
+    pub fn patch_dirs(&self) -> Vec<PathBuf> {
+        self.patch_dirs
+            .iter()
+            .map(|dir| expand(dir))
+            .filter(|dir| dir.is_dir())
+            .collect()
+    }
+
+    /// Cargo jobs: the command-line value, else the configured default.
+    pub fn cargo_jobs(&self, cli: Option<usize>) -> Option<usize> {
+        cli.or(self.defaults.cargo_jobs)
+    }
+
+    /// Remember a successful build as the new defaults and last-used values.
+    pub fn record_build(&mut self, choices: &BuildChoices) {
+        let defaults = &mut self.defaults;
+        defaults.profile = choices.options.profile.clone();
+        defaults.optimize_for_cpu = choices.options.cpu_target.is_some();
+        defaults.optimization = Some(choices.optimization);
+        if choices.optimization == OptimizationMode::Custom {
+            defaults.use_mold = choices.options.optimization.use_mold;
+            defaults.use_bolt = choices.options.optimization.use_bolt;
+        }
+        defaults.strip_symbols = choices.options.strip_symbols;
+        defaults.run_tests = choices.run_tests;
+        defaults.setup_alias = choices.setup_alias;
+        defaults.cleanup = choices.cleanup;
+
+        let mut disabled_patches = std::mem::take(&mut self.last.disabled_patches);
+        for patch in choices.patches {
+            match choices.disabled_patches.get(patch) {
+                Some(ids) if !ids.is_empty() => {
+                    disabled_patches.insert(patch.clone(), ids.clone());
+                }
+                _ => {
+                    disabled_patches.remove(patch);
+                }
+            }
+        }
+        self.last = LastUsed {
+            repo: Some(choices.repo.to_path_buf()),
+            tag: Some(choices.tag.to_string()),
+            patches: choices.patches.to_vec(),
+            disabled_patches,
+        };
+    }
+
+    /// Patch ids to leave out of `patch` by default: the ones switched off last time.
+    pub fn disabled_patch_ids(&self, patch: &Path) -> Vec<String> {
+        self.last
+            .disabled_patches
+            .get(patch)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Patches to pre-select: the last selection where still available, else `fallback`.
+    pub fn initial_patches(&self, available: &[PathBuf], fallback: Vec<PathBuf>) -> Vec<PathBuf> {
+        let remembered: Vec<PathBuf> = available
+            .iter()
+            .filter(|p| self.last.patches.contains(p))
+            .cloned()
+            .collect();
+        if remembered.is_empty() {
+            fallback
+        } else {
+            remembered
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::OptimizationFlags;
+
+    #[test]
+    fn record_build_round_trips_through_toml() {
+        let path = std::env::temp_dir().join(format!(
+            "codex-xtreme-config-test-{}/config.toml",
+            std::process::id()
+        ));
+        assert_eq!(Config::load_from(&path).unwrap(), Config::default());
+
+        let mut config = Config {
+            patch_dirs: vec![PathBuf::from("~/patches")],
+            ..Default::default()
+        };
+        config.defaults.cargo_jobs = Some(4);
+        config.last.disabled_patches.insert(
+            PathBuf::from("/p/other.toml"),
+            vec!["kept-while-unselected".to_string()],
+        );
+        let disabled = DisabledPatches::from([(
+            PathBuf::from("/p/privacy.toml"),
+            vec!["statsig-endpoint".to_string()],
+        )]);
+        let options = BuildOptions {
+            profile: "release".to_string(),
+            cpu_target: None,
+            optimization: OptimizationFlags {
+                use_mold: true,
+                use_bolt: false,
+            },
+            strip_symbols: false,
+            cargo_jobs: Some(16),
+            offline: false,
+        };
+        config.record_build(&BuildChoices {
+            repo: Path::new("/src/codex"),
+            tag: "rust-v0.105.0",
+            patches: &[PathBuf::from("/p/privacy.toml")],
+            disabled_patches: &disabled,
+            options: &options,
+            optimization: OptimizationMode::Custom,
+            run_tests: false,
+            setup_alias: true,
+            cleanup: false,
+        });
+        config.save_to(&path).unwrap();
+
+        let loaded = Config::load_from(&path).unwrap();
+        assert_eq!(loaded, config);
+        assert_eq!(loaded.defaults.profile, "release");
+        assert!(!loaded.defaults.optimize_for_cpu);
+        assert!(!loaded.defaults.use_bolt);
+        assert!(!loaded.defaults.cleanup);
+        assert_eq!(
+            loaded.disabled_patch_ids(Path::new("/p/privacy.toml")),
+            vec!["statsig-endpoint".to_string()]
+        );
+        assert_eq!(
+            loaded.disabled_patch_ids(Path::new("/p/other.toml")),
+            vec!["kept-while-unselected".to_string()]
+        );
+        assert_eq!(loaded.cargo_jobs(None), Some(4));
+        assert_eq!(loaded.cargo_jobs(Some(2)), Some(2));
+        assert_eq!(loaded.initial_repo(), Some(PathBuf::from("/src/codex")));
+        assert_eq!(
+            loaded.initial_patches(
+                &[
+                    PathBuf::from("/p/other.toml"),
+                    PathBuf::from("/p/privacy.toml")
+                ],
+                Vec::new()
+            ),
+            vec![PathBuf::from("/p/privacy.toml")]
+        );
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
@@ -2,13 +2,17 @@
 //!
 //! Shared functions used by both the cliclack UI and ratatui TUI.
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use codex_patcher::{load_from_path, matches_requirement, PatchConfig};
+use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
 use std::fmt;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
 
 pub use crate::cpu_detect::{detect_cpu_target, CpuTarget};
 
@@ -47,6 +51,120 @@ pub struct Release {
     pub published: String,
 }
 
+/// Conventional-commit type of a changelog entry, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChangeKind {
+    Breaking,
+    Feature,
+    Fix,
+    Performance,
+    Refactor,
+    Docs,
+    Tests,
+    Build,
+    Chore,
+    Other,
+}
+
+impl ChangeKind {
+    /// Map a conventional-commit type (`feat`, `fix`, ...) to a kind.
+    pub fn from_type(kind: &str) -> Option<Self> {
+        match kind.to_ascii_lowercase().as_str() {
+            "feat" | "feature" => Some(ChangeKind::Feature),
+            "fix" | "bugfix" | "hotfix" => Some(ChangeKind::Fix),
+            "perf" => Some(ChangeKind::Performance),
+            "refactor" => Some(ChangeKind::Refactor),
+            "docs" | "doc" => Some(ChangeKind::Docs),
+            "test" | "tests" => Some(ChangeKind::Tests),
+            "build" | "ci" | "deps" => Some(ChangeKind::Build),
+            "chore" | "style" | "revert" => Some(ChangeKind::Chore),
+            _ => None,
+        }
+    }
+
+    pub fn heading(self) -> &'static str {
+        match self {
+            ChangeKind::Breaking => "Breaking changes",
+            ChangeKind::Feature => "Features",
+            ChangeKind::Fix => "Fixes",
+            ChangeKind::Performance => "Performance",
+            ChangeKind::Refactor => "Refactoring",
+            ChangeKind::Docs => "Documentation",
+            ChangeKind::Tests => "Tests",
+            ChangeKind::Build => "Build & CI",
+            ChangeKind::Chore => "Chores",
+            ChangeKind::Other => "Other changes",
+        }
+    }
+
+    /// Short label for one-line summaries (`3 feat, 5 fix`).
+    pub fn short(self) -> &'static str {
+        match self {
+            ChangeKind::Breaking => "breaking",
+            ChangeKind::Feature => "feat",
+            ChangeKind::Fix => "fix",
+            ChangeKind::Performance => "perf",
+            ChangeKind::Refactor => "refactor",
+            ChangeKind::Docs => "docs",
+            ChangeKind::Tests => "test",
+            ChangeKind::Build => "build",
+            ChangeKind::Chore => "chore",
+            ChangeKind::Other => "other",
+        }
+    }
+}
+
+/// One commit in a release changelog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogEntry {
+    pub kind: ChangeKind,
+    pub scope: Option<String>,
+    /// Subject without the `type(scope):` prefix
+    pub summary: String,
+}
+
+impl ChangelogEntry {
+    /// Parse a commit subject, recognising `type(scope)!: summary`.
+    pub fn parse(subject: &str) -> Self {
+        let subject = subject.trim();
+        if let Some((prefix, summary)) = subject.split_once(": ") {
+            let breaking = prefix.ends_with('!');
+            let prefix = prefix.trim_end_matches('!');
+            let (kind, scope) = match prefix.split_once('(') {
+                Some((kind, scope)) if scope.ends_with(')') => {
+                    (kind, Some(scope.trim_end_matches(')').to_string()))
+                }
+                _ => (prefix, None),
+            };
+            if let Some(kind) = ChangeKind::from_type(kind) {
+                return Self {
+                    kind: if breaking { ChangeKind::Breaking } else { kind },
+                    scope,
+                    summary: summary.trim().to_string(),
+                };
+            }
+        }
+        Self {
+            kind: ChangeKind::Other,
+            scope: None,
+            summary: subject.to_string(),
+        }
+    }
+}
+
+/// A commit offered for cherry-picking in dev mode.
+#[derive(Debug, Clone)]
+pub struct CommitInfo {
+    pub sha: String,
+    pub short_sha: String,
+    pub subject: String,
+    pub author: String,
+    /// Author date, `YYYY-MM-DD`
+    pub date: String,
+    /// Paths touched by the commit
+    pub files: Vec<String>,
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // SYSTEM DETECTION
 // ═══════════════════════════════════════════════════════════════════════════
@@ -107,39 +225,216 @@ pub fn rust_version() -> String {
 // REPOSITORY MANAGEMENT
 // ═══════════════════════════════════════════════════════════════════════════
 
-/// Find existing Codex repositories
+/// Where to look for Codex checkouts.
+///
+/// Defaults to the usual source directories under `~`, plus `~/codex` itself (scanning all
+/// of `~` is too slow); override with
+/// `CODEX_XTREME_REPO_ROOTS` (a `:`-separated path list) and `CODEX_XTREME_REPO_DEPTH`.
+#[derive(Debug, Clone)]
+pub struct RepoDiscovery {
+    pub roots: Vec<PathBuf>,
+    /// How many directory levels below each root to search (0 = the root itself)
+    pub max_depth: usize,
+}
+
+impl Default for RepoDiscovery {
+    fn default() -> Self {
+        let roots = [
+            "~/codex",
+            "~/dev",
+            "~/src",
+            "~/code",
+            "~/projects",
+            "~/.local/src",
+        ]
+        .iter()
+        .map(|root| PathBuf::from(shellexpand::tilde(root).as_ref()))
+        .collect();
+        Self {
+            roots,
+            max_depth: 2,
+        }
+    }
+}
+
+impl RepoDiscovery {
+    pub fn from_env() -> Self {
+        let mut discovery = Self::default();
+        if let Some(roots) = std::env::var_os("CODEX_XTREME_REPO_ROOTS") {
+            discovery.roots = std::env::split_paths(&roots)
+                .filter(|p| !p.as_os_str().is_empty())
+                .map(|p| PathBuf::from(shellexpand::tilde(&p.to_string_lossy()).as_ref()))
+                .collect();
+        }
+        if let Some(depth) = std::env::var("CODEX_XTREME_REPO_DEPTH")
+            .ok()
+            .and_then(|d| d.trim().parse().ok())
+        {
+            discovery.max_depth = depth;
+        }
+        discovery
+    }
+}
+
+/// Find existing Codex repositories (and their git worktrees) under the configured roots
 pub fn find_codex_repos() -> Result<Vec<RepoInfo>> {
-    let candidates = [
-        "~/dev/codex",
-        "~/dev/cod3x",
-        "~/codex",
-        "~/src/codex",
-        "~/.local/src/codex",
-        "~/projects/codex",
-        "~/code/codex",
-    ];
+    find_codex_repos_in(&RepoDiscovery::from_env())
+}
 
-    let mut repos = Vec::new();
+pub fn find_codex_repos_in(discovery: &RepoDiscovery) -> Result<Vec<RepoInfo>> {
+    let mut candidates = Vec::new();
+    for root in &discovery.roots {
+        scan_for_workspaces(root, discovery.max_depth, &mut candidates);
+    }
 
-    for candidate in &candidates {
-        let expanded = shellexpand::tilde(candidate);
-        let path = PathBuf::from(expanded.as_ref());
-
-        let workspace_cargo = path.join(CODEX_RS_SUBDIR).join("Cargo.toml");
-        if workspace_cargo.exists() {
-            if let Ok(contents) = std::fs::read_to_string(&workspace_cargo) {
-                if contents.contains("codex-cli") || contents.contains("codex-common") {
-                    let branch = get_current_branch(&path).unwrap_or_else(|_| "unknown".into());
-                    let age = get_repo_age(&path);
-                    repos.push(RepoInfo { path, age, branch });
-                }
+    // Every checkout of a repo shares its worktree list, so each one finds the others.
+    // Build worktrees (on an `xtreme/*` branch or under `worktrees_dir()`) are not offered.
+    let build_prefix = worktree_branch("");
+    let mut build_dirs: Vec<PathBuf> = worktrees_dir()
+        .map(|dir| vec![dir.canonicalize().unwrap_or(dir)])
+        .unwrap_or_default();
+    let mut expanded = Vec::new();
+    for candidate in candidates {
+        for (path, branch) in list_worktrees(&candidate) {
+            if branch.is_some_and(|b| b.starts_with(&build_prefix)) {
+                build_dirs.push(path.canonicalize().unwrap_or(path));
+            } else {
+                expanded.push(path);
             }
         }
+        expanded.push(candidate);
     }
 
+    let mut seen = std::collections::HashSet::new();
+    let mut repos = Vec::new();
+    for path in expanded {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if build_dirs.iter().any(|dir| canonical.starts_with(dir))
+            || !seen.insert(canonical.clone())
+            || !canonical.join(CODEX_RS_SUBDIR).join("Cargo.toml").is_file()
+            || !is_codex_workspace(&canonical)
+        {
+            continue;
+        }
+        repos.push(repo_info(canonical));
+    }
+    repos.sort_by(|a, b| a.path.cmp(&b.path));
     Ok(repos)
 }
 
+/// Collect directories containing `codex-rs/Cargo.toml`, descending at most `depth` levels.
+/// Hidden directories, build output and symlinks are skipped; matches are not descended into.
+fn scan_for_workspaces(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    if dir.join(CODEX_RS_SUBDIR).join("Cargo.toml").is_file() {
+        out.push(dir.to_path_buf());
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut subdirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|e| {
+            let name = e.file_name();
+            let name = name.to_string_lossy();
+            !name.starts_with('.') && name != "node_modules" && name != "target"
+        })
+        .map(|e| e.path())
+        .collect();
+    subdirs.sort();
+    for sub in subdirs {
+        scan_for_workspaces(&sub, depth - 1, out);
+    }
+}
+
+/// The worktrees attached to the repo at `repo` (including the main one), each with its
+/// checked-out branch (`None` when HEAD is detached).
+fn list_worktrees(repo: &Path) -> Vec<(PathBuf, Option<String>)> {
+    let Ok(git) = resolve_command_path("git") else {
+        return Vec::new();
+    };
+    let output = Command::new(git)
+        .current_dir(repo)
+        .args(["worktree", "list", "--porcelain"])
+        .output();
+    match output {
+        // One block per worktree, separated by blank lines.
+        Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout)
+            .split("\n\n")
+            .filter_map(|block| {
+                let path = block.lines().find_map(|l| l.strip_prefix("worktree "))?;
+                let branch = block
+                    .lines()
+                    .find_map(|l| l.strip_prefix("branch refs/heads/"))
+                    .map(String::from);
+                Some((PathBuf::from(path), branch))
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether `repo/codex-rs` is a Cargo workspace containing the `codex-cli` package.
+///
+/// `cargo metadata` is slow, so answers are cached per path for the life of the process.
+fn is_codex_workspace(repo: &Path) -> bool {
+    static CACHE: std::sync::OnceLock<std::sync::Mutex<std::collections::HashMap<PathBuf, bool>>> =
+        std::sync::OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(known) = cache.lock().ok().and_then(|c| c.get(repo).copied()) {
+        return known;
+    }
+    let answer = workspace_has_codex_cli(repo).unwrap_or(false);
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(repo.to_path_buf(), answer);
+    }
+    answer
+}
+
+fn workspace_has_codex_cli(repo: &Path) -> Result<bool> {
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(repo.join(CODEX_RS_SUBDIR).join("Cargo.toml"))
+        .no_deps()
+        .exec()?;
+    Ok(metadata.packages.iter().any(|p| p.name == "codex-cli"))
+}
+
+fn repo_info(path: PathBuf) -> RepoInfo {
+    let branch = get_current_branch(&path).unwrap_or_else(|_| "unknown".into());
+    let age = get_repo_age(&path);
+    RepoInfo { path, age, branch }
+}
+
+/// Validate a user-entered repo path (`~` is expanded) and describe the repo.
+pub fn open_codex_repo(input: &str) -> Result<RepoInfo> {
+    let input = input.trim();
+    if input.is_empty() {
+        bail!("Enter a path");
+    }
+    let path = PathBuf::from(shellexpand::tilde(input).as_ref());
+    if !path.is_dir() {
+        bail!("{} is not a directory", path.display());
+    }
+    let manifest = path.join(CODEX_RS_SUBDIR).join("Cargo.toml");
+    if !manifest.is_file() {
+        bail!(
+            "{} not found; point at the repo root that contains {}/",
+            manifest.display(),
+            CODEX_RS_SUBDIR
+        );
+    }
+    let has_cli = workspace_has_codex_cli(&path)
+        .with_context(|| format!("Failed to read Cargo metadata for {}", manifest.display()))?;
+    if !has_cli {
+        bail!("{} has no codex-cli package", manifest.display());
+    }
+    Ok(repo_info(path.canonicalize().unwrap_or(path)))
+}
+
 fn get_current_branch(repo: &Path) -> Result<String> {
     let output = Command::new(resolve_command_path("git")?)
         .current_dir(repo)
@@ -158,25 +453,247 @@ fn get_repo_age(repo: &Path) -> String {
         .or_else(|_| git_dir.metadata().and_then(|m| m.modified()));
 
     match mtime {
-        Ok(time) => {
-            let duration = SystemTime::now().duration_since(time).unwrap_or_default();
-            let secs = duration.as_secs();
-            if secs < 60 {
-                format!("{}s ago", secs)
-            } else if secs < 3600 {
-                format!("{}m ago", secs / 60)
-            } else if secs < 86400 {
-                format!("{}h ago", secs / 3600)
-            } else {
-                format!("{}d ago", secs / 86400)
+        Ok(time) => format_age(time),
+        Err(_) => "unknown".into(),
+    }
+}
+
+/// "42s ago", "5m ago", "3h ago", "2d ago"
+pub fn format_age(time: SystemTime) -> String {
+    let secs = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or_default()
+        .as_secs();
+    if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86400)
+    }
+}
+
+/// How much history `clone_codex_with` fetches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloneHistory {
+    /// `--depth=N`
+    Shallow(u32),
+    /// All commits, blobs on demand (`--filter=blob:none`)
+    Blobless,
+    Full,
+}
+
+impl fmt::Display for CloneHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloneHistory::Shallow(depth) => write!(f, "shallow (last {} commits)", depth),
+            CloneHistory::Blobless => write!(f, "blobless (full history, blobs on demand)"),
+            CloneHistory::Full => write!(f, "full history"),
+        }
+    }
+}
+
+/// Where a clone comes from, as classified from `CloneOptions::source`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloneSource {
+    /// Remote URL (`https://`, `ssh://`, `git@host:path`)
+    Url(String),
+    /// Local repository or mirror
+    Local(PathBuf),
+    /// `git bundle` file
+    Bundle(PathBuf),
+}
+
+/// Options for cloning Codex: source (fork URL, local mirror or bundle), branch, history and
+/// sparse checkout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloneOptions {
+    /// URL, local path or `.bundle` file (`~` is expanded)
+    pub source: String,
+    /// Branch (or tag) to check out instead of the source's default
+    pub branch: Option<String>,
+    pub history: CloneHistory,
+    /// Only check out `codex-rs/` (`git sparse-checkout`)
+    pub sparse: bool,
+}
+
+impl Default for CloneOptions {
+    fn default() -> Self {
+        Self {
+            source: CODEX_REPO_URL.to_string(),
+            branch: None,
+            history: CloneHistory::Shallow(100),
+            sparse: false,
+        }
+    }
+}
+
+impl CloneOptions {
+    /// Classify and validate the source.
+    pub fn source_kind(&self) -> Result<CloneSource> {
+        let source = self.source.trim();
+        if source.is_empty() {
+            bail!("Enter a clone source (URL, local path or .bundle file)");
+        }
+        // `host:path` without a slash before the colon is scp-style ssh, like git treats it.
+        let scp_like = source
+            .split_once(':')
+            .is_some_and(|(host, _)| !host.is_empty() && !host.contains('/') && host.len() > 1);
+        if source.contains("://") || scp_like {
+            return Ok(CloneSource::Url(source.to_string()));
+        }
+
+        let path = PathBuf::from(shellexpand::tilde(source).as_ref());
+        if path.is_file() {
+            return Ok(CloneSource::Bundle(path));
+        }
+        if path.is_dir() {
+            if !path.join(".git").exists() && !path.join("HEAD").is_file() {
+                bail!("{} is not a git repository", path.display());
             }
+            return Ok(CloneSource::Local(path));
         }
-        Err(_) => "unknown".into(),
+        bail!("{} does not exist", path.display())
+    }
+
+    /// History actually used: bundles cannot be cloned shallow or partially.
+    pub fn effective_history(&self) -> CloneHistory {
+        match self.source_kind() {
+            Ok(CloneSource::Bundle(_)) => CloneHistory::Full,
+            _ => self.history,
+        }
+    }
+
+    /// Validate the options before cloning (the frontends call this to report errors early).
+    pub fn validate(&self) -> Result<CloneSource> {
+        let kind = self.source_kind()?;
+        if let CloneHistory::Shallow(0) = self.history {
+            bail!("Depth must be at least 1");
+        }
+        if let Some(branch) = &self.branch {
+            if branch.trim().is_empty() || branch.starts_with('-') || branch.contains(' ') {
+                bail!("Invalid branch name: {:?}", branch);
+            }
+        }
+        Ok(kind)
+    }
+
+    /// `git clone` arguments (everything before the destination).
+    fn clone_args(&self) -> Result<Vec<String>> {
+        let kind = self.validate()?;
+        let history = self.effective_history();
+        let mut args = vec!["clone".to_string()];
+        match history {
+            CloneHistory::Shallow(depth) => args.push(format!("--depth={}", depth)),
+            CloneHistory::Blobless => args.push("--filter=blob:none".to_string()),
+            CloneHistory::Full => {}
+        }
+        if let Some(branch) = &self.branch {
+            args.push(format!("--branch={}", branch.trim()));
+        }
+        if self.sparse {
+            args.push("--sparse".to_string());
+        }
+        args.push(match kind {
+            CloneSource::Url(url) => url,
+            // Local clones ignore --depth/--filter unless they go through the file:// transport.
+            CloneSource::Local(path) if history != CloneHistory::Full => {
+                format!("file://{}", path.canonicalize()?.display())
+            }
+            CloneSource::Local(path) | CloneSource::Bundle(path) => path.display().to_string(),
+        });
+        Ok(args)
     }
 }
 
 /// Clone the Codex repository to a destination
 pub fn clone_codex(dest: &Path) -> Result<RepoInfo> {
+    clone_codex_with(dest, &CloneOptions::default())
+}
+
+/// Clone Codex to `dest` with the given source, branch, history and sparse-checkout options.
+pub fn clone_codex_with(dest: &Path, options: &CloneOptions) -> Result<RepoInfo> {
+    clone_codex_with_progress(dest, options, &AtomicBool::new(false), |_| {})
+}
+
+/// Progress of a running clone, parsed from `git clone --progress`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloneProgress {
+    /// Git's phase, e.g. "Receiving objects"
+    pub phase: String,
+    /// Overall progress across phases, 0.0..=1.0
+    pub fraction: f64,
+    /// Object counts and transfer rate, e.g. "4500/10000, 12.00 MiB | 3.10 MiB/s"
+    pub detail: String,
+}
+
+/// Parse one line of `git clone --progress` output, e.g.
+/// `Receiving objects:  45% (4500/10000), 12.00 MiB | 3.10 MiB/s`.
+///
+/// Receiving objects fills the first 80% of [`CloneProgress::fraction`], resolving deltas
+/// the next 15% and checking out files the rest; server-side phases count as 0%.
+pub fn parse_clone_progress(line: &str) -> Option<CloneProgress> {
+    let line = line.trim().strip_prefix("remote:").unwrap_or(line).trim();
+    let (phase, rest) = line.split_once(':')?;
+    let (percent, rest) = rest.trim().split_once('%')?;
+    let percent: f64 = percent.trim().parse().ok()?;
+    let (start, span) = match phase {
+        "Receiving objects" => (0.0, 0.8),
+        "Resolving deltas" => (0.8, 0.15),
+        "Updating files" | "Checking out files" => (0.95, 0.05),
+        _ => (0.0, 0.0),
+    };
+    let detail = rest
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(", done.")
+        .replacen("), ", ", ", 1)
+        .trim_end_matches(')')
+        .to_string();
+    Some(CloneProgress {
+        phase: phase.to_string(),
+        fraction: start + span * (percent / 100.0).clamp(0.0, 1.0),
+        detail,
+    })
+}
+
+/// Forward `\r`/`\n`-separated lines (git redraws progress with `\r`) until EOF.
+fn send_lines(mut reader: impl Read, tx: mpsc::Sender<String>) {
+    let mut buf = [0u8; 4096];
+    let mut line = Vec::new();
+    while let Ok(n) = reader.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+        for &byte in &buf[..n] {
+            if byte == b'\r' || byte == b'\n' {
+                if !line.is_empty() {
+                    let _ = tx.send(String::from_utf8_lossy(&line).into_owned());
+                    line.clear();
+                }
+            } else {
+                line.push(byte);
+            }
+        }
+    }
+    if !line.is_empty() {
+        let _ = tx.send(String::from_utf8_lossy(&line).into_owned());
+    }
+}
+
+/// Like [`clone_codex_with`], reporting git's progress and stopping when `cancel` is set.
+///
+/// A cancelled clone kills git and removes the partial `dest`.
+pub fn clone_codex_with_progress(
+    dest: &Path,
+    options: &CloneOptions,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(CloneProgress),
+) -> Result<RepoInfo> {
+    let mut args = options.clone_args()?;
+    args.insert(1, "--progress".to_string());
     if dest.exists() {
         // Safety checks before removing
         if dest.is_symlink() {
@@ -204,33 +721,317 @@ pub fn clone_codex(dest: &Path) -> Result<RepoInfo> {
         std::fs::remove_dir_all(dest)?;
     }
 
-    let status = Command::new(resolve_command_path("git")?)
-        .args(["clone", "--depth=100", CODEX_REPO_URL])
+    let git = resolve_command_path("git")?;
+    let mut child = Command::new(&git)
+        .args(&args)
         .arg(dest)
+        .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::piped())
-        .status()?;
-
+        .spawn()?;
+    let stderr = child.stderr.take().context("git clone has no stderr")?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || send_lines(stderr, tx));
+
+    // Everything that is not a progress line, for the error message.
+    let mut messages = Vec::new();
+    let status = loop {
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(line) => match parse_clone_progress(&line) {
+                Some(progress) => on_progress(progress),
+                None if !line.starts_with("Cloning into") => messages.push(line),
+                None => {}
+            },
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break child.wait()?,
+        }
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            if dest.exists() {
+                std::fs::remove_dir_all(dest).with_context(|| {
+                    format!("Clone cancelled; failed to remove {}", dest.display())
+                })?;
+            }
+            bail!("Clone cancelled");
+        }
+    };
     if !status.success() {
-        bail!("Failed to clone repository");
+        bail!("Failed to clone repository: {}", messages.join("\n").trim());
+    }
+
+    if options.sparse {
+        let output = Command::new(&git)
+            .current_dir(dest)
+            .args(["sparse-checkout", "set", CODEX_RS_SUBDIR])
+            .output()?;
+        if !output.status.success() {
+            bail!(
+                "Cloned, but sparse checkout of {}/ failed: {}",
+                CODEX_RS_SUBDIR,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
     }
 
+    let branch = get_current_branch(dest)
+        .ok()
+        .filter(|b| !b.is_empty())
+        .unwrap_or_else(|| options.branch.clone().unwrap_or_else(|| "main".into()));
     Ok(RepoInfo {
         path: dest.to_path_buf(),
         age: "just now".into(),
-        branch: "main".into(),
+        branch,
     })
 }
 
-/// Fetch updates from remote
-pub fn fetch_repo(repo: &Path) -> Result<()> {
-    Command::new(resolve_command_path("git")?)
+/// How long [`fetch_repo`] waits for the remote before giving up.
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Fetch tags from the remote, giving up after `timeout`.
+///
+/// Git never prompts for credentials here (that would hang a UI that cannot answer).
+pub fn fetch_repo(repo: &Path, timeout: Duration) -> Result<()> {
+    let mut child = Command::new(resolve_command_path("git")?)
         .current_dir(repo)
         .args(["fetch", "--tags", "--quiet"])
-        .status()?;
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let deadline = std::time::Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if std::time::Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("git fetch timed out after {}s", timeout.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    if !status.success() {
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr);
+        }
+        let reason = stderr.lines().next().unwrap_or("").trim().to_string();
+        bail!("git fetch failed: {}", reason);
+    }
     Ok(())
 }
 
+/// When `repo` last fetched successfully (mtime of `FETCH_HEAD`).
+pub fn last_fetch_time(repo: &Path) -> Option<SystemTime> {
+    let fetch_head = git_stdout(repo, &["rev-parse", "--git-path", "FETCH_HEAD"]).ok()?;
+    repo.join(fetch_head).metadata().ok()?.modified().ok()
+}
+
+/// "fetched 3h ago" / "never fetched", for labelling a tag list.
+pub fn last_fetch_label(repo: &Path) -> String {
+    match last_fetch_time(repo) {
+        Some(time) => format!("fetched {}", format_age(time)),
+        None => "never fetched".to_string(),
+    }
+}
+
+/// True if `repo` is a shallow clone (e.g. made with `--depth`).
+pub fn is_shallow(repo: &Path) -> bool {
+    git_stdout(repo, &["rev-parse", "--is-shallow-repository"]).is_ok_and(|s| s == "true")
+}
+
+/// What [`ensure_history`] fetched so a shallow clone reaches a tag and commits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFetch {
+    /// The tag was missing and was fetched on its own
+    pub fetched_tag: bool,
+    /// Total `git fetch --deepen` steps, in commits
+    pub deepened_by: u32,
+    /// Fell back to `git fetch --unshallow`
+    pub unshallowed: bool,
+}
+
+impl HistoryFetch {
+    /// One-line summary for the UI, `None` when nothing had to be fetched.
+    pub fn summary(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.fetched_tag {
+            parts.push("fetched the tag".to_string());
+        }
+        if self.deepened_by > 0 {
+            parts.push(format!("deepened by {} commits", self.deepened_by));
+        }
+        if self.unshallowed {
+            parts.push("fetched the full history".to_string());
+        }
+        (!parts.is_empty()).then(|| format!("Shallow clone: {}", parts.join(", ")))
+    }
+}
+
+fn has_commit(repo: &Path, rev: &str) -> bool {
+    git_stdout(
+        repo,
+        &["rev-parse", "--verify", "-q", &format!("{rev}^{{commit}}")],
+    )
+    .is_ok()
+}
+
+/// What `tag` and `commits` still need from a shallow clone: the tag itself, each commit with
+/// its parent (a commit at the shallow boundary cannot be cherry-picked), and a merge base
+/// between the tag and each commit (for prerequisite detection).
+fn missing_history(repo: &Path, tag: &str, commits: &[String]) -> Vec<String> {
+    let mut missing = Vec::new();
+    let has_tag = has_commit(repo, tag);
+    if !has_tag {
+        missing.push(tag.to_string());
+    }
+    for sha in commits {
+        if !has_commit(repo, &format!("{sha}^")) {
+            missing.push(sha.clone());
+        } else if has_tag && git_stdout(repo, &["merge-base", tag, sha]).is_err() {
+            missing.push(format!("{tag}...{sha}"));
+        }
+    }
+    missing
+}
+
+/// Make sure a shallow clone has enough history to check out `tag` and cherry-pick
+/// `commits`, fetching as little as possible: a missing tag is fetched on its own, then the
+/// history is deepened in growing steps, and only as a last resort unshallowed.
+/// Full clones are left alone.
+pub fn ensure_history(repo: &Path, tag: &str, commits: &[String]) -> Result<HistoryFetch> {
+    const DEEPEN_STEPS: [u32; 5] = [100, 200, 400, 800, 1600];
+    let mut fetch = HistoryFetch::default();
+    if !is_shallow(repo) || missing_history(repo, tag, commits).is_empty() {
+        return Ok(fetch);
+    }
+
+    let remotes = git_stdout(repo, &["remote"])?;
+    let remote = match remotes.lines().find(|r| *r == "origin") {
+        Some(origin) => origin.to_string(),
+        None => match remotes.lines().next() {
+            Some(first) => first.to_string(),
+            None => bail!(
+                "{} is a shallow clone without a remote; cannot fetch the history for {}",
+                repo.display(),
+                tag
+            ),
+        },
+    };
+
+    if !has_commit(repo, tag) {
+        // Just the tagged commit; deepening below connects it if commits need a merge base.
+        let tag_ref = format!("refs/tags/{tag}:refs/tags/{tag}");
+        if run_git(repo, &["fetch", "--depth=1", &remote, &tag_ref]).is_ok() {
+            fetch.fetched_tag = true;
+        }
+    }
+
+    for step in DEEPEN_STEPS {
+        if missing_history(repo, tag, commits).is_empty() {
+            return Ok(fetch);
+        }
+        run_git(
+            repo,
+            &["fetch", "--quiet", &format!("--deepen={step}"), &remote],
+        )?;
+        fetch.deepened_by += step;
+        if !is_shallow(repo) {
+            break;
+        }
+    }
+
+    if is_shallow(repo) && !missing_history(repo, tag, commits).is_empty() {
+        run_git(
+            repo,
+            &["fetch", "--quiet", "--tags", "--unshallow", &remote],
+        )?;
+        fetch.unshallowed = true;
+    }
+    let missing = missing_history(repo, tag, commits);
+    if missing.contains(&tag.to_string()) {
+        bail!("{} not found locally or on {}", tag, remote);
+    }
+    Ok(fetch)
+}
+
+/// Commits between `previous` and `tag` (merges excluded), newest first.
+pub fn release_changelog(repo: &Path, tag: &str, previous: &str) -> Result<Vec<ChangelogEntry>> {
+    let output = Command::new(resolve_command_path("git")?)
+        .current_dir(repo)
+        .args([
+            "log",
+            "--no-merges",
+            "--format=%s",
+            &format!("{previous}..{tag}"),
+        ])
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "git log {}..{} failed: {}",
+            previous,
+            tag,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(ChangelogEntry::parse)
+        .collect())
+}
+
+/// Changelog as display lines: a `Heading (n)` line per kind followed by `- entry` lines.
+pub fn format_changelog(entries: &[ChangelogEntry]) -> Vec<String> {
+    let mut groups: Vec<(ChangeKind, Vec<&ChangelogEntry>)> = Vec::new();
+    for entry in entries {
+        match groups.iter_mut().find(|(kind, _)| *kind == entry.kind) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((entry.kind, vec![entry])),
+        }
+    }
+    groups.sort_by_key(|(kind, _)| *kind);
+
+    let mut lines = Vec::new();
+    for (kind, group) in groups {
+        lines.push(format!("{} ({})", kind.heading(), group.len()));
+        for entry in group {
+            match &entry.scope {
+                Some(scope) => lines.push(format!("- {}: {}", scope, entry.summary)),
+                None => lines.push(format!("- {}", entry.summary)),
+            }
+        }
+    }
+    lines
+}
+
+/// One-line summary, e.g. `12 commits: 3 feat, 7 fix, 2 other`.
+pub fn summarize_changelog(entries: &[ChangelogEntry]) -> String {
+    if entries.is_empty() {
+        return "no changes".to_string();
+    }
+    let mut counts: Vec<(ChangeKind, usize)> = Vec::new();
+    for entry in entries {
+        match counts.iter_mut().find(|(kind, _)| *kind == entry.kind) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((entry.kind, 1)),
+        }
+    }
+    counts.sort();
+    format!(
+        "{} commit{}: {}",
+        entries.len(),
+        if entries.len() == 1 { "" } else { "s" },
+        counts
+            .iter()
+            .map(|(kind, n)| format!("{} {}", n, kind.short()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
 /// Get all rust-v* releases from the repo (sorted newest first)
 pub fn get_releases(repo: &Path) -> Result<Vec<Release>> {
     let output = Command::new(resolve_command_path("git")?)
@@ -275,7 +1076,25 @@ pub fn get_releases(repo: &Path) -> Result<Vec<Release>> {
         });
     }
 
-    Ok(releases)
+    // `--sort=-v:refname` puts `0.99.0-alpha.1` above `0.99.0`; order by semver instead.
+    let versions: Vec<&str> = releases.iter().map(|r| r.version.as_str()).collect();
+    let order = crate::releases::select_releases(
+        &versions,
+        &crate::releases::ReleaseFilter {
+            include_prereleases: true,
+            ..Default::default()
+        },
+    );
+    let mut slots: Vec<Option<Release>> = releases.into_iter().map(Some).collect();
+    Ok(order.into_iter().filter_map(|i| slots[i].take()).collect())
+}
+
+/// Index of the newest stable release (or the newest release if all are pre-releases).
+pub fn latest_stable_release(releases: &[Release]) -> Option<usize> {
+    releases
+        .iter()
+        .position(|r| !crate::releases::is_prerelease(&r.version))
+        .or(if releases.is_empty() { None } else { Some(0) })
 }
 
 /// Get the current version of the repo
@@ -297,22 +1116,76 @@ pub fn get_current_version(repo: &Path) -> Option<String> {
 
 /// Check if repository has uncommitted changes
 pub fn has_uncommitted_changes(repo: &Path) -> bool {
+    !dirty_files(repo).is_empty()
+}
+
+/// Modified, staged and untracked files (`git status --porcelain` lines, e.g. `" M src/lib.rs"`).
+pub fn dirty_files(repo: &Path) -> Vec<String> {
     let output = match resolve_command_path("git") {
         Ok(path) => Command::new(path)
             .current_dir(repo)
             .args(["status", "--porcelain"])
             .output(),
-        Err(_) => return false,
+        Err(_) => return Vec::new(),
     };
 
     match output {
-        Ok(out) => !out.stdout.is_empty(),
-        Err(_) => false,
+        Ok(out) => String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(String::from)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Where the user's clone was before a build checked out a tag in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestorePoint {
+    /// Branch name, or commit SHA if HEAD was detached.
+    pub previous_ref: String,
+    /// Commit SHA of the stash entry created for the user's uncommitted changes.
+    pub stash: Option<String>,
+}
+
+/// Result of [`restore_checkout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreOutcome {
+    /// Previous ref checked out; the stash (if any) was popped cleanly.
+    Restored,
+    /// Previous ref checked out, but the stash entry is gone (dropped or popped by hand).
+    StashMissing,
+    /// Popping conflicted. The stash entry is kept; `files` have conflict markers.
+    StashConflicted {
+        stash_ref: String,
+        files: Vec<String>,
+    },
+}
+
+/// Current branch name, or the HEAD commit SHA when detached.
+pub fn current_ref(repo: &Path) -> Result<String> {
+    let git = resolve_command_path("git")?;
+    let branch = Command::new(&git)
+        .current_dir(repo)
+        .args(["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .output()?;
+    let name = String::from_utf8_lossy(&branch.stdout).trim().to_string();
+    if branch.status.success() && !name.is_empty() {
+        return Ok(name);
+    }
+
+    let head = Command::new(&git)
+        .current_dir(repo)
+        .args(["rev-parse", "HEAD"])
+        .output()?;
+    if !head.status.success() {
+        bail!("Failed to resolve HEAD in {}", repo.display());
     }
+    Ok(String::from_utf8_lossy(&head.stdout).trim().to_string())
 }
 
-/// Stash uncommitted changes
-pub fn stash_changes(repo: &Path) -> Result<()> {
+/// Stash uncommitted changes and return the stash commit SHA.
+pub fn stash_changes(repo: &Path) -> Result<String> {
     let status = Command::new(resolve_command_path("git")?)
         .current_dir(repo)
         // Include untracked so version checkouts/cherry-picks don't get blocked by local build
@@ -332,139 +1205,1169 @@ pub fn stash_changes(repo: &Path) -> Result<()> {
         bail!("Failed to stash changes");
     }
 
-    Ok(())
+    let output = Command::new(resolve_command_path("git")?)
+        .current_dir(repo)
+        .args(["rev-parse", "stash@{0}"])
+        .output()?;
+    if !output.status.success() {
+        bail!("Stashed changes but could not resolve stash@{{0}}");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 /// Checkout a specific version (tag or branch)
 ///
-/// Auto-stashes uncommitted changes to prevent data loss.
-pub fn checkout_version(repo: &Path, version: &str) -> Result<()> {
-    // Auto-stash uncommitted changes
-    if has_uncommitted_changes(repo) {
-        stash_changes(repo)?;
-    }
+/// Uncommitted changes are only stashed when `stash_dirty` is set (frontends ask first);
+/// otherwise a dirty tree is an error. Returns where the clone was, for [`restore_checkout`].
+pub fn checkout_version(repo: &Path, version: &str, stash_dirty: bool) -> Result<RestorePoint> {
+    let previous_ref = current_ref(repo)?;
+
+    let dirty = dirty_files(repo);
+    let stash = if dirty.is_empty() {
+        None
+    } else if stash_dirty {
+        Some(stash_changes(repo)?)
+    } else {
+        bail!(
+            "{} has {} uncommitted change(s); commit or stash them, allow stashing, or build in a worktree",
+            repo.display(),
+            dirty.len()
+        );
+    };
 
     // Checkout the version
-    let status = Command::new(resolve_command_path("git")?)
+    let output = Command::new(resolve_command_path("git")?)
         .current_dir(repo)
         .args(["checkout", version])
         .stdout(Stdio::null())
         .stderr(Stdio::piped())
-        .status()?;
+        .output()?;
 
-    if !status.success() {
-        bail!("Failed to checkout {}", version);
+    if !output.status.success() {
+        bail!(
+            "Failed to checkout {}: {}",
+            version,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
     }
 
+    Ok(RestorePoint {
+        previous_ref,
+        stash,
+    })
+}
+
+/// Put the clone back the way [`checkout_version`] found it.
+///
+/// Checks out the previous branch and pops the recorded stash entry. Build changes
+/// (patches, uncommitted cherry-picks) still in tracked files are never discarded: the
+/// restore fails instead, so the user can clean them up or keep them.
+pub fn restore_checkout(repo: &Path, point: &RestorePoint) -> Result<RestoreOutcome> {
+    let changed: Vec<String> = if current_ref(repo).ok().as_ref() == Some(&point.previous_ref) {
+        Vec::new()
+    } else {
+        dirty_files(repo)
+            .into_iter()
+            .filter(|line| !line.starts_with("??"))
+            .collect()
+    };
+    if !changed.is_empty() {
+        bail!(
+            "Not restoring {}: the build's changes are still in {} file(s) ({}). \
+             Clean them up first, or check out {} yourself",
+            point.previous_ref,
+            changed.len(),
+            changed
+                .iter()
+                .map(|line| line[3.min(line.len())..].to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            point.previous_ref
+        );
+    }
+
+    let git = resolve_command_path("git")?;
+    let output = Command::new(&git)
+        .current_dir(repo)
+        .args(["checkout", &point.previous_ref])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "Failed to checkout {}: {}",
+            point.previous_ref,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let Some(stash_sha) = &point.stash else {
+        return Ok(RestoreOutcome::Restored);
+    };
+
+    // Find the entry by SHA: the user may have stashed other things since.
+    let list = Command::new(&git)
+        .current_dir(repo)
+        .args(["stash", "list", "--format=%H"])
+        .output()?;
+    let Some(index) = String::from_utf8_lossy(&list.stdout)
+        .lines()
+        .position(|sha| sha.trim() == stash_sha)
+    else {
+        return Ok(RestoreOutcome::StashMissing);
+    };
+    let stash_ref = format!("stash@{{{index}}}");
+
+    let pop = Command::new(&git)
+        .current_dir(repo)
+        .args(["stash", "pop", &stash_ref])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .status()?;
+    if pop.success() {
+        return Ok(RestoreOutcome::Restored);
+    }
+
+    let conflicts = Command::new(&git)
+        .current_dir(repo)
+        .args(["diff", "--name-only", "--diff-filter=U"])
+        .output()?;
+    Ok(RestoreOutcome::StashConflicted {
+        stash_ref,
+        files: String::from_utf8_lossy(&conflicts.stdout)
+            .lines()
+            .map(String::from)
+            .collect(),
+    })
+}
+
+/// One-line summary of a restore for logs.
+pub fn describe_restore(point: &RestorePoint, outcome: &RestoreOutcome) -> String {
+    match outcome {
+        RestoreOutcome::Restored if point.stash.is_some() => format!(
+            "Restored {} and popped your stashed changes",
+            point.previous_ref
+        ),
+        RestoreOutcome::Restored => format!("Restored {}", point.previous_ref),
+        RestoreOutcome::StashMissing => format!(
+            "Restored {}; stash entry {} no longer exists (already popped or dropped?)",
+            point.previous_ref,
+            point.stash.as_deref().map_or("", |s| &s[..7.min(s.len())])
+        ),
+        RestoreOutcome::StashConflicted { stash_ref, files } if files.is_empty() => format!(
+            "Restored {}, but `git stash pop {}` failed; your changes are still in {}",
+            point.previous_ref, stash_ref, stash_ref
+        ),
+        RestoreOutcome::StashConflicted { stash_ref, files } => format!(
+            "Restored {}, but popping {} conflicted in: {}. Resolve the conflicts, then `git stash drop {}`",
+            point.previous_ref,
+            stash_ref,
+            files.join(", "),
+            stash_ref
+        ),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// BUILD WORKTREES
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Directory holding per-tag build worktrees (`~/.cache/codex-xtreme/worktrees`).
+pub fn worktrees_dir() -> Result<PathBuf> {
+    dirs::cache_dir()
+        .map(|d| d.join("codex-xtreme").join("worktrees"))
+        .ok_or_else(|| anyhow::anyhow!("Could not determine the user cache directory"))
+}
+
+/// Branch that carries a build's cherry-picks and patches, e.g. `xtreme/rust-v0.105.0`.
+pub fn worktree_branch(tag: &str) -> String {
+    format!("xtreme/{tag}")
+}
+
+/// Commits made by codex-xtreme in build worktrees use a fixed identity, so they work
+/// without a configured `user.email` and are easy to tell apart from the user's own.
+fn as_xtreme_committer(cmd: &mut Command) {
+    for var in ["GIT_AUTHOR_NAME", "GIT_COMMITTER_NAME"] {
+        cmd.env(var, "codex-xtreme");
+    }
+    for var in ["GIT_AUTHOR_EMAIL", "GIT_COMMITTER_EMAIL"] {
+        cmd.env(var, "codex-xtreme@localhost");
+    }
+}
+
+fn run_git(repo: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new(resolve_command_path("git")?)
+        .current_dir(repo)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
     Ok(())
 }
 
+/// Create (or reset) a dedicated worktree for building `tag` and return its path.
+///
+/// The worktree lives under [`worktrees_dir`] on branch `xtreme/<tag>`, which is reset to
+/// the tag on every build. The user's own clone is never checked out, stashed or modified;
+/// afterwards `git diff <tag>..xtreme/<tag>` shows exactly what the build changed.
+/// Ignored files (notably `codex-rs/target/`) are kept so rebuilds stay incremental.
+pub fn prepare_worktree(repo: &Path, tag: &str) -> Result<PathBuf> {
+    let dir_name: String = tag
+        .chars()
+        .map(|c| if c == '/' || c == '\\' { '_' } else { c })
+        .collect();
+    let path = worktrees_dir()?.join(dir_name);
+    let branch = worktree_branch(tag);
+    let target = format!("{tag}^{{commit}}");
+
+    if path.join(".git").exists() {
+        run_git(&path, &["checkout", "--force", "-B", &branch, &target])?;
+        run_git(&path, &["clean", "-fd"])?;
+    } else {
+        // Drop registrations of worktrees whose directories were deleted.
+        run_git(repo, &["worktree", "prune"])?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let path_str = path.to_string_lossy();
+        run_git(
+            repo,
+            &[
+                "worktree", "add", "--force", "-B", &branch, &path_str, &target,
+            ],
+        )?;
+    }
+
+    Ok(path)
+}
+
+/// Commit every change in `repo` (used for patches in build worktrees).
+///
+/// Returns `false` when there was nothing to commit.
+pub fn commit_all(repo: &Path, message: &str) -> Result<bool> {
+    if !has_uncommitted_changes(repo) {
+        return Ok(false);
+    }
+    run_git(repo, &["add", "--all"])?;
+    let mut cmd = Command::new(resolve_command_path("git")?);
+    as_xtreme_committer(&mut cmd);
+    let output = cmd
+        .current_dir(repo)
+        .args(["commit", "--no-verify", "-q", "-m", message])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "git commit failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(true)
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // DEV WORKFLOWS
 // ═══════════════════════════════════════════════════════════════════════════
 
-#[derive(Debug, Default, Clone)]
-pub struct CherryPickOutcome {
-    pub skipped: Vec<String>,
+/// Check that a user-supplied commit SHA looks valid (7-40 hex characters).
+pub fn is_valid_sha(sha: &str) -> bool {
+    sha.len() >= 7 && sha.len() <= 40 && sha.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// One entry of a cherry-pick list: a single commit or an `A..B` range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitSpec {
+    Sha(String),
+    Range(String, String),
 }
 
-/// Cherry-pick commits onto the current checkout without committing.
+/// Parse comma/whitespace separated SHAs and `A..B` ranges.
 ///
-/// This is used in `--dev` mode so users can apply hotfixes from main.
-/// Conflicts are handled by aborting the cherry-pick and recording the SHA.
-pub fn cherry_pick_commits(repo: &Path, shas: &[String]) -> Result<CherryPickOutcome> {
-    let mut outcome = CherryPickOutcome::default();
+/// Returns the valid entries and the ones that were rejected.
+pub fn parse_commit_specs(input: &str) -> (Vec<CommitSpec>, Vec<String>) {
+    let mut specs = Vec::new();
+    let mut invalid = Vec::new();
+    for part in input.split(|c: char| c == ',' || c.is_whitespace()) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        match part.split_once("..") {
+            Some((from, to)) if is_valid_sha(from) && is_valid_sha(to) => {
+                specs.push(CommitSpec::Range(from.to_string(), to.to_string()))
+            }
+            None if is_valid_sha(part) => specs.push(CommitSpec::Sha(part.to_string())),
+            _ => invalid.push(part.to_string()),
+        }
+    }
+    (specs, invalid)
+}
 
-    for sha in shas {
-        let status = Command::new(resolve_command_path("git")?)
-            .current_dir(repo)
-            .args(["cherry-pick", "--no-commit", sha])
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .status()?;
+/// Expand ranges (`A..B` = commits after A up to and including B) into SHAs, oldest first.
+pub fn expand_commit_specs(repo: &Path, specs: &[CommitSpec]) -> Result<Vec<String>> {
+    let mut shas: Vec<String> = Vec::new();
+    for spec in specs {
+        match spec {
+            CommitSpec::Sha(sha) => shas.push(sha.clone()),
+            CommitSpec::Range(from, to) => {
+                let output = Command::new(resolve_command_path("git")?)
+                    .current_dir(repo)
+                    .args([
+                        "rev-list",
+                        "--reverse",
+                        "--topo-order",
+                        &format!("{from}..{to}"),
+                    ])
+                    .output()?;
+                if !output.status.success() {
+                    bail!(
+                        "Invalid commit range {}..{}: {}",
+                        from,
+                        to,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    );
+                }
+                shas.extend(
+                    String::from_utf8_lossy(&output.stdout)
+                        .lines()
+                        .map(String::from),
+                );
+            }
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    shas.retain(|sha| seen.insert(sha.clone()));
+    Ok(shas)
+}
+
+/// Combine browsed commits with typed specs: expand ranges, drop duplicates (including
+/// short/full SHA pairs) and put everything in topological order.
+pub fn resolve_cherry_picks(
+    repo: &Path,
+    selected: &[String],
+    specs: &[CommitSpec],
+) -> Result<Vec<String>> {
+    let mut shas = selected.to_vec();
+    for sha in expand_commit_specs(repo, specs)? {
+        if !shas
+            .iter()
+            .any(|s| s.starts_with(sha.as_str()) || sha.starts_with(s.as_str()))
+        {
+            shas.push(sha);
+        }
+    }
+    Ok(topo_order_commits(repo, &shas))
+}
 
-        if !status.success() {
-            Command::new(resolve_command_path("git")?)
+/// The branch dev-mode commits are browsed from: `origin/main`, else local `main`.
+pub fn default_upstream(repo: &Path) -> Option<String> {
+    let git = resolve_command_path("git").ok()?;
+    ["origin/main", "origin/HEAD", "main"]
+        .into_iter()
+        .find(|candidate| {
+            Command::new(&git)
                 .current_dir(repo)
-                .args(["cherry-pick", "--abort"])
+                .args(["rev-parse", "--verify", "--quiet", candidate])
                 .stdout(Stdio::null())
                 .stderr(Stdio::null())
                 .status()
-                .ok();
-            outcome.skipped.push(sha.clone());
+                .is_ok_and(|s| s.success())
+        })
+        .map(String::from)
+}
+
+/// Commits in `base..upstream`, newest first, optionally limited to those touching `path`
+/// (e.g. `codex-rs/core`).
+pub fn list_commits(
+    repo: &Path,
+    base: &str,
+    upstream: &str,
+    path: Option<&str>,
+) -> Result<Vec<CommitInfo>> {
+    let mut cmd = Command::new(resolve_command_path("git")?);
+    cmd.current_dir(repo).args([
+        "log",
+        "--topo-order",
+        "--name-only",
+        "--format=%x1e%H%x1f%h%x1f%s%x1f%an%x1f%as",
+        &format!("{base}..{upstream}"),
+    ]);
+    if let Some(path) = path.filter(|p| !p.trim().is_empty()) {
+        cmd.args(["--", path.trim()]);
+    }
+    let output = cmd.output()?;
+    if !output.status.success() {
+        bail!(
+            "git log {}..{} failed: {}",
+            base,
+            upstream,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut commits = Vec::new();
+    for record in stdout.split('\x1e').filter(|r| !r.trim().is_empty()) {
+        let mut lines = record.lines();
+        let header = lines.next().unwrap_or_default();
+        let fields: Vec<&str> = header.split('\x1f').collect();
+        let [sha, short_sha, subject, author, date] = fields[..] else {
+            continue;
+        };
+        commits.push(CommitInfo {
+            sha: sha.to_string(),
+            short_sha: short_sha.to_string(),
+            subject: subject.to_string(),
+            author: author.to_string(),
+            date: date.to_string(),
+            files: lines
+                .filter(|l| !l.trim().is_empty())
+                .map(String::from)
+                .collect(),
+        });
+    }
+    Ok(commits)
+}
+
+/// `git show --stat --patch` for the commit browser's preview pane.
+pub fn commit_diff(repo: &Path, sha: &str) -> Result<String> {
+    let output = Command::new(resolve_command_path("git")?)
+        .current_dir(repo)
+        .args([
+            "show",
+            "--stat",
+            "--patch",
+            "--format=%H%n%an <%ae>%n%ad%n%n%B",
+            sha,
+        ])
+        .output()?;
+    if !output.status.success() {
+        bail!("git show {} failed", sha);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Reorder SHAs so parents come before children (the order they were committed upstream).
+///
+/// SHAs git cannot resolve keep their relative position at the end.
+pub fn topo_order_commits(repo: &Path, shas: &[String]) -> Vec<String> {
+    let Ok(git) = resolve_command_path("git") else {
+        return shas.to_vec();
+    };
+    let full: Vec<Option<String>> = shas
+        .iter()
+        .map(|sha| {
+            let output = Command::new(&git)
+                .current_dir(repo)
+                .args([
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("{sha}^{{commit}}"),
+                ])
+                .output()
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .collect();
+
+    let known: Vec<&String> = full.iter().flatten().collect();
+    if known.len() < 2 {
+        return shas.to_vec();
+    }
+
+    // Walk from all selected commits, stopping at their merge base with each other, so the
+    // listing stays small even on a long history.
+    let mut cmd = Command::new(&git);
+    cmd.current_dir(repo)
+        .args(["rev-list", "--topo-order", "--reverse"])
+        .args(known.iter().map(|s| s.as_str()));
+    if let Ok(base) = Command::new(&git)
+        .current_dir(repo)
+        .arg("merge-base")
+        .arg("--octopus")
+        .args(known.iter().map(|s| s.as_str()))
+        .output()
+    {
+        let base = String::from_utf8_lossy(&base.stdout).trim().to_string();
+        if !base.is_empty() {
+            cmd.arg(format!("^{base}^@"));
         }
     }
+    let order: std::collections::HashMap<String, usize> = match cmd.output() {
+        Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .enumerate()
+            .map(|(i, sha)| (sha.to_string(), i))
+            .collect(),
+        _ => return shas.to_vec(),
+    };
 
-    Ok(outcome)
+    let mut indexed: Vec<(usize, usize, &String)> = shas
+        .iter()
+        .zip(&full)
+        .enumerate()
+        .map(|(i, (sha, full))| {
+            let rank = full
+                .as_ref()
+                .and_then(|f| order.get(f))
+                .copied()
+                .unwrap_or(usize::MAX);
+            (rank, i, sha)
+        })
+        .collect();
+    indexed.sort();
+    indexed.into_iter().map(|(_, _, sha)| sha.clone()).collect()
 }
 
-// ═══════════════════════════════════════════════════════════════════════════
-// PATCHES
-// ═══════════════════════════════════════════════════════════════════════════
+/// Merge strategy option for retrying a conflicting cherry-pick (`git cherry-pick -X ...`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CherryPickStrategy {
+    /// `-X patience`: slower diff that often untangles moved code.
+    Patience,
+    /// `-X theirs`: resolve conflicting hunks in favour of the picked commit.
+    Theirs,
+}
 
-/// Find the patches directory
-pub fn find_patches_dir() -> Result<PathBuf> {
-    let candidates = [
-        // Development: sibling directory
-        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../codex-patcher/patches"),
-        // Installed: ~/.config/codex-patcher/patches
-        dirs::config_dir()
-            .unwrap_or_default()
-            .join("codex-patcher/patches"),
-    ];
+impl CherryPickStrategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            CherryPickStrategy::Patience => "patience",
+            CherryPickStrategy::Theirs => "theirs",
+        }
+    }
+}
+
+/// Opt-in ways to get a conflicting pick to apply.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CherryPickOptions {
+    /// Strategies tried in order after a plain pick conflicts.
+    pub retry_strategies: Vec<CherryPickStrategy>,
+    /// Pick the suggested prerequisite commits first, then retry.
+    pub include_prerequisites: bool,
+}
+
+/// Why a commit could not be picked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CherryPickFailure {
+    pub sha: String,
+    /// Files left with conflicts by the plain pick.
+    pub conflicted_files: Vec<String>,
+    /// git's error output.
+    pub error: String,
+    /// Earlier upstream commits (oldest first) that touch the conflicted files but are not
+    /// in the checkout: likely prerequisites.
+    pub prerequisites: Vec<String>,
+}
+
+impl CherryPickFailure {
+    /// Multi-line explanation for logs.
+    pub fn describe(&self) -> String {
+        let short = |s: &str| s[..7.min(s.len())].to_string();
+        let mut out = format!("{} could not be applied", short(&self.sha));
+        if !self.conflicted_files.is_empty() {
+            out.push_str(&format!(
+                "\n    conflicts: {}",
+                self.conflicted_files.join(", ")
+            ));
+        }
+        if let Some(line) = self
+            .error
+            .lines()
+            .find(|l| l.starts_with("CONFLICT") || l.starts_with("error"))
+        {
+            out.push_str(&format!("\n    git: {}", line.trim()));
+        }
+        if !self.prerequisites.is_empty() {
+            out.push_str(&format!(
+                "\n    likely prerequisites: {}",
+                self.prerequisites
+                    .iter()
+                    .map(|s| short(s))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        out
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CherryPickOutcome {
+    /// Commits that were applied, in order (including auto-included prerequisites).
+    pub applied: Vec<String>,
+    pub skipped: Vec<String>,
+    pub failures: Vec<CherryPickFailure>,
+    /// Picks that only applied with a retry strategy.
+    pub retried: Vec<(String, CherryPickStrategy)>,
+    /// Prerequisites picked automatically (`include_prerequisites`).
+    pub included_prerequisites: Vec<String>,
+}
+
+/// HEAD and index tree before a pick, so a failed attempt can be undone without losing
+/// earlier uncommitted (`--no-commit`) picks.
+struct PickSnapshot {
+    head: String,
+    tree: String,
+}
+
+fn git_stdout(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new(resolve_command_path("git")?)
+        .current_dir(repo)
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn pick_snapshot(repo: &Path) -> Result<PickSnapshot> {
+    Ok(PickSnapshot {
+        head: git_stdout(repo, &["rev-parse", "HEAD"])?,
+        tree: git_stdout(repo, &["write-tree"])?,
+    })
+}
+
+fn restore_pick_snapshot(repo: &Path, snapshot: &PickSnapshot) -> Result<()> {
+    // `--abort` would also discard earlier `--no-commit` picks; drop the sequencer state
+    // and put HEAD, index and worktree back explicitly instead.
+    git_stdout(repo, &["cherry-pick", "--quit"]).ok();
+    git_stdout(repo, &["read-tree", "--reset", "-u", &snapshot.tree])?;
+    git_stdout(repo, &["reset", "-q", "--soft", &snapshot.head])?;
+    Ok(())
+}
 
-    if let Ok(env_path) = std::env::var("CODEX_PATCHER_PATCHES") {
-        let path = PathBuf::from(env_path);
-        if path.exists() && path.is_dir() {
-            return Ok(path.canonicalize()?);
+/// Attempt one pick. On failure, returns (conflicted files, stderr) with the repo restored.
+fn try_cherry_pick(
+    repo: &Path,
+    sha: &str,
+    commit: bool,
+    strategy: Option<CherryPickStrategy>,
+) -> Result<std::result::Result<(), (Vec<String>, String)>> {
+    let snapshot = pick_snapshot(repo)?;
+    let mut cmd = Command::new(resolve_command_path("git")?);
+    if commit {
+        as_xtreme_committer(&mut cmd);
+    }
+    cmd.arg("cherry-pick");
+    if commit {
+        cmd.arg("-x");
+    } else {
+        cmd.arg("--no-commit");
+    }
+    if let Some(strategy) = strategy {
+        cmd.args(["-X", strategy.name()]);
+    }
+    let output = cmd
+        .arg(sha)
+        .current_dir(repo)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()?;
+    if output.status.success() {
+        return Ok(Ok(()));
+    }
+
+    let conflicted = git_stdout(repo, &["diff", "--name-only", "--diff-filter=U"])
+        .map(|out| out.lines().map(String::from).collect())
+        .unwrap_or_default();
+    let mut error = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        if !error.is_empty() {
+            error.push('\n');
         }
+        error.push_str(stderr.trim());
+    }
+    restore_pick_snapshot(repo, &snapshot)?;
+    Ok(Err((conflicted, error)))
+}
+
+/// Upstream commits before `sha` that touch `files` and are not in the checkout yet.
+///
+/// `exclude` lists commits already applied without committing (so not reachable from HEAD).
+pub fn find_prerequisites(
+    repo: &Path,
+    sha: &str,
+    files: &[String],
+    exclude: &[String],
+) -> Vec<String> {
+    const MAX_PREREQUISITES: usize = 10;
+    if files.is_empty() {
+        return Vec::new();
     }
+    let range = format!("HEAD..{sha}^");
+    let mut args = vec!["log", "--format=%H", "-n", "50", &range, "--"];
+    args.extend(files.iter().map(|f| f.as_str()));
+    let Ok(out) = git_stdout(repo, &args) else {
+        return Vec::new();
+    };
+    let mut prerequisites: Vec<String> = out
+        .lines()
+        .filter(|c| {
+            !exclude
+                .iter()
+                .any(|e| c.starts_with(e.as_str()) || e.starts_with(c))
+        })
+        .take(MAX_PREREQUISITES)
+        .map(String::from)
+        .collect();
+    prerequisites.reverse();
+    prerequisites
+}
+
+/// Cherry-pick commits onto the current checkout.
+///
+/// This is used in `--dev` mode so users can apply hotfixes from main. Commits are applied
+/// in topological order regardless of the order they were selected in.
+/// In a build worktree (`commit = true`) each pick becomes a commit on the `xtreme/<tag>`
+/// branch; otherwise the changes are left uncommitted in the checkout.
+/// A conflicting pick is rolled back and, unless one of the opt-in `options` gets it to
+/// apply, recorded in `skipped`/`failures` with the conflicted files and likely prerequisites.
+pub fn cherry_pick_commits(
+    repo: &Path,
+    shas: &[String],
+    commit: bool,
+    options: &CherryPickOptions,
+) -> Result<CherryPickOutcome> {
+    let mut outcome = CherryPickOutcome::default();
+
+    for sha in &topo_order_commits(repo, shas) {
+        if outcome
+            .included_prerequisites
+            .iter()
+            .any(|p| p.starts_with(sha.as_str()) || sha.starts_with(p.as_str()))
+        {
+            // Already pulled in as a prerequisite of an earlier pick.
+            continue;
+        }
+
+        let (conflicted_files, error) = match try_cherry_pick(repo, sha, commit, None)? {
+            Ok(()) => {
+                outcome.applied.push(sha.clone());
+                continue;
+            }
+            Err(failure) => failure,
+        };
+
+        let mut applied = false;
+        for &strategy in &options.retry_strategies {
+            if try_cherry_pick(repo, sha, commit, Some(strategy))?.is_ok() {
+                outcome.applied.push(sha.clone());
+                outcome.retried.push((sha.clone(), strategy));
+                applied = true;
+                break;
+            }
+        }
+        if applied {
+            continue;
+        }
 
-    for path in candidates {
-        if path.exists() && path.is_dir() {
-            return Ok(path.canonicalize()?);
+        let prerequisites = find_prerequisites(repo, sha, &conflicted_files, &outcome.applied);
+        if options.include_prerequisites && !prerequisites.is_empty() {
+            let snapshot = pick_snapshot(repo)?;
+            let mut picked = Vec::new();
+            let mut ok = true;
+            for prerequisite in &prerequisites {
+                if try_cherry_pick(repo, prerequisite, commit, None)?.is_err() {
+                    ok = false;
+                    break;
+                }
+                picked.push(prerequisite.clone());
+            }
+            if ok && try_cherry_pick(repo, sha, commit, None)?.is_ok() {
+                outcome.applied.extend(picked.iter().cloned());
+                outcome.included_prerequisites.extend(picked);
+                outcome.applied.push(sha.clone());
+                continue;
+            }
+            restore_pick_snapshot(repo, &snapshot)?;
         }
+
+        outcome.skipped.push(sha.clone());
+        outcome.failures.push(CherryPickFailure {
+            sha: sha.clone(),
+            conflicted_files,
+            error,
+            prerequisites,
+        });
     }
 
-    bail!("Could not find patches directory. Set CODEX_PATCHER_PATCHES env var.")
+    Ok(outcome)
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// PATCHES
+// ═══════════════════════════════════════════════════════════════════════════
+
 /// Check if a patch is compatible with a target version
 pub fn is_patch_compatible(version_range: Option<&str>, target_version: &str) -> bool {
-    // Strip "rust-v" prefix if present (tags come as "rust-v0.99.0")
-    let version = target_version
+    incompatibility(version_range, target_version).is_none()
+}
+
+/// `rust-v0.99.0` → `0.99.0`
+fn bare_version(target_version: &str) -> &str {
+    target_version
         .strip_prefix("rust-v")
-        .unwrap_or(target_version);
+        .unwrap_or(target_version)
+}
+
+/// Why a patch's `version_range` rules out `target_version`, or `None` when it matches.
+pub fn incompatibility(version_range: Option<&str>, target_version: &str) -> Option<String> {
+    // Strip "rust-v" prefix if present (tags come as "rust-v0.99.0")
+    let version = bare_version(target_version);
+    let range = version_range?;
 
     // Fail closed when the version requirement is malformed.
-    matches_requirement(version, version_range).unwrap_or(false)
+    match matches_requirement(version, Some(range)) {
+        Ok(true) => return None,
+        Ok(false) => {}
+        Err(_) if semver::VersionReq::parse(range).is_err() => {
+            return Some(format!("invalid version_range \"{}\"", range));
+        }
+        Err(_) => return Some(format!("{} is not a release version", target_version)),
+    }
+
+    // Pre-releases only match ranges naming a pre-release of the same x.y.z.
+    if let (Ok(parsed), Ok(req)) = (
+        semver::Version::parse(version),
+        semver::VersionReq::parse(range),
+    ) {
+        if !parsed.pre.is_empty() {
+            let release = semver::Version::new(parsed.major, parsed.minor, parsed.patch);
+            if req.matches(&release) {
+                return Some(format!(
+                    "{} is a pre-release; \"{}\" only covers {} pre-releases it names",
+                    version, range, release
+                ));
+            }
+        }
+    }
+    Some(format!("{} is outside \"{}\"", version, range))
+}
+
+/// Family of a version-specific patch file: `privacy-v0.105-alpha13.toml` → `privacy`.
+pub fn patch_family(patch: &Path) -> String {
+    let stem = patch
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let split = stem.match_indices("-v").find(|(idx, _)| {
+        stem[idx + 2..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_digit())
+    });
+    match split {
+        Some((idx, _)) => stem[..idx].to_string(),
+        None => stem,
+    }
+}
+
+/// Numbers in a variant's version suffix, for ordering: `privacy-v0.105-alpha13` → `[0, 105, 13]`.
+fn variant_key(patch: &Path) -> Vec<u64> {
+    let family = patch_family(patch);
+    let stem = patch
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    stem[family.len()..]
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|n| n.parse().ok())
+        .collect()
+}
+
+/// Swap each selected patch file for the best compatible member of its family.
+///
+/// Members of a family (`privacy.toml`, `privacy-v0.99-alpha23.toml`, ...) target different
+/// Codex versions. When any member is selected, the compatible one with the newest version
+/// suffix is selected instead and its siblings are dropped, so the family is never silently
+/// lost. Families without a compatible member are left alone. Returns the new selection and
+/// a note per swap.
+pub fn pick_patch_variants(
+    patches: &[(&Path, Option<&str>)],
+    selected: &[PathBuf],
+    target_version: &str,
+) -> (Vec<PathBuf>, Vec<String>) {
+    let mut picked: Vec<PathBuf> = Vec::new();
+    let mut notes = Vec::new();
+    let mut done: Vec<String> = Vec::new();
+    for path in selected {
+        let family = patch_family(path);
+        let members: Vec<&(&Path, Option<&str>)> = patches
+            .iter()
+            .filter(|(p, _)| patch_family(p) == family)
+            .collect();
+        if members.len() < 2 {
+            picked.push(path.clone());
+            continue;
+        }
+        if done.contains(&family) {
+            continue;
+        }
+        done.push(family.clone());
+        let best = members
+            .iter()
+            .filter(|(_, range)| is_patch_compatible(*range, target_version))
+            .max_by_key(|(p, _)| variant_key(p));
+        let chosen: Vec<PathBuf> = match best {
+            Some((best, _)) => vec![best.to_path_buf()],
+            // Nothing fits: keep what the user picked and let the build report it.
+            None => selected
+                .iter()
+                .filter(|p| patch_family(p) == family)
+                .cloned()
+                .collect(),
+        };
+        let dropped: Vec<String> = selected
+            .iter()
+            .filter(|p| patch_family(p) == family && !chosen.contains(p))
+            .map(|p| {
+                p.file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        if !dropped.is_empty() {
+            notes.push(format!(
+                "{}: using {} for {} instead of {}",
+                family,
+                chosen[0].file_stem().unwrap_or_default().to_string_lossy(),
+                bare_version(target_version),
+                dropped.join(", ")
+            ));
+        }
+        picked.extend(chosen);
+    }
+    (picked, notes)
+}
+
+/// Project-local patch directory, relative to the current directory or the selected repo.
+pub const PROJECT_PATCH_DIR: &str = ".codex-xtreme/patches";
+
+/// Where a patch directory was configured, in precedence order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PatchSource {
+    /// `CODEX_PATCHER_PATCHES` (a `:`-separated path list)
+    Env,
+    /// `patch_dirs` in the user config
+    Config,
+    /// `.codex-xtreme/patches` in the current directory or the selected repo
+    Project,
+    /// The codex-patcher dev checkout or `~/.config/codex-patcher/patches`
+    Builtin,
+}
+
+impl fmt::Display for PatchSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PatchSource::Env => "env",
+            PatchSource::Config => "config",
+            PatchSource::Project => "project",
+            PatchSource::Builtin => "built-in",
+        })
+    }
+}
+
+/// One layer of the patch search path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchDir {
+    pub path: PathBuf,
+    pub source: PatchSource,
+}
+
+impl fmt::Display for PatchDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.path.display(), self.source)
+    }
+}
+
+/// Existing patch directories, highest precedence first: `CODEX_PATCHER_PATCHES`, the user
+/// config's `patch_dirs`, project-local `.codex-xtreme/patches` (current directory, then
+/// `repo`), then the built-in locations. A directory listed twice keeps its first layer.
+pub fn patch_dirs(repo: Option<&Path>) -> Result<Vec<PatchDir>> {
+    let mut candidates: Vec<(PathBuf, PatchSource)> = Vec::new();
+    if let Some(paths) = std::env::var_os("CODEX_PATCHER_PATCHES") {
+        candidates.extend(
+            std::env::split_paths(&paths)
+                .filter(|p| !p.as_os_str().is_empty())
+                .map(|p| {
+                    let expanded = shellexpand::tilde(&p.to_string_lossy()).into_owned();
+                    (PathBuf::from(expanded), PatchSource::Env)
+                }),
+        );
+    }
+    let config = crate::config::Config::load().unwrap_or_default();
+    candidates.extend(
+        config
+            .patch_dirs()
+            .into_iter()
+            .map(|dir| (dir, PatchSource::Config)),
+    );
+    if let Ok(cwd) = std::env::current_dir() {
+        candidates.push((cwd.join(PROJECT_PATCH_DIR), PatchSource::Project));
+    }
+    if let Some(repo) = repo {
+        candidates.push((repo.join(PROJECT_PATCH_DIR), PatchSource::Project));
+    }
+    candidates.push((
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../codex-patcher/patches"),
+        PatchSource::Builtin,
+    ));
+    if let Some(config_dir) = dirs::config_dir() {
+        candidates.push((
+            config_dir.join("codex-patcher/patches"),
+            PatchSource::Builtin,
+        ));
+    }
+
+    let mut dirs: Vec<PatchDir> = Vec::new();
+    for (path, source) in candidates {
+        if !path.is_dir() {
+            continue;
+        }
+        let path = path.canonicalize().unwrap_or(path);
+        if !dirs.iter().any(|d| d.path == path) {
+            dirs.push(PatchDir { path, source });
+        }
+    }
+    if dirs.is_empty() {
+        bail!(
+            "Could not find a patches directory. Set CODEX_PATCHER_PATCHES, add patch_dirs to \
+             the config, or create {}",
+            PROJECT_PATCH_DIR
+        );
+    }
+    Ok(dirs)
+}
+
+/// Patch files found across `dirs`, with the directory each came from.
+///
+/// A file name present in several layers is taken from the first (highest precedence) one;
+/// each shadowed copy yields a warning.
+fn layered_patch_files(dirs: &[PatchDir]) -> (Vec<(PathBuf, &PatchDir)>, Vec<String>) {
+    let mut files: Vec<(PathBuf, &PatchDir)> = Vec::new();
+    let mut warnings = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir.path) else {
+            warnings.push(format!("Could not read patch directory {}", dir));
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension() == Some(OsStr::new("toml")))
+            .filter(|path| path.file_name() != Some(OsStr::new(crate::patch_order::ORDER_FILE)))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let shadowing = files
+                .iter()
+                .find(|(existing, _)| existing.file_name() == path.file_name());
+            match shadowing {
+                Some((_, winner)) => warnings.push(format!(
+                    "{} in {} shadows the copy in {}",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    winner,
+                    dir
+                )),
+                None => files.push((path, dir)),
+            }
+        }
+    }
+    (files, warnings)
+}
+
+/// Patches merged from every patch directory layer.
+#[derive(Debug, Clone, Default)]
+pub struct PatchCatalog {
+    /// Directories searched, highest precedence first.
+    pub dirs: Vec<PatchDir>,
+    /// Loadable patch files (shadowed copies left out), sorted by name.
+    pub patches: Vec<(PathBuf, PatchConfig)>,
+    /// Shadowed files, duplicate patch names and dangling relations, worth showing before
+    /// selection.
+    pub warnings: Vec<String>,
+    /// Declared `requires` / `after` / `conflicts_with` relations between the patch files.
+    pub order: crate::patch_order::PatchGraph,
 }
 
-/// Load all available patches, sorted alphabetically by name
-pub fn get_available_patches() -> Result<Vec<(PathBuf, PatchConfig)>> {
-    let patches_dir = find_patches_dir()?;
-    let mut patches = Vec::new();
+impl PatchCatalog {
+    /// The directory layer a loaded patch file came from.
+    pub fn source_of(&self, patch: &Path) -> Option<&PatchDir> {
+        self.dirs
+            .iter()
+            .find(|d| patch.parent() == Some(d.path.as_path()))
+    }
+}
 
-    for entry in std::fs::read_dir(&patches_dir)? {
-        let path = entry?.path();
-        if path.extension() == Some(OsStr::new("toml")) {
-            match load_from_path(&path) {
-                Ok(config) if !config.patches.is_empty() => {
-                    patches.push((path, config));
+/// Load all available patches from every patch directory, sorted alphabetically by name.
+pub fn get_available_patches(repo: Option<&Path>) -> Result<PatchCatalog> {
+    let dirs = patch_dirs(repo)?;
+    let (files, mut warnings) = layered_patch_files(&dirs);
+
+    let mut patches: Vec<(PathBuf, PatchConfig)> = Vec::new();
+    for (path, dir) in files {
+        match load_from_path(&path) {
+            Ok(config) if !config.patches.is_empty() => {
+                if let Some((other, _)) = patches
+                    .iter()
+                    .find(|(_, loaded)| loaded.meta.name == config.meta.name)
+                {
+                    warnings.push(format!(
+                        "Two patch files are named \"{}\": {} and {} ({})",
+                        config.meta.name,
+                        other.display(),
+                        path.display(),
+                        dir.source
+                    ));
                 }
-                _ => {}
+                patches.push((path, config));
             }
+            _ => {}
         }
     }
 
     patches.sort_by(|a, b| a.1.meta.name.cmp(&b.1.meta.name));
-
-    Ok(patches)
+    let order = crate::patch_order::PatchGraph::new(&patches)?;
+    warnings.extend(
+        order
+            .dangling_references()
+            .into_iter()
+            .map(|r| format!("{} matches no patch file", r)),
+    );
+
+    Ok(PatchCatalog {
+        dirs,
+        patches,
+        warnings,
+        order,
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::is_patch_compatible;
+    use super::*;
+    use crate::test_support::{git_in, TestRepo};
 
     #[test]
     fn patch_compatibility_strips_rust_prefix() {
@@ -474,6 +2377,105 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn higher_patch_layers_shadow_lower_ones() {
+        let root = TestRepo::new("patch-layers");
+        let layer = |name: &str, source: PatchSource, files: &[&str]| {
+            let path = root.join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            for file in files {
+                std::fs::write(path.join(file), "").unwrap();
+            }
+            PatchDir { path, source }
+        };
+        let dirs = [
+            layer(
+                "personal",
+                PatchSource::Config,
+                &["privacy.toml", "mine.toml"],
+            ),
+            layer(
+                "team",
+                PatchSource::Project,
+                &["privacy.toml", "team.toml", "notes.md"],
+            ),
+        ];
+
+        let (files, warnings) = layered_patch_files(&dirs);
+        let found: Vec<(String, PatchSource)> = files
+            .iter()
+            .map(|(path, dir)| {
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                (name, dir.source)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("mine.toml".to_string(), PatchSource::Config),
+                ("privacy.toml".to_string(), PatchSource::Config),
+                ("team.toml".to_string(), PatchSource::Project),
+            ]
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("privacy.toml in "));
+        assert!(warnings[0].contains("(config) shadows the copy in "));
+        assert!(warnings[0].ends_with("(project)"));
+    }
+
+    #[test]
+    fn incompatibility_explains_why() {
+        assert_eq!(incompatibility(None, "rust-v0.99.0"), None);
+        assert_eq!(
+            incompatibility(Some(">=0.105.0"), "rust-v0.99.0").unwrap(),
+            "0.99.0 is outside \">=0.105.0\""
+        );
+        assert!(incompatibility(Some(">=0.99.0"), "rust-v0.100.0-alpha.2")
+            .unwrap()
+            .contains("is a pre-release"));
+        assert_eq!(
+            incompatibility(Some(">=nope"), "rust-v0.99.0").unwrap(),
+            "invalid version_range \">=nope\""
+        );
+    }
+
+    #[test]
+    fn picks_the_compatible_member_of_a_patch_family() {
+        assert_eq!(
+            patch_family(Path::new("/p/privacy-v0.105-alpha13.toml")),
+            "privacy"
+        );
+        assert_eq!(patch_family(Path::new("/p/privacy.toml")), "privacy");
+        assert_eq!(
+            patch_family(Path::new("/p/undo-command.toml")),
+            "undo-command"
+        );
+
+        let old = PathBuf::from("/p/privacy-v0.99-alpha23.toml");
+        let new = PathBuf::from("/p/privacy-v0.105-alpha13.toml");
+        let base = PathBuf::from("/p/privacy.toml");
+        let other = PathBuf::from("/p/undo-command.toml");
+        let patches = [
+            (old.as_path(), Some(">=0.99.0-alpha.1, <0.100.0")),
+            (new.as_path(), Some(">=0.105.0-alpha.1, <0.106.0")),
+            (base.as_path(), None),
+            (other.as_path(), Some(">=0.200.0")),
+        ];
+
+        let (picked, notes) =
+            pick_patch_variants(&patches, &[old.clone(), other.clone()], "rust-v0.105.0");
+        assert_eq!(picked, vec![new.clone(), other.clone()]);
+        assert_eq!(
+            notes,
+            vec!["privacy: using privacy-v0.105-alpha13 for 0.105.0 instead of privacy-v0.99-alpha23"]
+        );
+
+        // Only the unversioned member fits: it replaces both selected variants.
+        let (picked, _) =
+            pick_patch_variants(&patches, &[old.clone(), new.clone()], "rust-v0.120.0");
+        assert_eq!(picked, vec![base]);
+    }
+
     #[test]
     fn patch_compatibility_fails_closed_on_invalid_requirement() {
         assert!(!is_patch_compatible(
@@ -481,4 +2483,407 @@ mod tests {
             "rust-v0.100.0-alpha.2"
         ));
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn discovers_nested_repos_and_their_worktrees() {
+        let root = TestRepo::new("discover-test");
+        let write_workspace = |repo: &Path, package: &str| {
+            let crate_dir = repo.join(CODEX_RS_SUBDIR).join("cli");
+            std::fs::create_dir_all(crate_dir.join("src")).unwrap();
+            std::fs::write(
+                repo.join(CODEX_RS_SUBDIR).join("Cargo.toml"),
+                "[workspace]\nmembers = [\"cli\"]\nresolver = \"2\"\n",
+            )
+            .unwrap();
+            std::fs::write(
+                crate_dir.join("Cargo.toml"),
+                format!(
+                    "[package]\nname = \"{package}\"\nversion = \"0.0.0\"\nedition = \"2021\"\n"
+                ),
+            )
+            .unwrap();
+            std::fs::write(crate_dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        };
+        let repo = root.join("work/forks/codex");
+        write_workspace(&repo, "codex-cli");
+        write_workspace(&root.join("other"), "not-codex");
+
+        let git = |args: &[&str]| git_in(&repo, args);
+        git(&["init", "-q", "-b", "main"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "init"]);
+        let worktree = root.join("elsewhere/codex-wt");
+        git(&["worktree", "add", "-q", worktree.to_str().unwrap()]);
+        // Build worktrees (on an `xtreme/*` branch) are not offered as repos.
+        let build_worktree = root.join("elsewhere/codex-build");
+        git(&[
+            "worktree",
+            "add",
+            "-q",
+            "-b",
+            &worktree_branch("v1"),
+            build_worktree.to_str().unwrap(),
+        ]);
+
+        let discover = |max_depth| -> Vec<PathBuf> {
+            let discovery = RepoDiscovery {
+                roots: vec![root.to_path_buf()],
+                max_depth,
+            };
+            find_codex_repos_in(&discovery)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.path)
+                .collect()
+        };
+        let expected = vec![
+            worktree.canonicalize().unwrap(),
+            repo.canonicalize().unwrap(),
+        ];
+        assert!(discover(1).is_empty());
+        // The worktree is two levels down; the main checkout is found through it.
+        assert_eq!(discover(2), expected);
+        assert_eq!(discover(3), expected);
+
+        // A root that is a checkout itself (like the default `~/codex`) is found at any depth.
+        assert!(RepoDiscovery::default()
+            .roots
+            .contains(&PathBuf::from(shellexpand::tilde("~/codex").as_ref())));
+        let at_root = RepoDiscovery {
+            roots: vec![repo.clone()],
+            max_depth: 0,
+        };
+        let found: Vec<PathBuf> = find_codex_repos_in(&at_root)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.path)
+            .collect();
+        assert_eq!(found, expected);
+
+        assert!(open_codex_repo(repo.to_str().unwrap()).is_ok());
+        assert!(open_codex_repo(root.join("other").to_str().unwrap()).is_err());
+        assert!(open_codex_repo(root.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn parses_git_clone_progress() {
+        let receiving =
+            parse_clone_progress("Receiving objects:  50% (4500/9000), 12.00 MiB | 3.10 MiB/s")
+                .unwrap();
+        assert_eq!(receiving.phase, "Receiving objects");
+        assert!((receiving.fraction - 0.4).abs() < 1e-9);
+        assert_eq!(receiving.detail, "4500/9000, 12.00 MiB | 3.10 MiB/s");
+
+        let resolving = parse_clone_progress("Resolving deltas: 100% (300/300), done.").unwrap();
+        assert!((resolving.fraction - 0.95).abs() < 1e-9);
+        assert_eq!(resolving.detail, "300/300");
+
+        let remote = parse_clone_progress("remote: Counting objects:  10% (1/10)").unwrap();
+        assert_eq!(remote.phase, "Counting objects");
+        assert_eq!(remote.fraction, 0.0);
+        assert!(parse_clone_progress("Cloning into 'codex'...").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn clones_local_mirror_and_bundle_with_options() {
+        let root = TestRepo::new("clone-test");
+        let source = root.join("mirror");
+        std::fs::create_dir_all(source.join(CODEX_RS_SUBDIR)).unwrap();
+        std::fs::create_dir_all(source.join("docs")).unwrap();
+        std::fs::write(source.join(CODEX_RS_SUBDIR).join("lib.rs"), "").unwrap();
+        std::fs::write(source.join("docs/index.md"), "").unwrap();
+        let git = git_in;
+        git(&source, &["init", "-q", "-b", "main"]);
+        git(&source, &["add", "."]);
+        git(&source, &["commit", "-q", "-m", "one"]);
+        git(&source, &["commit", "-q", "--allow-empty", "-m", "two"]);
+        git(&source, &["branch", "fork"]);
+
+        let options = CloneOptions {
+            source: source.display().to_string(),
+            branch: Some("fork".to_string()),
+            history: CloneHistory::Shallow(1),
+            sparse: true,
+        };
+        let dest = root.join("shallow");
+        let info = clone_codex_with(&dest, &options).unwrap();
+        assert_eq!(info.branch, "fork");
+        assert!(dest.join(CODEX_RS_SUBDIR).join("lib.rs").exists());
+        assert!(!dest.join("docs").exists());
+        assert!(dest.join(".git/shallow").exists());
+
+        let bundle = root.join("codex.bundle");
+        git(
+            &source,
+            &["bundle", "create", "-q", bundle.to_str().unwrap(), "--all"],
+        );
+        let options = CloneOptions {
+            source: bundle.display().to_string(),
+            ..Default::default()
+        };
+        assert_eq!(options.effective_history(), CloneHistory::Full);
+        let dest = root.join("from-bundle");
+        clone_codex_with(&dest, &options).unwrap();
+        assert!(dest.join("docs/index.md").exists());
+
+        // Cancelling kills git and removes the partial clone.
+        let dest = root.join("cancelled");
+        let cancel = AtomicBool::new(true);
+        let err = clone_codex_with_progress(&dest, &options, &cancel, |_| {}).unwrap_err();
+        assert!(err.to_string().contains("cancelled"));
+        assert!(!dest.exists());
+
+        let missing = CloneOptions {
+            source: root.join("nope").display().to_string(),
+            ..Default::default()
+        };
+        assert!(missing.validate().is_err());
+        assert!(matches!(
+            CloneOptions::default().validate(),
+            Ok(CloneSource::Url(_))
+        ));
+        let scp = CloneOptions {
+            source: "git@github.com:me/codex.git".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(scp.source_kind(), Ok(CloneSource::Url(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ensure_history_deepens_shallow_clones_on_demand() {
+        let root = TestRepo::new("shallow-test");
+        let upstream = root.join("upstream");
+        std::fs::create_dir_all(&upstream).unwrap();
+        let git = git_in;
+        git(&upstream, &["init", "-q", "-b", "main"]);
+        for i in 0..8 {
+            git(
+                &upstream,
+                &["commit", "-q", "--allow-empty", "-m", &format!("c{i}")],
+            );
+            if i == 1 {
+                git(&upstream, &["tag", "v1"]);
+            }
+        }
+        let tip = git(&upstream, &["rev-parse", "HEAD"]);
+
+        let clone = root.join("clone");
+        let url = format!("file://{}", upstream.display());
+        git(
+            &root,
+            &["clone", "-q", "--depth=2", &url, clone.to_str().unwrap()],
+        );
+        assert!(is_shallow(&clone));
+        assert!(!has_commit(&clone, "v1"));
+
+        // Checkout only needs the tagged commit.
+        let fetch = ensure_history(&clone, "v1", &[]).unwrap();
+        assert!(fetch.fetched_tag);
+        assert_eq!(fetch.deepened_by, 0);
+        assert!(is_shallow(&clone));
+        assert!(checkout_version(&clone, "v1", false).is_ok());
+
+        // Cherry-picking the tip onto v1 needs its parent and a merge base.
+        let fetch = ensure_history(&clone, "v1", std::slice::from_ref(&tip)).unwrap();
+        assert!(fetch.deepened_by > 0);
+        assert!(fetch.summary().unwrap().contains("deepened"));
+        assert!(!is_shallow(&clone));
+        assert_eq!(
+            ensure_history(&clone, "v1", &[tip]).unwrap(),
+            HistoryFetch::default()
+        );
+        assert!(ensure_history(&upstream, "v1", &[])
+            .unwrap()
+            .summary()
+            .is_none());
+
+        assert_eq!(last_fetch_label(&upstream), "never fetched");
+        fetch_repo(&clone, FETCH_TIMEOUT).unwrap();
+        assert!(last_fetch_label(&clone).starts_with("fetched "));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stash_checkout_and_restore_round_trip() {
+        let repo = TestRepo::init("stash-test", "mywork");
+        let git = |args: &[&str]| repo.git(args);
+        std::fs::write(repo.join("file.txt"), "v1\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "v1"]);
+        git(&["tag", "v1"]);
+        std::fs::write(repo.join("file.txt"), "v2\n").unwrap();
+        git(&["commit", "-q", "-am", "v2"]);
+
+        std::fs::write(repo.join("file.txt"), "local edit\n").unwrap();
+        assert_eq!(dirty_files(&repo), vec![" M file.txt".to_string()]);
+        assert!(
+            checkout_version(&repo, "v1", false).is_err(),
+            "dirty tree needs consent"
+        );
+
+        let point = checkout_version(&repo, "v1", true).unwrap();
+        assert_eq!(point.previous_ref, "mywork");
+        assert!(point.stash.is_some());
+        assert_eq!(
+            std::fs::read_to_string(repo.join("file.txt")).unwrap(),
+            "v1\n"
+        );
+
+        // A build-time patch left in a tracked file blocks the restore instead of being lost.
+        std::fs::write(repo.join("file.txt"), "patched\n").unwrap();
+        let err = restore_checkout(&repo, &point).unwrap_err();
+        assert!(err.to_string().contains("file.txt"), "{}", err);
+        assert_eq!(
+            std::fs::read_to_string(repo.join("file.txt")).unwrap(),
+            "patched\n"
+        );
+        git(&["checkout", "-q", "--", "file.txt"]);
+        assert_eq!(
+            restore_checkout(&repo, &point).unwrap(),
+            RestoreOutcome::Restored
+        );
+        assert_eq!(current_ref(&repo).unwrap(), "mywork");
+        assert_eq!(
+            std::fs::read_to_string(repo.join("file.txt")).unwrap(),
+            "local edit\n"
+        );
+        assert_eq!(
+            restore_checkout(&repo, &point).unwrap(),
+            RestoreOutcome::StashMissing
+        );
+    }
+
+    #[test]
+    fn commit_specs_accept_shas_and_ranges() {
+        let (specs, invalid) = parse_commit_specs("abc1234, def5678..0123abc  not-a-sha,,");
+        assert_eq!(
+            specs,
+            vec![
+                CommitSpec::Sha("abc1234".into()),
+                CommitSpec::Range("def5678".into(), "0123abc".into()),
+            ]
+        );
+        assert_eq!(invalid, vec!["not-a-sha".to_string()]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commits_are_listed_filtered_and_topo_ordered() {
+        let repo = TestRepo::init("commits-test", "main");
+        let git = |args: &[&str]| repo.git(args);
+        std::fs::write(repo.join("base.txt"), "base\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "base"]);
+        git(&["tag", "v1"]);
+        let mut shas = Vec::new();
+        for (dir, n) in [("core", 1), ("tui", 2), ("core", 3)] {
+            std::fs::create_dir_all(repo.join(dir)).unwrap();
+            std::fs::write(repo.join(dir).join(format!("{n}.txt")), "x\n").unwrap();
+            git(&["add", "."]);
+            git(&["commit", "-q", "-m", &format!("{dir} change {n}")]);
+            shas.push(git(&["rev-parse", "HEAD"]));
+        }
+
+        let all = list_commits(&repo, "v1", "main", None).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].subject, "core change 3");
+        assert_eq!(all[0].files, vec!["core/3.txt".to_string()]);
+
+        let core_only = list_commits(&repo, "v1", "main", Some("core")).unwrap();
+        assert_eq!(core_only.len(), 2);
+
+        let reversed = vec![shas[2].clone(), shas[0][..7].to_string()];
+        assert_eq!(
+            topo_order_commits(&repo, &reversed),
+            vec![shas[0][..7].to_string(), shas[2].clone()]
+        );
+
+        let range = format!("{}..{}", &shas[0][..7], &shas[2][..7]);
+        let (specs, _) = parse_commit_specs(&range);
+        assert_eq!(
+            resolve_cherry_picks(&repo, &[shas[2].clone()], &specs).unwrap(),
+            vec![shas[1].clone(), shas[2].clone()]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn conflicting_pick_reports_and_includes_prerequisites() {
+        let repo = TestRepo::init("pick-test", "main");
+        let git = |args: &[&str]| repo.git(args);
+        git(&["commit", "-q", "--allow-empty", "-m", "base"]);
+        git(&["tag", "v1"]);
+        let commit = |file: &str, contents: &str| {
+            std::fs::write(repo.join(file), contents).unwrap();
+            git(&["add", "."]);
+            git(&["commit", "-q", "-m", file]);
+            git(&["rev-parse", "HEAD"])
+        };
+        let a = commit("a", "a\n");
+        let b1 = commit("b", "x\n");
+        let b2 = commit("b", "y\n");
+        git(&["checkout", "-q", "v1"]);
+
+        // Without options the pick of b2 is rolled back, keeping the earlier no-commit pick.
+        let picks = vec![b2.clone(), a.clone()];
+        let outcome =
+            cherry_pick_commits(&repo, &picks, false, &CherryPickOptions::default()).unwrap();
+        assert_eq!(outcome.applied, vec![a.clone()]);
+        assert_eq!(outcome.skipped, vec![b2.clone()]);
+        assert_eq!(outcome.failures[0].conflicted_files, vec!["b".to_string()]);
+        assert_eq!(outcome.failures[0].prerequisites, vec![b1.clone()]);
+        assert!(repo.join("a").exists() && !repo.join("b").exists());
+
+        git(&["reset", "-q", "--hard", "v1"]);
+        let options = CherryPickOptions {
+            retry_strategies: vec![CherryPickStrategy::Patience],
+            include_prerequisites: true,
+        };
+        let outcome = cherry_pick_commits(&repo, &picks, false, &options).unwrap();
+        assert_eq!(outcome.applied, vec![a, b1.clone(), b2]);
+        assert_eq!(outcome.included_prerequisites, vec![b1]);
+        assert!(outcome.failures.is_empty());
+        assert_eq!(std::fs::read_to_string(repo.join("b")).unwrap(), "y\n");
+    }
+
+    #[test]
+    fn changelog_groups_conventional_commits() {
+        let entries: Vec<ChangelogEntry> = [
+            "fix(tui): wrap long lines (#123)",
+            "feat: add /review command",
+            "Bump version",
+            "feat(core)!: drop legacy config",
+            "fix: handle 429",
+        ]
+        .into_iter()
+        .map(ChangelogEntry::parse)
+        .collect();
+
+        assert_eq!(entries[0].kind, ChangeKind::Fix);
+        assert_eq!(entries[0].scope.as_deref(), Some("tui"));
+        assert_eq!(entries[3].kind, ChangeKind::Breaking);
+        assert_eq!(entries[2].summary, "Bump version");
+
+        assert_eq!(
+            format_changelog(&entries),
+            vec![
+                "Breaking changes (1)",
+                "- core: drop legacy config",
+                "Features (1)",
+                "- add /review command",
+                "Fixes (2)",
+                "- tui: wrap long lines (#123)",
+                "- handle 429",
+                "Other changes (1)",
+                "- Bump version",
+            ]
+        );
+        assert_eq!(
+            summarize_changelog(&entries),
+            "5 commits: 1 breaking, 1 feat, 2 fix, 1 other"
+        );
+    }
 }
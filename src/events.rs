@@ -0,0 +1,210 @@
+//! Machine-readable event stream (`--events=json`).
+//!
+//! Every `workflow::Event` is written as one JSON object per line (NDJSON), followed by a
+//! final `result` record, so dashboards and editor integrations can follow a build without
+//! scraping terminal output.
+//!
+//! ```text
+//! {"event":"phase","phase":"compiling","ts_ms":1760000000000}
+//! {"event":"progress","value":0.42,"ts_ms":1760000000100}
+//! {"event":"result","success":true,"binary_path":"/path/to/codex","elapsed_secs":812.4,"ts_ms":...}
+//! ```
+
+use crate::workflow::Event;
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Supported `--events` formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventFormat {
+    Json,
+}
+
+impl EventFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "json" => Ok(EventFormat::Json),
+            other => bail!("Unsupported event format '{other}' (expected \"json\")"),
+        }
+    }
+}
+
+/// Convert a workflow event into its JSON record (without timestamp).
+pub fn event_to_json(event: &Event) -> Value {
+    match event {
+        Event::Phase(phase) => json!({ "event": "phase", "phase": phase.name() }),
+        Event::Progress(value) => json!({ "event": "progress", "value": value }),
+        Event::CurrentItem(item) => json!({ "event": "current_item", "item": item }),
+        Event::Log(message) => json!({ "event": "log", "message": message }),
+        Event::PatchFileApplied(name) => {
+            json!({ "event": "patch_file", "name": name, "status": "applied" })
+        }
+        Event::PatchFileSkipped { name, reason } => json!({
+            "event": "patch_file",
+            "name": name,
+            "status": "skipped",
+            "reason": reason,
+        }),
+        Event::PatchFilePartial {
+            name,
+            applied,
+            total,
+            reason,
+        } => json!({
+            "event": "patch_file",
+            "name": name,
+            "status": "partial",
+            "applied": applied,
+            "total": total,
+            "reason": reason,
+        }),
+        Event::RequiredPatchFailed(failure) => json!({
+            "event": "required_patch_failed",
+            "file": failure.file,
+            "id": failure.id,
+            "status": failure.status.as_str(),
+            "target": failure.target.as_ref().map(|t| t.display().to_string()),
+            "reason": failure.reason,
+        }),
+    }
+}
+
+/// Writes NDJSON records to stdout or a file (use `/dev/fd/N` for a file descriptor).
+pub struct EventWriter {
+    out: Box<dyn Write + Send>,
+    /// Set after the first write error; the build keeps going without the stream.
+    broken: bool,
+}
+
+impl EventWriter {
+    pub fn stdout() -> Self {
+        Self {
+            out: Box::new(std::io::stdout()),
+            broken: false,
+        }
+    }
+
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to open event stream: {}", path.display()))?;
+        Ok(Self {
+            out: Box::new(file),
+            broken: false,
+        })
+    }
+
+    pub fn event(&mut self, event: &Event) {
+        self.write_record(event_to_json(event));
+    }
+
+    /// Final record: success with the binary path, or the error message.
+    pub fn result(&mut self, result: std::result::Result<&Path, String>, elapsed: Duration) {
+        let record = match result {
+            Ok(binary_path) => json!({
+                "event": "result",
+                "success": true,
+                "binary_path": binary_path.display().to_string(),
+                "elapsed_secs": elapsed.as_secs_f64(),
+            }),
+            Err(error) => json!({
+                "event": "result",
+                "success": false,
+                "error": error,
+                "elapsed_secs": elapsed.as_secs_f64(),
+            }),
+        };
+        self.write_record(record);
+    }
+
+    fn write_record(&mut self, mut record: Value) {
+        if self.broken {
+            return;
+        }
+        let ts_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        record["ts_ms"] = json!(ts_ms);
+
+        let written = serde_json::to_writer(&mut self.out, &record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.out.write_all(b"\n"))
+            .and_then(|_| self.out.flush());
+        if written.is_err() {
+            self.broken = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::Phase;
+
+    #[test]
+    fn events_serialize_with_stable_names() {
+        assert_eq!(
+            event_to_json(&Event::Phase(Phase::Compiling)),
+            json!({ "event": "phase", "phase": "compiling" })
+        );
+        assert_eq!(
+            event_to_json(&Event::PatchFileSkipped {
+                name: "privacy".to_string(),
+                reason: "version".to_string(),
+            }),
+            json!({
+                "event": "patch_file",
+                "name": "privacy",
+                "status": "skipped",
+                "reason": "version",
+            })
+        );
+        assert_eq!(
+            event_to_json(&Event::PatchFilePartial {
+                name: "privacy".to_string(),
+                applied: 2,
+                total: 3,
+                reason: "no match".to_string(),
+            }),
+            json!({
+                "event": "patch_file",
+                "name": "privacy",
+                "status": "partial",
+                "applied": 2,
+                "total": 3,
+                "reason": "no match",
+            })
+        );
+    }
+
+    #[test]
+    fn required_patch_failures_are_records_of_their_own() {
+        let failure = crate::workflow::RequiredPatchFailure {
+            file: "privacy".to_string(),
+            id: "statsig-endpoint".to_string(),
+            status: crate::manifest::PatchStatus::Failed,
+            target: Some("core/src/statsig.rs".into()),
+            reason: Some("no match".to_string()),
+        };
+        assert_eq!(
+            event_to_json(&Event::RequiredPatchFailed(failure)),
+            json!({
+                "event": "required_patch_failed",
+                "file": "privacy",
+                "id": "statsig-endpoint",
+                "status": "failed",
+                "target": "core/src/statsig.rs",
+                "reason": "no match",
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_format_is_rejected() {
+        assert!(EventFormat::parse("json").is_ok());
+        assert!(EventFormat::parse("yaml").is_err());
+    }
+}
@@ -0,0 +1,352 @@
+//! Versioned install store.
+//!
+//! Finished binaries are copied out of `codex-rs/target/` (which the next `cargo build` or
+//! `cargo clean` clobbers) into a store under the user data dir:
+//!
+//! ```text
+//! ~/.local/share/codex-xtreme/
+//!   builds/0.105.0-xtreme-native-bolt-1a2b3c4d/codex
+//!   builds/0.105.0-xtreme-native-bolt-1a2b3c4d/manifest.json
+//!   current -> builds/0.105.0-xtreme-native-bolt-1a2b3c4d
+//!   history            (one build id per line, most recent last)
+//! ```
+//!
+//! The shell alias points at `current/codex`, so switching builds or rolling back is just
+//! repointing the `current` symlink.
+
+use crate::manifest::BuildManifest;
+use crate::workflow::CODEX_BINARY;
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// A build in the store.
+#[derive(Debug, Clone)]
+pub struct InstalledBuild {
+    pub id: String,
+    pub dir: PathBuf,
+    pub binary: PathBuf,
+    /// Copy of the build manifest, if one was stored alongside the binary.
+    pub manifest: Option<BuildManifest>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InstallStore {
+    root: PathBuf,
+}
+
+impl InstallStore {
+    /// The store under the platform data dir (`~/.local/share/codex-xtreme` on Linux).
+    pub fn open_default() -> Result<Self> {
+        let data_dir = dirs::data_dir().context("Could not determine the user data directory")?;
+        Ok(Self::at(data_dir.join("codex-xtreme")))
+    }
+
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn builds_dir(&self) -> PathBuf {
+        self.root.join("builds")
+    }
+
+    fn current_link(&self) -> PathBuf {
+        self.root.join("current")
+    }
+
+    fn history_file(&self) -> PathBuf {
+        self.root.join("history")
+    }
+
+    /// Stable path of the active binary (what the shell alias points at).
+    pub fn current_binary(&self) -> PathBuf {
+        self.current_link().join(CODEX_BINARY)
+    }
+
+    /// Copy a finished binary (and its manifest) into the store and make it current.
+    ///
+    /// Installing an identical binary again reuses the existing entry.
+    pub fn install(&self, binary_path: &Path, manifest: &BuildManifest) -> Result<InstalledBuild> {
+        let hash = crate::manifest::hash_file(binary_path)?;
+        let id = build_id(manifest, &hash);
+        let dir = self.builds_dir().join(&id);
+        let binary = dir.join(CODEX_BINARY);
+
+        if !binary.exists() {
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+            // Copy under a temp name first so a half-written binary never looks installed.
+            let partial = dir.join(format!("{CODEX_BINARY}.partial"));
+            std::fs::copy(binary_path, &partial).with_context(|| {
+                format!("Failed to copy {} into the store", binary_path.display())
+            })?;
+            std::fs::rename(&partial, &binary)?;
+        }
+        std::fs::write(dir.join("manifest.json"), manifest.to_json()?)?;
+
+        self.use_build(&id)?;
+        Ok(InstalledBuild {
+            id,
+            dir,
+            binary,
+            manifest: Some(manifest.clone()),
+        })
+    }
+
+    /// All installed builds, newest first.
+    pub fn list(&self) -> Result<Vec<InstalledBuild>> {
+        let builds_dir = self.builds_dir();
+        if !builds_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut builds = Vec::new();
+        for entry in std::fs::read_dir(&builds_dir)? {
+            let dir = entry?.path();
+            let binary = dir.join(CODEX_BINARY);
+            if !binary.is_file() {
+                continue;
+            }
+            let Some(id) = dir.file_name().map(|n| n.to_string_lossy().to_string()) else {
+                continue;
+            };
+            let manifest = std::fs::read_to_string(dir.join("manifest.json"))
+                .ok()
+                .and_then(|s| BuildManifest::from_json(&s).ok());
+            builds.push(InstalledBuild {
+                id,
+                dir,
+                binary,
+                manifest,
+            });
+        }
+
+        builds.sort_by(|a, b| {
+            let built_at = |b: &InstalledBuild| b.manifest.as_ref().map_or(0, |m| m.built_at);
+            built_at(b).cmp(&built_at(a)).then_with(|| a.id.cmp(&b.id))
+        });
+        Ok(builds)
+    }
+
+    /// Id of the build `current` points at, if any.
+    pub fn current(&self) -> Option<String> {
+        let target = std::fs::read_link(self.current_link()).ok()?;
+        target.file_name().map(|n| n.to_string_lossy().to_string())
+    }
+
+    /// Resolve a full id or unique prefix to an installed build.
+    pub fn find(&self, id: &str) -> Result<InstalledBuild> {
+        let builds = self.list()?;
+        if let Some(build) = builds.iter().find(|b| b.id == id) {
+            return Ok(build.clone());
+        }
+        let matches: Vec<&InstalledBuild> =
+            builds.iter().filter(|b| b.id.starts_with(id)).collect();
+        match matches.as_slice() {
+            [build] => Ok((*build).clone()),
+            [] => bail!("No installed build matches '{id}' (see `codex-xtreme list`)"),
+            _ => bail!(
+                "'{id}' is ambiguous: {}",
+                matches
+                    .iter()
+                    .map(|b| b.id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Point `current` at an installed build and record it in the history.
+    pub fn use_build(&self, id: &str) -> Result<InstalledBuild> {
+        let build = self.find(id)?;
+        self.point_current_at(&build.id)?;
+
+        let mut history = self.read_history();
+        history.push(build.id.clone());
+        self.write_history(&history)?;
+        Ok(build)
+    }
+
+    /// Switch back to the build that was current before this one.
+    ///
+    /// Repeated rollbacks walk further back through the history. Builds that have been
+    /// removed are skipped.
+    pub fn rollback(&self) -> Result<InstalledBuild> {
+        let current = self.current().context("No build is currently active")?;
+        let mut history = self.read_history();
+
+        // Drop the current build (and any repeats of it) from the tail.
+        while history.last().is_some_and(|id| *id == current) {
+            history.pop();
+        }
+        while let Some(previous) = history.last().cloned() {
+            if previous != current
+                && self
+                    .builds_dir()
+                    .join(&previous)
+                    .join(CODEX_BINARY)
+                    .is_file()
+            {
+                self.point_current_at(&previous)?;
+                self.write_history(&history)?;
+                return self.find(&previous);
+            }
+            history.pop();
+        }
+        bail!("No previous build to roll back to")
+    }
+
+    /// Delete an installed build. The active build cannot be removed.
+    pub fn remove(&self, id: &str) -> Result<InstalledBuild> {
+        let build = self.find(id)?;
+        if self.current().as_deref() == Some(build.id.as_str()) {
+            bail!(
+                "{} is the current build; `use` or `rollback` to another build first",
+                build.id
+            );
+        }
+        std::fs::remove_dir_all(&build.dir)
+            .with_context(|| format!("Failed to remove {}", build.dir.display()))?;
+        Ok(build)
+    }
+
+    fn point_current_at(&self, id: &str) -> Result<()> {
+        let link = self.current_link();
+        let tmp = self.root.join("current.tmp");
+        std::fs::remove_file(&tmp).ok();
+        symlink_dir(&Path::new("builds").join(id), &tmp)?;
+        // rename() over the old link is atomic, so `current/codex` never disappears.
+        std::fs::rename(&tmp, &link).with_context(|| format!("Failed to update {}", link.display()))
+    }
+
+    fn read_history(&self) -> Vec<String> {
+        std::fs::read_to_string(self.history_file())
+            .map(|s| {
+                s.lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn write_history(&self, history: &[String]) -> Result<()> {
+        let mut contents = history.join("\n");
+        contents.push('\n');
+        std::fs::write(self.history_file(), contents).context("Failed to write install history")
+    }
+}
+
+#[cfg(unix)]
+fn symlink_dir(target: &Path, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link)
+        .with_context(|| format!("Failed to create symlink {}", link.display()))
+}
+
+#[cfg(not(unix))]
+fn symlink_dir(_target: &Path, _link: &Path) -> Result<()> {
+    bail!("The install store requires symlink support (unix only)")
+}
+
+/// `<version>-<variant>-<hash>`, e.g. `0.105.0-xtreme-native-bolt-1a2b3c4d`.
+pub fn build_id(manifest: &BuildManifest, binary_hash: &str) -> String {
+    let version = manifest
+        .tag
+        .strip_prefix("rust-v")
+        .or_else(|| manifest.tag.strip_prefix('v'))
+        .unwrap_or(&manifest.tag);
+
+    let mut variant = manifest.profile.clone();
+    if let Some(cpu) = &manifest.cpu_target {
+        variant.push('-');
+        variant.push_str(cpu);
+    }
+    if manifest.bolt {
+        variant.push_str("-bolt");
+    }
+
+    let id = format!(
+        "{}-{}-{}",
+        version,
+        variant,
+        &binary_hash[..8.min(binary_hash.len())]
+    );
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::{BuildOptions, OptimizationFlags};
+
+    fn manifest(tag: &str, bolt: bool) -> BuildManifest {
+        let options = BuildOptions {
+            profile: "xtreme".to_string(),
+            cpu_target: Some("native".to_string()),
+            optimization: OptimizationFlags {
+                use_mold: false,
+                use_bolt: bolt,
+            },
+            strip_symbols: true,
+            cargo_jobs: None,
+            offline: false,
+        };
+        BuildManifest::new(Path::new("/nonexistent"), tag, &options)
+    }
+
+    #[test]
+    fn build_id_includes_version_variant_and_hash() {
+        assert_eq!(
+            build_id(&manifest("rust-v0.105.0", true), "1a2b3c4d5e6f"),
+            "0.105.0-xtreme-native-bolt-1a2b3c4d"
+        );
+        assert_eq!(
+            build_id(&manifest("feature/x", false), "ffff0000"),
+            "feature_x-xtreme-native-ffff0000"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_switch_rollback_and_remove() {
+        let root =
+            std::env::temp_dir().join(format!("codex-xtreme-install-test-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        let store = InstallStore::at(&root);
+        let src = root.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+
+        let bin_a = src.join("a");
+        let bin_b = src.join("b");
+        std::fs::write(&bin_a, b"build a").unwrap();
+        std::fs::write(&bin_b, b"build b").unwrap();
+
+        let a = store
+            .install(&bin_a, &manifest("rust-v0.1.0", false))
+            .unwrap();
+        let b = store
+            .install(&bin_b, &manifest("rust-v0.2.0", false))
+            .unwrap();
+        assert_eq!(store.current().as_deref(), Some(b.id.as_str()));
+        assert_eq!(std::fs::read(store.current_binary()).unwrap(), b"build b");
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        assert!(store.remove(&b.id).is_err(), "current build is protected");
+        assert_eq!(store.rollback().unwrap().id, a.id);
+        assert_eq!(std::fs::read(store.current_binary()).unwrap(), b"build a");
+        assert!(store.rollback().is_err(), "nothing before the first build");
+
+        store.remove(&b.id).unwrap();
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
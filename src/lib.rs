@@ -12,3 +12,34 @@ pub mod core;
 
 // Shared workflow (build, BOLT, etc) used by both frontends.
 pub mod workflow;
+
+// Recipe files for non-interactive builds.
+pub mod recipe;
+
+// NDJSON event stream for dashboards/editor integrations.
+pub mod events;
+
+// Build provenance manifests (sidecar + embedded ELF section).
+pub mod manifest;
+
+// Versioned install store (`current` symlink, switch, rollback).
+pub mod install;
+
+// Content-addressed build cache.
+pub mod cache;
+
+// Semver-aware release filtering/grouping shared by both frontends.
+pub mod releases;
+
+// User config (`~/.config/codex-xtreme/config.toml`): defaults and last-used choices.
+pub mod config;
+
+// Restore the clone after in-place builds; detect interrupted runs.
+pub mod cleanup;
+
+// Declared ordering and dependencies between patch files.
+pub mod patch_order;
+
+// Scratch git repositories shared by the unit tests.
+#[cfg(test)]
+mod test_support;
@@ -5,15 +5,42 @@
 
 use anyhow::{bail, Context, Result};
 use cliclack::{confirm, input, intro, log, multiselect, outro, select, spinner};
-use codex_xtreme::core::check_prerequisites;
+use codex_xtreme::config::{BuildChoices, Config};
+use codex_xtreme::core::{check_prerequisites, RepoInfo};
 use codex_xtreme::cpu_detect::detect_cpu_target;
+use codex_xtreme::events::{EventFormat, EventWriter};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-use std::time::SystemTime;
-use tracing::{debug, info, instrument, warn};
+use std::process::Command;
+use std::time::Instant;
+use tracing::{info, instrument, warn};
+
+/// What to run (the interactive wizard unless a subcommand is given)
+enum Subcommand {
+    Wizard,
+    /// Non-interactive build driven by a recipe file
+    Build {
+        recipe: PathBuf,
+    },
+    /// Print the build manifest of a binary (embedded section or sidecar file)
+    Inspect {
+        path: PathBuf,
+        json: bool,
+    },
+    /// Manage the versioned install store
+    Store(StoreCommand),
+}
+
+/// `list` / `use` / `rollback` / `remove` against the install store
+enum StoreCommand {
+    List,
+    Use(String),
+    Rollback,
+    Remove(String),
+}
 
 /// CLI arguments
 struct Args {
+    subcommand: Subcommand,
     /// Developer mode - enables cherry-pick UI and other advanced options
     dev_mode: bool,
     /// Print CPU detection result and exit
@@ -22,6 +49,28 @@ struct Args {
     tui: bool,
     /// Limit parallel cargo jobs (reduces peak CPU usage during builds/tests).
     cargo_jobs: Option<usize>,
+    /// Machine-readable event stream format (`--events=json`)
+    events: Option<EventFormat>,
+    /// Where to write the event stream (defaults to stdout)
+    events_file: Option<PathBuf>,
+    /// Build cache directory override (`--cache-dir`)
+    cache_dir: Option<PathBuf>,
+    /// Disable the build cache for this run (`--no-cache`)
+    no_cache: bool,
+    /// Build in a dedicated git worktree instead of the selected clone (`--worktree`)
+    worktree: bool,
+    /// Recipe builds: stash a dirty clone before checkout (`--stash`)
+    stash: bool,
+    /// Recipe builds: restore the previous branch and pop the stash afterwards (`--restore`)
+    restore: bool,
+    /// Never touch the network: no tag fetch, no history deepening, `cargo --offline`
+    offline: bool,
+    /// Leave patched sources in the clone after an in-place build (`--no-cleanup`)
+    no_cleanup: bool,
+    /// Refuse to build while selected patches edit the same lines (`--strict`)
+    strict: bool,
+    /// Stop when any selected patch fails to apply, not just required files (`--require-all-patches`)
+    require_all: bool,
 }
 
 fn resolve_command_path(name: &str) -> Result<PathBuf> {
@@ -35,35 +84,6 @@ fn resolve_command_path(name: &str) -> Result<PathBuf> {
 /// The Rust workspace lives in this subdirectory of the repo root
 const CODEX_RS_SUBDIR: &str = "codex-rs";
 
-/// GitHub repo URL
-const CODEX_REPO_URL: &str = "https://github.com/openai/codex.git";
-
-// ═══════════════════════════════════════════════════════════════════════════
-// TYPES
-// ═══════════════════════════════════════════════════════════════════════════
-
-#[derive(Debug)]
-struct RepoInfo {
-    /// Path to the repo root (not codex-rs)
-    path: PathBuf,
-    age: String,
-    branch: String,
-}
-
-impl RepoInfo {
-    /// Returns the path to the codex-rs workspace
-    fn workspace_path(&self) -> PathBuf {
-        self.path.join(CODEX_RS_SUBDIR)
-    }
-}
-
-#[derive(Debug, Clone)]
-struct Release {
-    tag: String,
-    version: String,
-    published: String,
-}
-
 // ═══════════════════════════════════════════════════════════════════════════
 // MAIN WIZARD FLOW
 // ═══════════════════════════════════════════════════════════════════════════
@@ -74,15 +94,43 @@ fn parse_args() -> Args {
     // Show help
     if args.iter().any(|a| a == "--help" || a == "-h") {
         eprintln!("codex-xtreme - Build your perfect Codex binary\n");
-        eprintln!("Usage: codex-xtreme [OPTIONS]\n");
-        eprintln!("Options:");
+        eprintln!("Usage: codex-xtreme [OPTIONS]");
+        eprintln!("       codex-xtreme build --recipe <FILE> [OPTIONS]");
+        eprintln!("       codex-xtreme inspect <BINARY> [--json]");
+        eprintln!("       codex-xtreme list | use <ID> | rollback | remove <ID>\n");
+        eprintln!("Commands:");
+        eprintln!("  build --recipe FILE  Build non-interactively from a TOML recipe");
+        eprintln!("  inspect BINARY       Show how a binary was built (tag, patches, flags)");
+        eprintln!("  list                 List installed builds (* marks the current one)");
+        eprintln!("  use ID               Make an installed build current (ID prefix is enough)");
+        eprintln!("  rollback             Switch back to the previously used build");
+        eprintln!("  remove ID            Delete an installed build");
+        eprintln!("\nOptions:");
         eprintln!("  --dev, -d    Developer mode (cherry-pick commits, extra options)");
         eprintln!("  --tui        Run the full-screen TUI (same workflow, different UI)");
         eprintln!("  --detect-cpu-only   Print CPU detection result and exit");
         eprintln!("  --jobs, -j N Limit parallel cargo jobs (reduces CPU usage)");
+        eprintln!("  --events=json       Stream build events as NDJSON (stdout by default)");
+        eprintln!("  --events-file PATH  Write the event stream to PATH (e.g. /dev/fd/3)");
+        eprintln!("  --cache-dir PATH    Build cache directory (local or shared filesystem)");
+        eprintln!("  --no-cache          Always rebuild; do not read or write the build cache");
+        eprintln!("  --worktree          Build in ~/.cache/codex-xtreme/worktrees/<tag> (branch xtreme/<tag>)");
+        eprintln!("                      instead of checking out the tag in your clone");
+        eprintln!("  --stash             build: stash uncommitted changes before checkout");
+        eprintln!("  --restore           build: restore your branch and pop the stash afterwards");
+        eprintln!("  --offline           Skip fetching tags and history; run cargo with --offline");
+        eprintln!("  --no-cleanup        Keep patched files in your clone after an in-place build");
+        eprintln!(
+            "  --strict            Refuse to build when selected patches edit the same lines"
+        );
+        eprintln!("  --require-all-patches  Stop when any selected patch fails to apply");
         eprintln!("  --help, -h   Show this help message");
         eprintln!("\nEnvironment:");
         eprintln!("  RUST_LOG=debug    Enable debug logging");
+        eprintln!("  CODEX_XTREME_CACHE_DIR=PATH  Default build cache directory");
+        eprintln!("  CODEX_XTREME_REPO_ROOTS=DIR:DIR  Where to look for Codex checkouts");
+        eprintln!("  CODEX_XTREME_REPO_DEPTH=N    How deep to search below each root (default 2)");
+        eprintln!("  CODEX_PATCHER_PATCHES=DIR:DIR  Patch directories searched first");
         std::process::exit(0);
     }
 
@@ -123,6 +171,18 @@ fn parse_args() -> Args {
         Ok(found)
     }
 
+    /// Value of `--name VALUE` or `--name=VALUE`, if present.
+    fn flag_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+        args.iter().enumerate().find_map(|(idx, arg)| {
+            if arg == name {
+                args.get(idx + 1).map(String::as_str)
+            } else {
+                arg.strip_prefix(name)
+                    .and_then(|rest| rest.strip_prefix('='))
+            }
+        })
+    }
+
     let cargo_jobs = match parse_cargo_jobs(&args) {
         Ok(v) => v,
         Err(e) => {
@@ -131,11 +191,68 @@ fn parse_args() -> Args {
         }
     };
 
+    let subcommand = match args.get(1).map(String::as_str) {
+        Some("build") => match flag_value(&args, "--recipe") {
+            Some(recipe) => Subcommand::Build {
+                recipe: PathBuf::from(recipe),
+            },
+            None => {
+                eprintln!("error: build requires --recipe <FILE>");
+                std::process::exit(2);
+            }
+        },
+        Some("inspect") => match args.get(2).filter(|a| !a.starts_with('-')) {
+            Some(path) => Subcommand::Inspect {
+                path: PathBuf::from(path),
+                json: args.iter().any(|a| a == "--json"),
+            },
+            None => {
+                eprintln!("error: inspect requires a binary path");
+                std::process::exit(2);
+            }
+        },
+        Some("list") => Subcommand::Store(StoreCommand::List),
+        Some("rollback") => Subcommand::Store(StoreCommand::Rollback),
+        Some(cmd @ ("use" | "remove")) => match args.get(2).filter(|a| !a.starts_with('-')) {
+            Some(id) if cmd == "use" => Subcommand::Store(StoreCommand::Use(id.clone())),
+            Some(id) => Subcommand::Store(StoreCommand::Remove(id.clone())),
+            None => {
+                eprintln!("error: {cmd} requires a build id (see `codex-xtreme list`)");
+                std::process::exit(2);
+            }
+        },
+        _ => Subcommand::Wizard,
+    };
+
+    let events = match flag_value(&args, "--events").map(EventFormat::parse) {
+        Some(Ok(format)) => Some(format),
+        Some(Err(e)) => {
+            eprintln!("error: {e}");
+            std::process::exit(2);
+        }
+        None => None,
+    };
+    let events_file = flag_value(&args, "--events-file").map(PathBuf::from);
+    let cache_dir =
+        flag_value(&args, "--cache-dir").map(|dir| PathBuf::from(shellexpand::tilde(dir).as_ref()));
+
     Args {
+        subcommand,
         dev_mode: args.iter().any(|a| a == "--dev" || a == "-d"),
         detect_cpu_only: args.iter().any(|a| a == "--detect-cpu-only"),
         tui: args.iter().any(|a| a == "--tui"),
         cargo_jobs,
+        events,
+        events_file,
+        cache_dir,
+        no_cache: args.iter().any(|a| a == "--no-cache"),
+        worktree: args.iter().any(|a| a == "--worktree"),
+        stash: args.iter().any(|a| a == "--stash"),
+        restore: args.iter().any(|a| a == "--restore"),
+        offline: args.iter().any(|a| a == "--offline"),
+        no_cleanup: args.iter().any(|a| a == "--no-cleanup"),
+        strict: args.iter().any(|a| a == "--strict"),
+        require_all: args.iter().any(|a| a == "--require-all-patches"),
     }
 }
 
@@ -151,16 +268,56 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if let Subcommand::Inspect { path, json } = &args.subcommand {
+        return run_inspect(path, *json);
+    }
+
+    if let Subcommand::Store(command) = &args.subcommand {
+        return run_store_command(command);
+    }
+
+    if let Subcommand::Build { recipe } = &args.subcommand {
+        tracing_subscriber::fmt()
+            .with_env_filter(
+                tracing_subscriber::EnvFilter::from_default_env()
+                    .add_directive(tracing::Level::WARN.into()),
+            )
+            .with_target(false)
+            .with_writer(std::io::stderr)
+            .init();
+
+        if let Err(err) = check_prerequisites() {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        let events = open_event_writer(&args, true)?;
+        return run_recipe_build(recipe, &args, events);
+    }
+
     // `codex-xtreme --tui` runs the same workflow via the ratatui UI.
     if args.tui {
+        // The TUI owns the terminal: the stream can only go to a file.
+        if args.events.is_some() && args.events_file.is_none() {
+            eprintln!("error: --events with --tui requires --events-file PATH");
+            std::process::exit(2);
+        }
         if let Err(err) = check_prerequisites() {
             eprintln!("{err}");
             std::process::exit(1);
         }
 
+        let events = open_event_writer(&args, false)?;
         let rt = tokio::runtime::Runtime::new().context("Failed to create tokio runtime")?;
         return rt
-            .block_on(codex_xtreme::tui::run_app(args.dev_mode, args.cargo_jobs))
+            .block_on(codex_xtreme::tui::run_app(
+                args.dev_mode,
+                args.cargo_jobs,
+                args.worktree,
+                args.offline,
+                args.strict,
+                args.require_all,
+                events,
+            ))
             .map_err(|e| anyhow::anyhow!(e));
     }
 
@@ -186,6 +343,43 @@ fn main() -> Result<()> {
         std::process::exit(1);
     }
 
+    // The wizard owns the terminal, so the event stream must go to a file.
+    let mut events = open_event_writer(&args, false)?;
+    let start_time = Instant::now();
+    let result = run_wizard(&args, &mut events);
+    if let Some(writer) = events.as_mut() {
+        writer.result(
+            result
+                .as_ref()
+                .map(|p| p.as_path())
+                .map_err(|e| format!("{e:#}")),
+            start_time.elapsed(),
+        );
+    }
+    result.map(|_| ())
+}
+
+/// Open the `--events` stream, if requested.
+fn open_event_writer(args: &Args, allow_stdout: bool) -> Result<Option<EventWriter>> {
+    let Some(EventFormat::Json) = args.events else {
+        return Ok(None);
+    };
+    match &args.events_file {
+        Some(path) => EventWriter::create(path).map(Some),
+        None if allow_stdout => Ok(Some(EventWriter::stdout())),
+        None => bail!("--events=json with the interactive wizard requires --events-file PATH"),
+    }
+}
+
+/// Write an event to the `--events` stream, if one is open.
+fn tee_event(events: &mut Option<EventWriter>, ev: &codex_xtreme::workflow::Event) {
+    if let Some(writer) = events.as_mut() {
+        writer.event(ev);
+    }
+}
+
+/// The interactive cliclack wizard. Returns the path of the finished binary.
+fn run_wizard(args: &Args, events: &mut Option<EventWriter>) -> Result<PathBuf> {
     if args.dev_mode {
         intro("🚀 CODEX XTREME [DEV MODE] - Build Your Perfect Codex")?;
     } else {
@@ -211,48 +405,84 @@ fn main() -> Result<()> {
         rust_ver
     ));
 
+    // Remembered defaults and last-used choices
+    let mut config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            log::warning(format!("{:#} (using built-in defaults)", e))?;
+            Config::default()
+        }
+    };
+    let defaults = config.defaults.clone();
+    let cargo_jobs = config.cargo_jobs(args.cargo_jobs);
+
     // ───────────────────────────────────────────────────────────────────────
     // PHASE 2: Repository Selection
     // ───────────────────────────────────────────────────────────────────────
-    let repos = find_codex_repos()?;
-
-    let repo = if repos.is_empty() {
-        log::info("No existing Codex repositories found")?;
-        if confirm("Clone fresh from GitHub?")
-            .initial_value(true)
-            .interact()?
-        {
-            clone_codex()?
-        } else {
-            bail!("No repository selected");
-        }
-    } else {
-        let mut items: Vec<(String, String, String)> = repos
+    let mut repos = codex_xtreme::core::find_codex_repos()?;
+    if let Some(preferred) = config.preferred_repo() {
+        let known = repos
             .iter()
-            .map(|r| {
-                (
-                    r.path.display().to_string(),
-                    format!("{}", r.path.display()),
-                    format!("{} | {}", r.branch, r.age),
-                )
-            })
-            .collect();
-        items.push((
-            "__clone__".into(),
-            "Clone fresh".into(),
-            "Get latest from GitHub".into(),
-        ));
+            .any(|r| preferred.canonicalize().is_ok_and(|p| p == r.path));
+        if !known {
+            match codex_xtreme::core::open_codex_repo(&preferred.to_string_lossy()) {
+                Ok(repo) => repos.insert(0, repo),
+                Err(e) => log::warning(format!("Preferred repo from config: {:#}", e))?,
+            }
+        }
+    }
 
-        let selected: String = select("Select Codex repository").items(&items).interact()?;
+    if repos.is_empty() {
+        log::info(
+            "No existing Codex repositories found (set CODEX_XTREME_REPO_ROOTS to search elsewhere)",
+        )?;
+    }
+    let mut items: Vec<(String, String, String)> = repos
+        .iter()
+        .map(|r| {
+            (
+                r.path.display().to_string(),
+                format!("{}", r.path.display()),
+                format!("{} | {}", r.branch, r.age),
+            )
+        })
+        .collect();
+    items.push((
+        "__path__".into(),
+        "Enter a path".into(),
+        "Use a checkout somewhere else".into(),
+    ));
+    items.push((
+        "__clone__".into(),
+        "Clone fresh".into(),
+        "Get latest from GitHub".into(),
+    ));
 
-        if selected == "__clone__" {
-            clone_codex()?
-        } else {
-            repos
-                .into_iter()
-                .find(|r| r.path.display().to_string() == selected)
-                .expect("Selected repo not found")
+    let mut repo_select = select("Select Codex repository").items(&items);
+    if let Some(initial) = config.initial_repo().and_then(|p| p.canonicalize().ok()) {
+        if repos.iter().any(|r| r.path == initial) {
+            repo_select = repo_select.initial_value(initial.display().to_string());
+        }
+    }
+    let selected: String = repo_select.interact()?;
+
+    let repo = match selected.as_str() {
+        "__clone__" => clone_codex()?,
+        "__path__" => {
+            let path: String = input("Path to the Codex repo (the directory containing codex-rs/)")
+                .placeholder("~/dev/codex")
+                .validate(|value: &String| {
+                    codex_xtreme::core::open_codex_repo(value)
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                })
+                .interact()?;
+            codex_xtreme::core::open_codex_repo(&path)?
         }
+        _ => repos
+            .into_iter()
+            .find(|r| r.path.display().to_string() == selected)
+            .expect("Selected repo not found"),
     };
 
     let workspace = repo.workspace_path();
@@ -269,169 +499,332 @@ fn main() -> Result<()> {
         CODEX_RS_SUBDIR
     ))?;
 
+    // A previous build may have been interrupted half-way: offer to undo what it left behind.
+    if let Some(leftovers) = codex_xtreme::cleanup::detect_leftovers(&repo.path) {
+        log::warning(leftovers.describe().join("\n"))?;
+        if confirm("Clean it up now?").initial_value(true).interact()? {
+            let outcome = codex_xtreme::cleanup::clean_leftovers(&repo.path, &leftovers)?;
+            let tag = leftovers.journal.as_ref().map_or("", |j| j.tag.as_str());
+            log::success(outcome.summary(tag))?;
+            if let Some(journal) = &leftovers.journal {
+                let point = journal.restore_point();
+                let prompt = if point.stash.is_some() {
+                    format!(
+                        "Restore {} and pop your stashed changes?",
+                        point.previous_ref
+                    )
+                } else {
+                    format!("Restore {}?", point.previous_ref)
+                };
+                if confirm(prompt)
+                    .initial_value(point.stash.is_some())
+                    .interact()?
+                {
+                    let outcome = codex_xtreme::core::restore_checkout(&repo.path, &point)?;
+                    let summary = codex_xtreme::core::describe_restore(&point, &outcome);
+                    match outcome {
+                        codex_xtreme::core::RestoreOutcome::Restored => log::success(summary)?,
+                        _ => log::warning(summary)?,
+                    }
+                }
+            }
+        }
+    }
+
     // ───────────────────────────────────────────────────────────────────────
     // PHASE 3: Version Selection
     // ───────────────────────────────────────────────────────────────────────
     let sp = spinner();
-    sp.start("Fetching releases from GitHub...");
-    fetch_repo(&repo.path)?;
-    let releases = get_github_releases(&repo.path)?;
+    let fetch_error = if args.offline {
+        sp.start("Reading local tags (offline)...");
+        None
+    } else {
+        sp.start("Fetching releases from GitHub...");
+        codex_xtreme::core::fetch_repo(&repo.path, codex_xtreme::core::FETCH_TIMEOUT).err()
+    };
+    let releases = codex_xtreme::core::get_releases(&repo.path)?;
     let current_version = get_current_version(&repo.path);
+    let fetched = codex_xtreme::core::last_fetch_label(&repo.path);
     sp.stop(format!(
-        "Found {} releases (current: {})",
+        "Found {} releases (current: {}; tags {}{})",
         releases.len(),
-        current_version.as_deref().unwrap_or("unknown")
+        current_version.as_deref().unwrap_or("unknown"),
+        fetched,
+        if args.offline { ", offline" } else { "" }
     ));
+    if let Some(e) = fetch_error {
+        log::warning(format!(
+            "Could not fetch tags ({:#}); the list may be stale (tags {})",
+            e, fetched
+        ))?;
+    }
 
     // Let user select target version
     let target_tag = if releases.is_empty() {
         log::warning("No releases found, using main branch")?;
         "main".to_string()
     } else {
-        // Separate stable and pre-release versions
-        let stable_releases: Vec<_> = releases
-            .iter()
-            .filter(|r| {
-                !r.version.contains("alpha")
-                    && !r.version.contains("beta")
-                    && !r.version.contains("rc")
-            })
-            .collect();
-
-        let display_releases = if stable_releases.is_empty() {
-            // No stable releases, show all
-            &releases
+        use codex_xtreme::releases::{self as semver_releases, ReleaseFilter};
+        // Releases with a changelog summary in the hint (one `git log` each)
+        const SUMMARIZED: usize = 15;
+
+        let versions: Vec<&str> = releases.iter().map(|r| r.version.as_str()).collect();
+        let has_stable = versions.iter().any(|v| !semver_releases::is_prerelease(v));
+        let has_prereleases = versions.iter().any(|v| semver_releases::is_prerelease(v));
+        let include_prereleases = !has_stable
+            || (has_prereleases
+                && confirm("Include pre-releases (alpha/beta/rc)?")
+                    .initial_value(false)
+                    .interact()?);
+        let shown = semver_releases::select_releases(
+            &versions,
+            &ReleaseFilter {
+                include_prereleases,
+                ..Default::default()
+            },
+        );
+        let latest = codex_xtreme::core::latest_stable_release(&releases);
+
+        // Long lists: pick the minor line first (newest line first), then the release in it.
+        let candidates = if shown.len() > SUMMARIZED {
+            let groups = semver_releases::group_by_minor(&versions, &shown);
+            let line_items: Vec<(usize, String, String)> = groups
+                .iter()
+                .enumerate()
+                .map(|(g, (line, members))| {
+                    let newest = &releases[members[0]];
+                    let count = if members.len() == 1 {
+                        "1 release".to_string()
+                    } else {
+                        format!("{} releases", members.len())
+                    };
+                    (
+                        g,
+                        format!("{}.x", line),
+                        format!("latest {} · {}", newest.version, count),
+                    )
+                })
+                .collect();
+            let mut line_select = select("Select release line (type to filter)")
+                .items(&line_items)
+                .filter_mode();
+            let last_line = groups.iter().position(|(_, members)| {
+                members
+                    .iter()
+                    .any(|&i| Some(&releases[i].tag) == config.last.tag.as_ref())
+            });
+            if let Some(g) = last_line {
+                line_select = line_select.initial_value(g);
+            }
+            let group = line_select.interact()?;
+            groups[group].1.clone()
         } else {
-            // Show stable first, then add option for pre-releases
-            &releases
+            shown
         };
 
-        let mut release_items: Vec<(String, String, String)> = display_releases
+        let release_items: Vec<(String, String, String)> = candidates
             .iter()
             .enumerate()
-            .map(|(i, r)| {
-                let is_prerelease = r.version.contains("alpha")
-                    || r.version.contains("beta")
-                    || r.version.contains("rc");
-                let label = if i == 0 && !is_prerelease {
+            .map(|(n, &i)| {
+                let r = &releases[i];
+                let label = if Some(i) == latest && !semver_releases::is_prerelease(&r.version) {
                     format!("{} (latest stable)", r.version)
-                } else if i == 0 {
+                } else if Some(i) == latest {
                     format!("{} (latest)", r.version)
                 } else {
                     r.version.clone()
                 };
-                let hint = if Some(&r.version) == current_version.as_ref() {
+                let mut hint = if Some(&r.version) == current_version.as_ref() {
                     format!("{} - CURRENT", r.published)
                 } else {
                     r.published.clone()
                 };
+                // What this release brings over its predecessor (only for the first few)
+                if n < SUMMARIZED {
+                    if let Some(previous) = semver_releases::predecessor(&versions, i) {
+                        if let Ok(entries) = codex_xtreme::core::release_changelog(
+                            &repo.path,
+                            &r.tag,
+                            &releases[previous].tag,
+                        ) {
+                            hint.push_str(" · ");
+                            hint.push_str(&codex_xtreme::core::summarize_changelog(&entries));
+                        }
+                    }
+                }
                 (r.tag.clone(), label, hint)
             })
             .collect();
 
-        // Limit to reasonable number (last 15 releases)
-        release_items.truncate(15);
-
-        select("Select target version")
+        let mut version_select = select("Select target version (type to filter)")
             .items(&release_items)
-            .interact()?
-            .to_string()
+            .filter_mode();
+        if let Some(tag) = &config.last.tag {
+            if release_items.iter().any(|(t, _, _)| t == tag) {
+                version_select = version_select.initial_value(tag.clone());
+            }
+        }
+        version_select.interact()?.to_string()
     };
 
-    // Checkout the target version
-    let sp = spinner();
-    sp.start(format!("Checking out {}...", target_tag));
-    codex_xtreme::core::checkout_version(&repo.path, &target_tag)?;
-    sp.stop(format!("Checked out {}", target_tag));
+    // An in-place checkout needs a clean tree: show what would be stashed and ask first.
+    let mut allow_stash = false;
+    if !args.worktree {
+        let dirty = codex_xtreme::core::dirty_files(&repo.path);
+        if !dirty.is_empty() {
+            const SHOWN: usize = 20;
+            let mut listing = dirty.iter().take(SHOWN).cloned().collect::<Vec<_>>();
+            if dirty.len() > SHOWN {
+                listing.push(format!("… and {} more", dirty.len() - SHOWN));
+            }
+            log::warning(format!(
+                "{} has uncommitted changes:\n{}",
+                repo.path.display(),
+                listing.join("\n")
+            ))?;
+            allow_stash = confirm("Stash these changes (git stash) and continue?")
+                .initial_value(false)
+                .interact()?;
+            if !allow_stash {
+                bail!(
+                    "Aborted: commit or stash your changes, or rerun with --worktree to build without touching this clone"
+                );
+            }
+        }
+    }
+
+    // Cherry-pick browsing, the conflict check and the dry run read the tag from git, so
+    // make sure a shallow clone has it. Nothing is checked out until the build starts.
+    if !args.offline {
+        ensure_history(&repo.path, &target_tag, &[])?;
+    }
+    let workspace = repo.workspace_path();
 
     // ───────────────────────────────────────────────────────────────────────
     // PHASE 4: Cherry-pick Commits (--dev mode only)
     // ───────────────────────────────────────────────────────────────────────
-    //
-    // NOTE: This happens immediately after checkout while the working tree is clean.
-    // Cherry-picking after applying patches can fail because git refuses to operate
-    // with local modifications.
+    let mut cherry_pick_shas: Vec<String> = Vec::new();
+    let mut cherry_pick_options = codex_xtreme::core::CherryPickOptions::default();
     if args.dev_mode {
-        log::info(format!(
-            "Dev mode: View commits at https://github.com/openai/codex/compare/{}...main",
-            target_tag
-        ))?;
-
-        let cherry_pick_input: String =
-            input("Cherry-pick commits (comma-separated SHAs, or empty to skip)")
-                .placeholder("abc1234, def5678")
-                .default_input("")
-                .interact()?;
-
-        let cherry_pick_shas: Vec<String> = cherry_pick_input
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .filter(|s| {
-                // Validate SHA format: 7-40 hex characters
-                let valid =
-                    s.len() >= 7 && s.len() <= 40 && s.chars().all(|c| c.is_ascii_hexdigit());
-                if !valid {
-                    eprintln!("Warning: Invalid SHA format '{}', skipping", s);
-                }
-                valid
-            })
-            .collect();
+        cherry_pick_shas = browse_cherry_picks(&repo.path, &target_tag)?;
 
         if !cherry_pick_shas.is_empty() {
-            let sp = spinner();
-            sp.start(format!(
-                "Cherry-picking {} commits...",
-                cherry_pick_shas.len()
-            ));
-            let outcome = codex_xtreme::core::cherry_pick_commits(&repo.path, &cherry_pick_shas)?;
-            sp.stop("Cherry-pick complete");
-
-            if !outcome.skipped.is_empty() {
-                log::warning(format!(
-                    "Skipped {} conflicting commit(s): {}",
-                    outcome.skipped.len(),
-                    outcome
-                        .skipped
-                        .iter()
-                        .map(|s| &s[..7.min(s.len())])
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ))?;
-            }
+            use codex_xtreme::core::CherryPickStrategy;
+            cherry_pick_options = select("If a commit conflicts")
+                .item(
+                    codex_xtreme::core::CherryPickOptions::default(),
+                    "Skip it",
+                    "report conflicted files and likely prerequisites",
+                )
+                .item(
+                    codex_xtreme::core::CherryPickOptions {
+                        retry_strategies: vec![
+                            CherryPickStrategy::Patience,
+                            CherryPickStrategy::Theirs,
+                        ],
+                        include_prerequisites: false,
+                    },
+                    "Retry with -X patience, then -X theirs",
+                    "theirs keeps the picked commit's side of conflicting hunks",
+                )
+                .item(
+                    codex_xtreme::core::CherryPickOptions {
+                        retry_strategies: vec![
+                            CherryPickStrategy::Patience,
+                            CherryPickStrategy::Theirs,
+                        ],
+                        include_prerequisites: true,
+                    },
+                    "Retry, then pull in prerequisite commits",
+                    "earlier upstream commits touching the same files",
+                )
+                .interact()?;
         }
     }
 
     // ───────────────────────────────────────────────────────────────────────
     // PHASE 5: Patch Selection
     // ───────────────────────────────────────────────────────────────────────
-    let available_patches = codex_xtreme::core::get_available_patches()?;
-
+    let catalog = codex_xtreme::core::get_available_patches(Some(&repo.path))?;
+    if !catalog.warnings.is_empty() {
+        log::warning(catalog.warnings.join("\n"))?;
+    }
+    let available_patches = &catalog.patches;
+    let mut selected_patches: Vec<PathBuf> = Vec::new();
+    // Patch ids switched off inside selected files (last run's choices where still present).
+    // Required files (every file with --require-all-patches) have to apply in full, so they
+    // keep all ids.
+    let mut disabled_patches = codex_xtreme::workflow::DisabledPatches::new();
+    for (path, patch_config) in available_patches {
+        if args.require_all || catalog.order.is_required(path) {
+            continue;
+        }
+        let ids: Vec<String> = config
+            .disabled_patch_ids(path)
+            .into_iter()
+            .filter(|id| patch_config.patches.iter().any(|p| &p.id == id))
+            .collect();
+        if !ids.is_empty() {
+            disabled_patches.insert(path.clone(), ids);
+        }
+    }
     if available_patches.is_empty() {
         log::warning("No patches found. Skipping patch selection.")?;
     } else {
-        let patch_items: Vec<(PathBuf, String, String)> = available_patches
+        // Variants of a family (privacy, privacy-v0.105-alpha13, ...) are listed together.
+        let mut by_family: Vec<&(PathBuf, codex_patcher::PatchConfig)> =
+            available_patches.iter().collect();
+        by_family.sort_by_key(|(path, _)| codex_xtreme::core::patch_family(path));
+        let patch_items: Vec<(PathBuf, String, String)> = by_family
             .iter()
-            .map(|(path, config)| {
-                let compatible = codex_xtreme::core::is_patch_compatible(
+            .enumerate()
+            .map(|(idx, (path, config))| {
+                let incompatible = codex_xtreme::core::incompatibility(
                     config.meta.version_range.as_deref(),
                     &target_tag,
                 );
+                let family = codex_xtreme::core::patch_family(path);
+                let branch = if idx > 0
+                    && codex_xtreme::core::patch_family(&by_family[idx - 1].0) == family
+                {
+                    "└ "
+                } else {
+                    ""
+                };
+                let count = match disabled_patches.get(path) {
+                    Some(ids) => format!(
+                        "{}/{}",
+                        config.patches.len().saturating_sub(ids.len()),
+                        config.patches.len()
+                    ),
+                    None => config.patches.len().to_string(),
+                };
                 (
                     path.clone(),
-                    format!(
-                        "{} ({}){}",
-                        config.meta.name,
-                        config.patches.len(),
-                        if compatible { "" } else { " [incompatible]" }
-                    ),
-                    config.meta.description.clone().unwrap_or_default(),
+                    match (&incompatible, &config.meta.version_range) {
+                        (Some(_), Some(range)) => {
+                            format!(
+                                "{}{} ({}) [needs {}]",
+                                branch, config.meta.name, count, range
+                            )
+                        }
+                        _ => format!("{}{} ({})", branch, config.meta.name, count),
+                    },
+                    match (&incompatible, catalog.source_of(path)) {
+                        (Some(reason), _) => format!("✗ {}", reason),
+                        (None, Some(dir)) if catalog.dirs.len() > 1 => format!(
+                            "{} · {}",
+                            config.meta.description.clone().unwrap_or_default(),
+                            dir.source
+                        ),
+                        _ => config.meta.description.clone().unwrap_or_default(),
+                    },
                 )
             })
             .collect();
 
-        // Default: select only compatible patches (matches TUI behavior).
-        let defaults: Vec<PathBuf> = available_patches
+        // Default: the last selection, else only compatible patches (matches TUI behavior).
+        let compatible: Vec<PathBuf> = available_patches
             .iter()
             .filter(|(_, config)| {
                 codex_xtreme::core::is_patch_compatible(
@@ -442,30 +835,74 @@ fn main() -> Result<()> {
             .map(|(p, _)| p.clone())
             .collect();
 
-        let selected_patches: Vec<PathBuf> = multiselect("Select patches to apply")
-            .items(&patch_items)
-            .initial_values(defaults)
-            .required(false)
-            .interact()?;
-
-        if !selected_patches.is_empty() {
-            let sp = spinner();
-            sp.start(format!("Applying {} patches...", selected_patches.len()));
-            codex_xtreme::workflow::apply_patches(&workspace, &selected_patches, |ev| match ev {
-                codex_xtreme::workflow::Event::Phase(_) => {}
-                codex_xtreme::workflow::Event::Progress(_) => {}
-                codex_xtreme::workflow::Event::CurrentItem(s) => sp.set_message(s),
-                codex_xtreme::workflow::Event::Log(s) => {
-                    let _ = log::info(s);
-                }
-                codex_xtreme::workflow::Event::PatchFileApplied(name) => {
-                    let _ = log::success(format!("Applied patch file: {}", name));
-                }
-                codex_xtreme::workflow::Event::PatchFileSkipped { name, reason } => {
-                    let _ = log::warning(format!("Skipped patch file: {} ({})", name, reason));
+        let available: Vec<PathBuf> = available_patches.iter().map(|(p, _)| p.clone()).collect();
+        let initial = config.initial_patches(&available, compatible);
+        // One compatible variant per selected family, so a family never silently drops out.
+        let ranges: Vec<(&Path, Option<&str>)> = available_patches
+            .iter()
+            .map(|(p, c)| (p.as_path(), c.meta.version_range.as_deref()))
+            .collect();
+        let (mut initial, notes) =
+            codex_xtreme::core::pick_patch_variants(&ranges, &initial, &target_tag);
+        if !notes.is_empty() {
+            log::info(notes.join("\n"))?;
+        }
+        loop {
+            selected_patches = multiselect("Select patches to apply")
+                .items(&patch_items)
+                .initial_values(initial)
+                .required(false)
+                .interact()?;
+            // Pull in declared requirements and put the files in build order.
+            match order_patches(&catalog.order, &selected_patches, &target_tag) {
+                Ok(ordered) => selected_patches = ordered,
+                Err(e) => {
+                    log::error(format!("{:#}", e))?;
+                    initial = selected_patches.clone();
+                    continue;
                 }
-            })?;
-            sp.stop("Patches applied");
+            }
+            choose_patch_ids(
+                available_patches,
+                &selected_patches,
+                &mut disabled_patches,
+                |path| args.require_all || catalog.order.is_required(path),
+            )?;
+            let deselected = resolve_patch_conflicts(
+                &workspace,
+                &target_tag,
+                &mut selected_patches,
+                &mut disabled_patches,
+                |path| args.require_all || catalog.order.is_required(path),
+                args.strict,
+            )?;
+            if deselected {
+                // Back to the selection, so requirements are checked without the dropped file.
+                log::info("Deselected a required file; check the selection again")?;
+                initial = selected_patches.clone();
+                continue;
+            }
+            if selected_patches.is_empty()
+                || !confirm("Preview the changes first (dry run)?")
+                    .initial_value(false)
+                    .interact()?
+            {
+                break;
+            }
+            preview_patches(
+                &workspace,
+                &target_tag,
+                &selected_patches,
+                &disabled_patches,
+            )?;
+            if confirm("Apply these patches?")
+                .initial_value(true)
+                .interact()?
+            {
+                break;
+            }
+            // Back to the selection, keeping what was picked.
+            initial = selected_patches.clone();
         }
     }
 
@@ -483,6 +920,11 @@ fn main() -> Result<()> {
             "Standard Release",
             "Default cargo release, ~3min build",
         )
+        .initial_value(if defaults.profile == "release" {
+            "release"
+        } else {
+            "xtreme"
+        })
         .interact()?
         .to_string();
 
@@ -490,7 +932,7 @@ fn main() -> Result<()> {
         "Optimize for your CPU? ({})",
         cpu_target.display_name()
     ))
-    .initial_value(true)
+    .initial_value(defaults.optimize_for_cpu)
     .interact()?;
 
     let has_bolt = codex_xtreme::core::has_bolt();
@@ -521,6 +963,11 @@ fn main() -> Result<()> {
         "Custom",
         "Choose mold/BOLT manually (BOLT disables mold)",
     );
+    opt_select = opt_select.initial_value(match defaults.optimization_mode(has_mold, has_bolt) {
+        codex_xtreme::workflow::OptimizationMode::RunFast => "run_fast",
+        codex_xtreme::workflow::OptimizationMode::BuildFast => "build_fast",
+        codex_xtreme::workflow::OptimizationMode::Custom => "custom",
+    });
     let optimization_mode: String = opt_select.interact()?.to_string();
 
     let opt_mode = match optimization_mode.as_str() {
@@ -535,7 +982,7 @@ fn main() -> Result<()> {
     if opt_mode == codex_xtreme::workflow::OptimizationMode::Custom {
         if has_mold {
             optimization.use_mold = confirm("Use mold linker? (faster linking)")
-                .initial_value(true)
+                .initial_value(defaults.use_mold)
                 .interact()?;
         } else {
             optimization.use_mold = false;
@@ -543,7 +990,7 @@ fn main() -> Result<()> {
 
         if has_bolt {
             optimization.use_bolt = confirm("Use BOLT optimization? (runtime performance)")
-                .initial_value(true)
+                .initial_value(defaults.use_bolt)
                 .interact()?;
         } else {
             optimization.use_bolt = false;
@@ -553,111 +1000,206 @@ fn main() -> Result<()> {
     optimization.enforce_invariants();
 
     let strip_symbols = confirm("Strip symbols? (smaller binary)")
-        .initial_value(true)
+        .initial_value(defaults.strip_symbols)
         .interact()?;
 
+    let options = codex_xtreme::workflow::BuildOptions {
+        profile: profile.clone(),
+        cpu_target: if use_cpu_opt {
+            Some(cpu_target.rustc_target_cpu().to_string())
+        } else {
+            None
+        },
+        optimization: optimization.clone(),
+        strip_symbols,
+        cargo_jobs,
+        offline: args.offline,
+    };
+
     // ───────────────────────────────────────────────────────────────────────
-    // PHASE 7: Build
+    // PHASE 7: After the Build
     // ───────────────────────────────────────────────────────────────────────
-    if profile == "xtreme" {
-        codex_xtreme::workflow::inject_xtreme_profile(&workspace)?;
-    }
+    let run_tests = confirm("Run quick verification tests after the build?")
+        .initial_value(defaults.run_tests)
+        .interact()?;
+    let setup_alias = confirm("Set up shell alias?")
+        .initial_value(defaults.setup_alias)
+        .interact()?;
 
-    log::info("Starting build (this may take a while)...")?;
+    // An in-place build changes the clone; ask now how to leave it, so the pipeline can
+    // clean up and restore the branch even when the build fails.
+    let mut cleanup = defaults.cleanup;
+    let mut restore_checkout = false;
+    if !args.worktree {
+        if !args.no_cleanup {
+            cleanup = confirm(format!(
+                "Afterwards, restore the files this build changes in {}?",
+                repo.path.display()
+            ))
+            .initial_value(defaults.cleanup)
+            .interact()?;
+        }
+        // Restoring needs a clean tree, so it is not offered when the patched sources stay.
+        if cleanup && !args.no_cleanup {
+            let previous_ref = codex_xtreme::core::current_ref(&repo.path)?;
+            let prompt = if allow_stash {
+                format!(
+                    "Afterwards, check out {} again and pop your stashed changes?",
+                    previous_ref
+                )
+            } else {
+                format!("Afterwards, check out {} again?", previous_ref)
+            };
+            restore_checkout = confirm(prompt).initial_value(allow_stash).interact()?;
+        }
+    }
 
-    let build_sp = spinner();
-    build_sp.start("Compiling...");
-    let mut binary_path = codex_xtreme::workflow::build_with_autofix(
-        &workspace,
-        &profile,
-        if use_cpu_opt {
-            Some(cpu_target.rustc_target_cpu())
-        } else {
+    let params = codex_xtreme::workflow::PipelineParams {
+        repo_path: repo.path.clone(),
+        version: target_tag.clone(),
+        cherry_pick_shas: cherry_pick_shas.clone(),
+        cherry_pick_options: cherry_pick_options.clone(),
+        patches: selected_patches.clone(),
+        disabled_patches: disabled_patches
+            .iter()
+            .filter(|(path, _)| selected_patches.contains(path))
+            .map(|(path, ids)| (path.clone(), ids.clone()))
+            .collect(),
+        build_options: options.clone(),
+        run_tests,
+        setup_alias,
+        cache_dir: if args.no_cache {
             None
+        } else {
+            args.cache_dir
+                .clone()
+                .or_else(codex_xtreme::cache::default_dir)
         },
-        &optimization,
-        args.cargo_jobs,
-        |ev| match ev {
-            codex_xtreme::workflow::Event::Phase(_) => {}
-            codex_xtreme::workflow::Event::Progress(_) => {}
-            codex_xtreme::workflow::Event::CurrentItem(s) => build_sp.set_message(s),
-            codex_xtreme::workflow::Event::Log(_) => {}
-            codex_xtreme::workflow::Event::PatchFileApplied(_) => {}
-            codex_xtreme::workflow::Event::PatchFileSkipped { .. } => {}
-        },
-    )?;
-    build_sp.stop("Compiled");
-
-    log::success(format!("Build complete: {}", binary_path.display()))?;
+        use_worktree: args.worktree,
+        allow_stash,
+        restore_checkout,
+        cleanup: cleanup && !args.no_cleanup,
+        strict: args.strict,
+        require_all: args.require_all,
+    };
 
-    // BOLT post-link optimization
-    if optimization.use_bolt {
-        let sp = spinner();
-        sp.start("Running BOLT optimization (profile + reoptimize)...");
-        match codex_xtreme::workflow::run_bolt_optimization(&binary_path, |ev| match ev {
+    // ───────────────────────────────────────────────────────────────────────
+    // PHASE 8: Build
+    // ───────────────────────────────────────────────────────────────────────
+    log::info("Starting build (this may take a while)...")?;
+    let sp = spinner();
+    sp.start(format!("Checking out {}...", target_tag));
+    let mut required_failures = Vec::new();
+    let result = codex_xtreme::workflow::run_pipeline(&params, |ev| {
+        tee_event(events, &ev);
+        match ev {
             codex_xtreme::workflow::Event::Phase(_) => {}
             codex_xtreme::workflow::Event::Progress(_) => {}
             codex_xtreme::workflow::Event::CurrentItem(s) => sp.set_message(s),
-            codex_xtreme::workflow::Event::Log(_) => {}
-            codex_xtreme::workflow::Event::PatchFileApplied(_) => {}
-            codex_xtreme::workflow::Event::PatchFileSkipped { .. } => {}
-        }) {
-            Ok(bolted_path) => {
-                binary_path = bolted_path;
-                sp.stop("BOLT optimization complete");
+            codex_xtreme::workflow::Event::Log(s) => {
+                let _ = log::info(s);
+            }
+            codex_xtreme::workflow::Event::PatchFileApplied(name) => {
+                let _ = log::success(format!("Applied patch file: {}", name));
+            }
+            codex_xtreme::workflow::Event::PatchFileSkipped { name, reason } => {
+                let _ = log::warning(format!("Skipped patch file: {} ({})", name, reason));
             }
-            Err(e) => {
-                sp.stop(format!("BOLT failed: {} (using non-BOLT binary)", e));
+            codex_xtreme::workflow::Event::PatchFilePartial {
+                name,
+                applied,
+                total,
+                reason,
+            } => {
+                let _ = log::warning(format!(
+                    "Partly applied patch file: {} ({} of {} patches; {})",
+                    name, applied, total, reason
+                ));
+            }
+            codex_xtreme::workflow::Event::RequiredPatchFailed(failure) => {
+                required_failures.push(failure);
             }
         }
-    }
-
-    if strip_symbols {
-        let sp = spinner();
-        sp.start("Stripping symbols...");
-        match codex_xtreme::workflow::strip_binary(&binary_path) {
-            Ok(_) => sp.stop("Stripped symbols"),
-            Err(e) => sp.stop(format!("Strip failed: {} (continuing)", e)),
+    });
+    let outcome = match result {
+        Ok(outcome) => {
+            sp.stop("Build complete");
+            outcome
+        }
+        Err(e) => {
+            sp.error("Build failed");
+            if !required_failures.is_empty() {
+                let lines: Vec<String> = required_failures
+                    .iter()
+                    .map(|failure| format!("  ✗ {}", failure))
+                    .collect();
+                log::error(format!(
+                    "Required patches that did not apply:\n{}",
+                    lines.join("\n")
+                ))?;
+            }
+            return Err(e);
         }
+    };
+    // The store copy survives the next cargo build/clean; the alias follows `current`.
+    let binary_path = outcome
+        .installed
+        .map_or(outcome.binary_path, |build| build.binary);
+    if !args.worktree && !params.cleanup {
+        log::info(format!(
+            "Patched sources stay in {} (the next run offers to clean them up)",
+            repo.path.display()
+        ))?;
     }
 
-    // ───────────────────────────────────────────────────────────────────────
-    // PHASE 8: Test & Finish
-    // ───────────────────────────────────────────────────────────────────────
-    if confirm("Run quick verification tests?")
-        .initial_value(true)
-        .interact()?
-    {
-        let sp = spinner();
-        sp.start("Running verification tests...");
-        codex_xtreme::workflow::run_verification_tests(
-            &workspace,
-            args.cargo_jobs,
-            |ev| match ev {
-                codex_xtreme::workflow::Event::Phase(_) => {}
-                codex_xtreme::workflow::Event::Progress(_) => {}
-                codex_xtreme::workflow::Event::CurrentItem(s) => sp.set_message(s),
-                codex_xtreme::workflow::Event::Log(s) => {
-                    // Tests are a side step; keep output concise.
-                    let _ = log::info(s);
-                }
-                codex_xtreme::workflow::Event::PatchFileApplied(_) => {}
-                codex_xtreme::workflow::Event::PatchFileSkipped { .. } => {}
-            },
-        )?;
-        sp.stop("Verification tests finished");
+    // Remember this build's choices for next time
+    config.record_build(&BuildChoices {
+        repo: &repo.path,
+        tag: &target_tag,
+        patches: &selected_patches,
+        disabled_patches: &disabled_patches,
+        options: &options,
+        optimization: opt_mode,
+        run_tests,
+        setup_alias,
+        cleanup,
+    });
+    match config.save() {
+        Ok(path) => info!(path = %path.display(), "Saved user config"),
+        Err(e) => log::warning(format!("Could not save config: {:#}", e))?,
     }
 
-    if confirm("Set up shell alias?")
-        .initial_value(true)
+    if confirm("Save these choices as a recipe file?")
+        .initial_value(false)
         .interact()?
     {
-        let sp = spinner();
-        sp.start("Setting up shell alias...");
-        match codex_xtreme::workflow::setup_alias(&binary_path)? {
-            Some(rc_file) => sp.stop(format!("Updated alias in {}", rc_file)),
-            None => sp.stop("Fish shell detected: add alias manually"),
-        }
+        let recipe_path: String = input("Recipe path")
+            .default_input("codex-xtreme-recipe.toml")
+            .interact()?;
+        let recipe_path = PathBuf::from(shellexpand::tilde(&recipe_path).as_ref());
+        let mut recipe = codex_xtreme::recipe::Recipe::from_choices(
+            &repo.path,
+            &target_tag,
+            &cherry_pick_shas,
+            &selected_patches,
+            &options,
+            run_tests,
+            setup_alias,
+        );
+        recipe.cherry_pick = cherry_pick_options;
+        recipe.disabled_patches = params.disabled_patches.clone();
+        recipe.worktree = args.worktree;
+        recipe.stash = allow_stash;
+        recipe.restore = restore_checkout;
+        recipe.cleanup = params.cleanup;
+        recipe.strict = args.strict;
+        recipe.require_all = args.require_all;
+        recipe.save(&recipe_path)?;
+        log::success(format!(
+            "Saved recipe: {} (rebuild with `codex-xtreme build --recipe {}`)",
+            recipe_path.display(),
+            recipe_path.display()
+        ))?;
     }
 
     outro(format!(
@@ -665,173 +1207,641 @@ fn main() -> Result<()> {
         binary_path.display()
     ))?;
 
-    Ok(())
+    Ok(binary_path)
 }
 
-// ═══════════════════════════════════════════════════════════════════════════
-// REPOSITORY MANAGEMENT
-// ═══════════════════════════════════════════════════════════════════════════
+/// Dev-mode commit browser: pick commits from `<tag>..origin/main`, optionally limited to a
+/// path, plus any SHAs or `A..B` ranges typed in by hand.
+fn browse_cherry_picks(repo: &Path, tag: &str) -> Result<Vec<String>> {
+    let mut selected: Vec<String> = Vec::new();
+
+    match codex_xtreme::core::default_upstream(repo) {
+        Some(upstream) => {
+            let path_filter: String = input(format!(
+                "Browse {tag}..{upstream}: only commits touching path"
+            ))
+            .placeholder("codex-rs/core (empty for all)")
+            .default_input("")
+            .required(false)
+            .interact()?;
+            let commits =
+                codex_xtreme::core::list_commits(repo, tag, &upstream, Some(path_filter.as_str()))?;
+
+            if commits.is_empty() {
+                log::info(format!("No commits in {tag}..{upstream} match"))?;
+            } else {
+                let items: Vec<(String, String, String)> = commits
+                    .iter()
+                    .map(|c| {
+                        let mut files = c.files.iter().take(3).cloned().collect::<Vec<_>>();
+                        if c.files.len() > 3 {
+                            files.push(format!("+{} more", c.files.len() - 3));
+                        }
+                        (
+                            c.sha.clone(),
+                            format!("{} {}", c.short_sha, c.subject),
+                            format!("{} · {} · {}", c.author, c.date, files.join(", ")),
+                        )
+                    })
+                    .collect();
+                selected = multiselect(format!(
+                    "Cherry-pick commits ({} in {tag}..{upstream}; type to filter)",
+                    commits.len()
+                ))
+                .items(&items)
+                .filter_mode()
+                .max_rows(15)
+                .required(false)
+                .interact()?;
 
-#[instrument]
-fn find_codex_repos() -> Result<Vec<RepoInfo>> {
-    let candidates = [
-        "~/dev/codex",
-        "~/dev/cod3x",
-        "~/codex",
-        "~/src/codex",
-        "~/.local/src/codex",
-        "~/projects/codex",
-        "~/code/codex",
-    ];
-
-    let mut repos = Vec::new();
-
-    for candidate in &candidates {
-        let expanded = shellexpand::tilde(candidate);
-        let path = PathBuf::from(expanded.as_ref());
-
-        let workspace_cargo = path.join(CODEX_RS_SUBDIR).join("Cargo.toml");
-        if workspace_cargo.exists() {
-            if let Ok(contents) = std::fs::read_to_string(&workspace_cargo) {
-                if contents.contains("codex-cli") || contents.contains("codex-common") {
-                    let branch = get_current_branch(&path).unwrap_or_else(|_| "unknown".into());
-                    let age = get_repo_age(&path);
-                    repos.push(RepoInfo { path, age, branch });
+                if !selected.is_empty()
+                    && confirm("Review the selected diffs in your pager first?")
+                        .initial_value(false)
+                        .interact()?
+                {
+                    for sha in codex_xtreme::core::topo_order_commits(repo, &selected) {
+                        Command::new(resolve_command_path("git")?)
+                            .current_dir(repo)
+                            .args(["show", "--stat", "--patch", &sha])
+                            .status()?;
+                    }
+                    if !confirm(format!("Apply these {} commit(s)?", selected.len()))
+                        .initial_value(true)
+                        .interact()?
+                    {
+                        selected.clear();
+                    }
                 }
             }
         }
+        None => log::warning(format!(
+            "No origin/main or main branch found; enter commits manually \
+             (https://github.com/openai/codex/compare/{tag}...main)"
+        ))?,
     }
 
-    Ok(repos)
+    let extra: String = input("Additional SHAs or ranges (A..B), comma-separated")
+        .placeholder("abc1234, def5678..0123abc")
+        .default_input("")
+        .required(false)
+        .interact()?;
+    let (specs, invalid) = codex_xtreme::core::parse_commit_specs(&extra);
+    if !invalid.is_empty() {
+        log::warning(format!("Ignoring invalid entries: {}", invalid.join(", ")))?;
+    }
+    // Apply (and record) parents before children.
+    codex_xtreme::core::resolve_cherry_picks(repo, &selected, &specs)
 }
 
-fn get_current_branch(repo: &Path) -> Result<String> {
-    let output = Command::new(resolve_command_path("git")?)
-        .current_dir(repo)
-        .args(["branch", "--show-current"])
-        .output()?;
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+/// Add the patch files `selected` requires and return everything in build order.
+fn order_patches(
+    order: &codex_xtreme::patch_order::PatchGraph,
+    selected: &[PathBuf],
+    target_tag: &str,
+) -> Result<Vec<PathBuf>> {
+    let added = order.requirements(selected, target_tag)?;
+    if !added.is_empty() {
+        let reasons: Vec<String> = added.iter().map(|(_, reason)| reason.clone()).collect();
+        log::info(format!("Also selected {}", reasons.join(", ")))?;
+    }
+    let mut selected = selected.to_vec();
+    selected.extend(added.into_iter().map(|(path, _)| path));
+    let ordered = order.order(&selected)?;
+    if ordered != selected {
+        let names: Vec<String> = ordered
+            .iter()
+            .map(|p| {
+                p.file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        log::info(format!("Patch order: {}", names.join(", ")))?;
+    }
+    Ok(ordered)
 }
 
-fn get_repo_age(repo: &Path) -> String {
-    let git_dir = repo.join(".git");
-    let fetch_head = git_dir.join("FETCH_HEAD");
-
-    let mtime = fetch_head
-        .metadata()
-        .and_then(|m| m.modified())
-        .or_else(|_| git_dir.metadata().and_then(|m| m.modified()));
-
-    match mtime {
-        Ok(time) => {
-            let duration = SystemTime::now().duration_since(time).unwrap_or_default();
-            let secs = duration.as_secs();
-            if secs < 60 {
-                format!("{}s ago", secs)
-            } else if secs < 3600 {
-                format!("{}m ago", secs / 60)
-            } else if secs < 86400 {
-                format!("{}h ago", secs / 3600)
+/// Offer to switch off individual patches inside selected files that bundle several.
+///
+/// Files that are `required` have to apply in full and are not offered.
+fn choose_patch_ids(
+    available: &[(PathBuf, codex_patcher::PatchConfig)],
+    selected: &[PathBuf],
+    disabled: &mut codex_xtreme::workflow::DisabledPatches,
+    required: impl Fn(&Path) -> bool,
+) -> Result<()> {
+    let bundles: Vec<&(PathBuf, codex_patcher::PatchConfig)> = available
+        .iter()
+        .filter(|(path, config)| {
+            selected.contains(path) && config.patches.len() > 1 && !required(path)
+        })
+        .collect();
+    if bundles.is_empty()
+        || !confirm("Choose individual patches inside these files?")
+            .initial_value(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+    for (path, config) in bundles {
+        let items: Vec<(String, String, String)> = config
+            .patches
+            .iter()
+            .map(|p| (p.id.clone(), p.id.clone(), p.file.clone()))
+            .collect();
+        let off = disabled.get(path).cloned().unwrap_or_default();
+        let enabled: Vec<String> = multiselect(format!("Patches in {}", config.meta.name))
+            .items(&items)
+            .initial_values(
+                items
+                    .iter()
+                    .map(|(id, _, _)| id.clone())
+                    .filter(|id| !off.contains(id))
+                    .collect(),
+            )
+            .required(false)
+            .interact()?;
+        let off: Vec<String> = items
+            .into_iter()
+            .map(|(id, _, _)| id)
+            .filter(|id| !enabled.contains(id))
+            .collect();
+        if off.is_empty() {
+            disabled.remove(path);
+        } else {
+            disabled.insert(path.clone(), off);
+        }
+    }
+    Ok(())
+}
+
+/// Check the selection for patches that edit the same lines (as of `revision`) and let the
+/// user drop one side.
+///
+/// Dropped patches are added to `disabled`, except in `required` files, which cannot apply
+/// in part: dropping one of their patches removes the whole file from `selected`. Returns
+/// true when that happened. In strict mode keeping both sides is not offered and a failed
+/// check is an error.
+fn resolve_patch_conflicts(
+    workspace: &Path,
+    revision: &str,
+    selected: &mut Vec<PathBuf>,
+    disabled: &mut codex_xtreme::workflow::DisabledPatches,
+    required: impl Fn(&Path) -> bool,
+    strict: bool,
+) -> Result<bool> {
+    if selected.len() < 2 {
+        return Ok(false);
+    }
+    let sp = spinner();
+    sp.start("Checking patches for conflicts...");
+    let conflicts = match codex_xtreme::workflow::find_patch_conflicts(
+        workspace,
+        Some(revision),
+        selected,
+        disabled,
+    ) {
+        Ok(conflicts) => conflicts,
+        Err(e) if strict => {
+            sp.error("Conflict check failed");
+            return Err(e.context("Patch conflict check failed (strict mode)"));
+        }
+        Err(e) => {
+            sp.error(format!("Conflict check failed: {}", e));
+            return Ok(false);
+        }
+    };
+    if conflicts.is_empty() {
+        sp.stop("No conflicting patches");
+        return Ok(false);
+    }
+    sp.stop(format!("{} conflicting patch pair(s)", conflicts.len()));
+    let before = selected.len();
+
+    for conflict in conflicts {
+        let is_dropped = |edit: &codex_xtreme::workflow::PatchEdit| {
+            disabled
+                .get(&edit.patch_file)
+                .is_some_and(|ids| ids.contains(&edit.patch_id))
+        };
+        // An earlier answer may already have dropped one side.
+        if is_dropped(&conflict.first)
+            || is_dropped(&conflict.second)
+            || !selected.contains(&conflict.first.patch_file)
+            || !selected.contains(&conflict.second.patch_file)
+        {
+            continue;
+        }
+        let side = |edit: &codex_xtreme::workflow::PatchEdit| {
+            let file = edit
+                .patch_file
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if required(&edit.patch_file) {
+                format!("Deselect {} (required, drops {})", file, edit.patch_id)
             } else {
-                format!("{}d ago", secs / 86400)
+                format!("Drop {} ({})", edit.patch_id, file)
             }
+        };
+        let mut choice = select(conflict.describe())
+            .item(
+                Some(conflict.second.clone()),
+                side(&conflict.second),
+                "applied second",
+            )
+            .item(
+                Some(conflict.first.clone()),
+                side(&conflict.first),
+                "applied first",
+            );
+        if !strict {
+            choice = choice.item(
+                None,
+                "Keep both",
+                "the second may fail or rewrite the first",
+            );
+        }
+        match choice.interact()? {
+            Some(edit) if required(&edit.patch_file) => {
+                selected.retain(|path| path != &edit.patch_file);
+                disabled.remove(&edit.patch_file);
+            }
+            Some(edit) => disabled
+                .entry(edit.patch_file)
+                .or_default()
+                .push(edit.patch_id),
+            None => {}
         }
-        Err(_) => "unknown".into(),
     }
+    Ok(selected.len() < before)
 }
 
-fn clone_codex() -> Result<RepoInfo> {
-    let dest = shellexpand::tilde("~/dev/codex-xtreme-build");
-    let dest_path = PathBuf::from(dest.as_ref());
+/// Dry-run the selected patches on a scratch copy of `revision` and print each outcome with
+/// its diff.
+fn preview_patches(
+    workspace: &Path,
+    revision: &str,
+    selected: &[PathBuf],
+    disabled: &codex_xtreme::workflow::DisabledPatches,
+) -> Result<()> {
+    let sp = spinner();
+    sp.start("Dry-running patches...");
+    let previews = match codex_xtreme::workflow::preview_patches(
+        workspace,
+        Some(revision),
+        selected,
+        disabled,
+    ) {
+        Ok(previews) => previews,
+        Err(e) => {
+            sp.error(format!("Preview failed: {}", e));
+            return Ok(());
+        }
+    };
+    sp.stop(format!(
+        "Dry run: {}",
+        codex_xtreme::workflow::preview_summary(&previews)
+    ));
 
-    if dest_path.exists() {
-        std::fs::remove_dir_all(&dest_path)?;
+    for file in &previews {
+        let summary: Vec<String> = file.patches.iter().map(|p| p.summary()).collect();
+        let heading = format!("{}\n{}", file.name, summary.join("\n"));
+        if file.patches.iter().any(|p| p.is_problem()) {
+            log::warning(heading)?;
+        } else {
+            log::info(heading)?;
+        }
+        for patch in file.patches.iter().filter(|p| !p.diff.is_empty()) {
+            println!("{}", patch.diff.trim_end());
+        }
     }
+    Ok(())
+}
 
-    let sp = spinner();
-    sp.start("Cloning Codex from GitHub...");
-
-    let status = Command::new(resolve_command_path("git")?)
-        .args(["clone", "--depth=100", CODEX_REPO_URL])
-        .arg(&dest_path)
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .status()?;
+/// Non-interactive build: run the shared pipeline with choices taken from a recipe.
+///
+/// Progress goes to stderr; the final binary path is printed on stdout for scripts
+/// (unless stdout carries the `--events` stream, whose result record has the path).
+fn run_recipe_build(
+    recipe_path: &Path,
+    args: &Args,
+    mut events: Option<EventWriter>,
+) -> Result<()> {
+    use codex_xtreme::workflow::Event;
+
+    let start_time = Instant::now();
+    let result = (|| {
+        let recipe = codex_xtreme::recipe::Recipe::load(recipe_path)?;
+        let base_dir = recipe_path.parent().unwrap_or(Path::new(""));
+        let mut params = recipe.into_pipeline_params(base_dir, args.cargo_jobs)?;
+        if args.no_cache {
+            params.cache_dir = None;
+        } else if let Some(dir) = &args.cache_dir {
+            params.cache_dir = Some(dir.clone());
+        }
+        params.use_worktree |= args.worktree;
+        params.allow_stash |= args.stash;
+        params.restore_checkout |= args.restore;
+        params.cleanup &= !args.no_cleanup;
+        params.strict |= args.strict;
+        params.require_all |= args.require_all;
+        params.build_options.offline |= args.offline;
+
+        info!(recipe = %recipe_path.display(), version = %params.version, "Starting recipe build");
+        eprintln!(
+            "codex-xtreme: building {} from {}",
+            params.version,
+            recipe_path.display()
+        );
 
-    if !status.success() {
-        bail!("Failed to clone repository");
+        codex_xtreme::workflow::run_pipeline(&params, |ev| {
+            tee_event(&mut events, &ev);
+            match ev {
+                Event::Phase(phase) => eprintln!("==> {}", phase.name()),
+                Event::Progress(_) => {}
+                Event::CurrentItem(s) => eprintln!("    {}", s),
+                Event::Log(s) => eprintln!("{}", s),
+                Event::PatchFileApplied(name) => eprintln!("  ✓ Applied patch file: {}", name),
+                Event::PatchFileSkipped { name, reason } => {
+                    eprintln!("  ⊘ Skipped patch file: {} ({})", name, reason)
+                }
+                Event::PatchFilePartial {
+                    name,
+                    applied,
+                    total,
+                    reason,
+                } => eprintln!(
+                    "  ◐ Partly applied patch file: {} ({} of {} patches; {})",
+                    name, applied, total, reason
+                ),
+                Event::RequiredPatchFailed(failure) => eprintln!("  ✗ required: {}", failure),
+            }
+        })
+        // Report the install store copy; the target/ binary is clobbered by the next build.
+        .map(|outcome| {
+            outcome
+                .installed
+                .map_or(outcome.binary_path, |build| build.binary)
+        })
+    })();
+
+    if let Some(writer) = events.as_mut() {
+        writer.result(
+            result
+                .as_ref()
+                .map(|p| p.as_path())
+                .map_err(|e| format!("{e:#}")),
+            start_time.elapsed(),
+        );
     }
+    let binary_path = result?;
 
-    sp.stop("Repository cloned");
-
-    Ok(RepoInfo {
-        path: dest_path,
-        age: "just now".into(),
-        branch: "main".into(),
-    })
+    eprintln!(
+        "codex-xtreme: build complete in {:.1}s",
+        start_time.elapsed().as_secs_f64()
+    );
+    if !(events.is_some() && args.events_file.is_none()) {
+        println!("{}", binary_path.display());
+    }
+    Ok(())
 }
 
-fn fetch_repo(repo: &Path) -> Result<()> {
-    Command::new(resolve_command_path("git")?)
-        .current_dir(repo)
-        .args(["fetch", "--tags", "--quiet"])
-        .status()?;
+/// `codex-xtreme list|use|rollback|remove`: manage the versioned install store.
+fn run_store_command(command: &StoreCommand) -> Result<()> {
+    let store = codex_xtreme::install::InstallStore::open_default()?;
+    match command {
+        StoreCommand::List => {
+            let builds = store.list()?;
+            if builds.is_empty() {
+                eprintln!("No installed builds in {}", store.builds_dir().display());
+                return Ok(());
+            }
+            let current = store.current();
+            for build in builds {
+                let marker = if current.as_deref() == Some(build.id.as_str()) {
+                    "*"
+                } else {
+                    " "
+                };
+                let built = build
+                    .manifest
+                    .as_ref()
+                    .map(|m| codex_xtreme::manifest::format_utc(m.built_at))
+                    .unwrap_or_default();
+                println!("{} {:<48} {}", marker, build.id, built);
+            }
+        }
+        StoreCommand::Use(id) => {
+            let build = store.use_build(id)?;
+            eprintln!("Now using {}", build.id);
+            println!("{}", store.current_binary().display());
+        }
+        StoreCommand::Rollback => {
+            let build = store.rollback()?;
+            eprintln!("Rolled back to {}", build.id);
+            println!("{}", store.current_binary().display());
+        }
+        StoreCommand::Remove(id) => {
+            let build = store.remove(id)?;
+            eprintln!("Removed {}", build.id);
+        }
+    }
     Ok(())
 }
 
-/// Get all rust-v* releases from the repo (sorted newest first)
-#[instrument(skip(repo), fields(repo = %repo.display()))]
-fn get_github_releases(repo: &Path) -> Result<Vec<Release>> {
-    // Get all tags matching rust-v*
-    let output = Command::new(resolve_command_path("git")?)
-        .current_dir(repo)
-        .args([
-            "tag",
-            "-l",
-            "rust-v*",
-            "--sort=-v:refname", // Sort by version, newest first
-            "--format=%(refname:short)|%(creatordate:short)",
-        ])
-        .output()?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    debug!(raw_tags = %stdout.lines().count(), "Fetched tags from git");
-
-    let mut seen = std::collections::HashSet::new();
-    let mut releases = Vec::new();
-    for line in stdout.lines() {
-        let parts: Vec<&str> = line.split('|').collect();
-        let tag = match parts.first() {
-            Some(tag) => tag.to_string(),
-            None => continue,
-        };
+/// `codex-xtreme inspect`: print the provenance manifest of a built binary.
+fn run_inspect(path: &Path, json: bool) -> Result<()> {
+    let (manifest, source) = codex_xtreme::manifest::load(path)?;
+    if json {
+        println!("{}", manifest.to_json()?);
+        return Ok(());
+    }
 
-        // Filter out malformed tags (like rust-vv*, rust-vrust-v*)
-        if !tag.starts_with("rust-v") || tag.starts_with("rust-vv") || tag.starts_with("rust-vrust")
-        {
-            debug!(tag = %tag, "Skipping malformed tag");
-            continue;
+    let source = match source {
+        codex_xtreme::manifest::ManifestSource::Embedded => "embedded".to_string(),
+        codex_xtreme::manifest::ManifestSource::Sidecar(p) => p.display().to_string(),
+    };
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+
+    println!("{}", path.display());
+    println!("  manifest:    {}", source);
+    println!("  tag:         {}", manifest.tag);
+    println!(
+        "  commit:      {}",
+        manifest.commit.as_deref().unwrap_or("unknown")
+    );
+    println!(
+        "  built:       {} ({:.0}s, codex-xtreme {})",
+        codex_xtreme::manifest::format_utc(manifest.built_at),
+        manifest.build_secs,
+        manifest.builder_version
+    );
+    println!("  profile:     {}", manifest.profile);
+    println!(
+        "  cpu target:  {}",
+        manifest.cpu_target.as_deref().unwrap_or("generic")
+    );
+    println!(
+        "  RUSTFLAGS:   {}",
+        if manifest.rustflags.is_empty() {
+            "(none)"
+        } else {
+            &manifest.rustflags
         }
+    );
+    println!(
+        "  mold/BOLT/strip: {}/{}/{}",
+        yes_no(manifest.mold),
+        yes_no(manifest.bolt),
+        yes_no(manifest.stripped)
+    );
+    println!(
+        "  toolchain:   {}; {}",
+        manifest.rustc_version.as_deref().unwrap_or("rustc unknown"),
+        manifest.cargo_version.as_deref().unwrap_or("cargo unknown")
+    );
 
-        if !seen.insert(tag.clone()) {
-            debug!(tag = %tag, "Skipping duplicate tag");
-            continue;
+    if manifest.cherry_picks.is_empty() {
+        println!("  cherry-picks: none");
+    } else {
+        println!("  cherry-picks: {}", manifest.cherry_picks.join(", "));
+    }
+
+    if manifest.patches.is_empty() {
+        println!("  patches:     none");
+    } else {
+        println!("  patches:");
+        for patch in &manifest.patches {
+            println!(
+                "    {} (sha256 {}) {}",
+                patch.name,
+                &patch.sha256[..12.min(patch.sha256.len())],
+                patch.path.display()
+            );
+            for result in &patch.results {
+                match &result.reason {
+                    Some(reason) => {
+                        println!("      {:<16} {} ({})", result.status, result.id, reason)
+                    }
+                    None => println!("      {:<16} {}", result.status, result.id),
+                }
+            }
+            for id in &patch.disabled {
+                println!("      {:<16} {}", "disabled", id);
+            }
         }
+    }
+    Ok(())
+}
 
-        let published = parts.get(1).unwrap_or(&"").to_string();
-        let version = tag.strip_prefix("rust-v").unwrap_or(&tag).to_string();
+// ═══════════════════════════════════════════════════════════════════════════
+// REPOSITORY MANAGEMENT
+// ═══════════════════════════════════════════════════════════════════════════
 
-        debug!(tag = %tag, version = %version, published = %published, "Found release");
+/// Ask for clone options (source, destination, branch, history, sparse) and clone.
+fn clone_codex() -> Result<RepoInfo> {
+    use codex_xtreme::core::{CloneHistory, CloneOptions, CloneSource, CODEX_RS_SUBDIR};
+
+    let defaults = CloneOptions::default();
+    let source: String = input("Clone from (URL, local repo path or .bundle file)")
+        .default_input(&defaults.source)
+        .validate(|value: &String| {
+            CloneOptions {
+                source: value.clone(),
+                ..Default::default()
+            }
+            .source_kind()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+        })
+        .interact()?;
+    let dest: String = input("Clone into")
+        .default_input("~/dev/codex-xtreme-build")
+        .interact()?;
+    let branch: String = input("Branch (blank for the default branch)")
+        .required(false)
+        .interact()?;
 
-        releases.push(Release {
-            tag,
-            version,
-            published,
-        });
+    let mut options = CloneOptions {
+        source,
+        branch: Some(branch.trim().to_string()).filter(|b| !b.is_empty()),
+        ..defaults
+    };
+    let is_bundle = matches!(options.source_kind(), Ok(CloneSource::Bundle(_)));
+    options.history = if is_bundle {
+        // Bundles are always cloned in full.
+        CloneHistory::Full
+    } else {
+        match select("History")
+            .item("shallow", "Shallow", "Last N commits (fastest)")
+            .item(
+                "blobless",
+                "Blobless",
+                "All commits, file contents on demand",
+            )
+            .item("full", "Full", "Everything (largest download)")
+            .interact()?
+        {
+            "shallow" => {
+                let depth: u32 = input("Depth (commits)")
+                    .default_input("100")
+                    .validate(|value: &String| match value.parse::<u32>() {
+                        Ok(n) if n > 0 => Ok(()),
+                        _ => Err("Enter a positive number"),
+                    })
+                    .interact()?;
+                CloneHistory::Shallow(depth)
+            }
+            "blobless" => CloneHistory::Blobless,
+            _ => CloneHistory::Full,
+        }
+    };
+    options.sparse = confirm(format!("Sparse checkout (only {}/)?", CODEX_RS_SUBDIR))
+        .initial_value(false)
+        .interact()?;
+    options.validate()?;
+
+    let dest_path = PathBuf::from(shellexpand::tilde(&dest).as_ref());
+    let sp = spinner();
+    sp.start(format!(
+        "Cloning {} ({})...",
+        options.source, options.history
+    ));
+    match codex_xtreme::core::clone_codex_with(&dest_path, &options) {
+        Ok(repo) => {
+            sp.stop("Repository cloned");
+            Ok(repo)
+        }
+        Err(e) => {
+            sp.error("Clone failed");
+            Err(e)
+        }
     }
+}
 
-    info!(count = releases.len(), "Found releases");
-    Ok(releases)
+/// Deepen a shallow clone until `tag` and `commits` are reachable (no-op for full clones).
+fn ensure_history(repo: &Path, tag: &str, commits: &[String]) -> Result<()> {
+    if !codex_xtreme::core::is_shallow(repo) {
+        return Ok(());
+    }
+    let sp = spinner();
+    sp.start(format!("Shallow clone: fetching history for {}...", tag));
+    match codex_xtreme::core::ensure_history(repo, tag, commits) {
+        Ok(fetch) => {
+            sp.stop(
+                fetch
+                    .summary()
+                    .unwrap_or_else(|| "Shallow clone: history already complete".to_string()),
+            );
+            Ok(())
+        }
+        Err(e) => {
+            sp.error("Fetching history failed");
+            Err(e)
+        }
+    }
 }
 
 /// Get the current version of the repo (from git describe or Cargo.toml)
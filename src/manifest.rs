@@ -0,0 +1,434 @@
+//! Build provenance manifests.
+//!
+//! Every binary produced by the pipeline gets a JSON manifest describing how it was made
+//! (tag, commit, cherry-picks, patch files and their per-patch results, RUSTFLAGS, toolchain).
+//! The manifest is written next to the binary as `<binary>.manifest.json` and embedded in the
+//! binary itself as an ELF section, so `codex-xtreme inspect <binary>` works even after the
+//! binary has been copied away from its build directory.
+
+use crate::workflow::{build_rustflags, BuildOptions};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// ELF section holding the embedded manifest.
+pub const MANIFEST_SECTION: &str = ".codex_xtreme_manifest";
+
+/// Bumped when the manifest layout changes incompatibly.
+pub const MANIFEST_VERSION: u32 = 1;
+
+fn resolve_command_path(name: &str) -> Result<PathBuf> {
+    which::which(name).map_err(|_| anyhow::anyhow!("Required command not found in PATH: {name}"))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// TYPES
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildManifest {
+    pub manifest_version: u32,
+    /// Version of codex-xtreme that produced the build.
+    pub builder_version: String,
+    pub tag: String,
+    /// Commit the tag resolved to (cherry-picks and patches are applied on top of it).
+    pub commit: Option<String>,
+    /// Cherry-picks that applied cleanly.
+    pub cherry_picks: Vec<String>,
+    pub patches: Vec<PatchFileRecord>,
+    pub profile: String,
+    pub cpu_target: Option<String>,
+    /// RUSTFLAGS passed to cargo (empty when none were set).
+    pub rustflags: String,
+    pub rustc_version: Option<String>,
+    pub cargo_version: Option<String>,
+    pub mold: bool,
+    /// Whether BOLT actually rewrote the binary (not just whether it was requested).
+    pub bolt: bool,
+    pub stripped: bool,
+    /// Unix timestamp (seconds) when the manifest was recorded.
+    pub built_at: u64,
+    /// Wall-clock build duration in seconds.
+    pub build_secs: f64,
+    /// Build cache key, when the build cache was enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_key: Option<String>,
+}
+
+/// One selected patch file and what happened to each patch in it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatchFileRecord {
+    pub name: String,
+    pub path: PathBuf,
+    /// SHA-256 of the patch file contents.
+    pub sha256: String,
+    /// Patch ids in the file that were switched off for this build.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled: Vec<String>,
+    pub results: Vec<PatchRecord>,
+}
+
+/// Outcome of one patch, mirroring `codex_patcher::PatchResult` (plus `error`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatchStatus {
+    Applied,
+    AlreadyApplied,
+    SkippedVersion,
+    Failed,
+    /// The patcher returned an error (or panicked) instead of a result.
+    Error,
+    /// Switched off for this build, so never run. Manifests list these ids under `disabled`;
+    /// the status only shows up in dry-run previews and required-patch failures.
+    Disabled,
+}
+
+impl PatchStatus {
+    /// The serialized name, e.g. `already_applied`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PatchStatus::Applied => "applied",
+            PatchStatus::AlreadyApplied => "already_applied",
+            PatchStatus::SkippedVersion => "skipped_version",
+            PatchStatus::Failed => "failed",
+            PatchStatus::Error => "error",
+            PatchStatus::Disabled => "disabled",
+        }
+    }
+
+    /// True when the patch's change is in the workspace.
+    pub fn is_in_place(self) -> bool {
+        matches!(self, PatchStatus::Applied | PatchStatus::AlreadyApplied)
+    }
+}
+
+impl std::fmt::Display for PatchStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Serializable form of a single `codex_patcher::PatchResult`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatchRecord {
+    pub id: String,
+    pub status: PatchStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl BuildManifest {
+    /// Start a manifest for a build of `tag` in `repo` with the requested options.
+    ///
+    /// Post-build facts (`bolt`, `stripped`, `build_secs`) default to what was requested;
+    /// callers overwrite them with what actually happened.
+    pub fn new(repo: &Path, tag: &str, options: &BuildOptions) -> Self {
+        let workspace = repo.join(crate::core::CODEX_RS_SUBDIR);
+        Self {
+            manifest_version: MANIFEST_VERSION,
+            builder_version: env!("CARGO_PKG_VERSION").to_string(),
+            tag: tag.to_string(),
+            commit: resolve_commit(repo, tag),
+            cherry_picks: Vec::new(),
+            patches: Vec::new(),
+            profile: options.profile.clone(),
+            cpu_target: options.cpu_target.clone(),
+            rustflags: build_rustflags(options.cpu_target.as_deref(), &options.optimization)
+                .join(" "),
+            rustc_version: command_version(&workspace, "rustc"),
+            cargo_version: command_version(&workspace, "cargo"),
+            mold: options.optimization.use_mold,
+            bolt: options.optimization.use_bolt,
+            stripped: options.strip_symbols,
+            built_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            build_secs: 0.0,
+            cache_key: None,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize build manifest")
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self> {
+        serde_json::from_str(contents).context("Failed to parse build manifest")
+    }
+}
+
+/// Commit SHA that `tag` resolves to in `repo`.
+///
+/// Resolved from the tag rather than `HEAD` so build worktrees (where cherry-picks and
+/// patches are committed on top) record the same commit as in-place builds.
+pub fn resolve_commit(repo: &Path, tag: &str) -> Option<String> {
+    let output = Command::new(resolve_command_path("git").ok()?)
+        .current_dir(repo)
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{tag}^{{commit}}"))
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !sha.is_empty()).then_some(sha)
+}
+
+/// First line of `<tool> --version`, e.g. `rustc 1.85.0 (4d91de4e4 2025-02-17)`.
+///
+/// Runs in `workspace` so a toolchain pinned there (`rust-toolchain.toml`) is the one reported.
+fn command_version(workspace: &Path, tool: &str) -> Option<String> {
+    let output = Command::new(resolve_command_path(tool).ok()?)
+        .current_dir(workspace)
+        .arg("--version")
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let line = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()?
+        .trim()
+        .to_string();
+    (output.status.success() && !line.is_empty()).then_some(line)
+}
+
+/// SHA-256 of a file's contents, hex encoded.
+pub fn hash_file(path: &Path) -> Result<String> {
+    let contents =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(format!("{:x}", Sha256::digest(&contents)))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// WRITE / EMBED
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Path of the sidecar manifest for a binary.
+pub fn sidecar_path(binary_path: &Path) -> PathBuf {
+    let mut name = binary_path.as_os_str().to_os_string();
+    name.push(".manifest.json");
+    PathBuf::from(name)
+}
+
+/// Write `<binary>.manifest.json` and return its path.
+pub fn write_sidecar(binary_path: &Path, manifest: &BuildManifest) -> Result<PathBuf> {
+    let path = sidecar_path(binary_path);
+    std::fs::write(&path, manifest.to_json()?)
+        .with_context(|| format!("Failed to write manifest: {}", path.display()))?;
+    Ok(path)
+}
+
+fn objcopy() -> Result<PathBuf> {
+    // Prefer GNU objcopy, fall back to llvm-objcopy.
+    which::which("objcopy")
+        .or_else(|_| which::which("llvm-objcopy"))
+        .map_err(|_| anyhow::anyhow!("objcopy not found in PATH (needed for embedded manifests)"))
+}
+
+/// Embed the manifest into the binary as a non-loaded ELF section.
+///
+/// Run this after BOLT and strip, since both rewrite the binary.
+pub fn embed(binary_path: &Path, manifest: &BuildManifest) -> Result<()> {
+    let objcopy = objcopy()?;
+    let json_path = binary_path.with_extension("manifest.tmp");
+    std::fs::write(&json_path, manifest.to_json()?)
+        .with_context(|| format!("Failed to write {}", json_path.display()))?;
+
+    let output = Command::new(objcopy)
+        .arg("--remove-section")
+        .arg(MANIFEST_SECTION)
+        .arg("--add-section")
+        .arg(format!("{}={}", MANIFEST_SECTION, json_path.display()))
+        .arg("--set-section-flags")
+        .arg(format!("{}=noload,readonly", MANIFEST_SECTION))
+        .arg(binary_path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output();
+    std::fs::remove_file(&json_path).ok();
+
+    let output = output.context("objcopy failed to spawn")?;
+    if !output.status.success() {
+        bail!(
+            "objcopy failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Write the sidecar and embed the manifest. Returns the sidecar path.
+///
+/// Embedding is best-effort: its error is returned alongside the sidecar path so callers
+/// can warn without losing the sidecar.
+pub fn record(
+    binary_path: &Path,
+    manifest: &BuildManifest,
+) -> Result<(PathBuf, Option<anyhow::Error>)> {
+    let sidecar = write_sidecar(binary_path, manifest)?;
+    Ok((sidecar, embed(binary_path, manifest).err()))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// READ
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Where an inspected manifest came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestSource {
+    Embedded,
+    Sidecar(PathBuf),
+}
+
+/// Read the manifest embedded in a binary.
+pub fn read_embedded(binary_path: &Path) -> Result<BuildManifest> {
+    let pid = std::process::id();
+    let tmp_dir = std::env::temp_dir();
+    let dump_path = tmp_dir.join(format!("codex-xtreme-manifest-{pid}.json"));
+    let copy_path = tmp_dir.join(format!("codex-xtreme-inspect-{pid}.bin"));
+
+    // objcopy always writes an output file; point it at a scratch copy so the
+    // inspected binary is never modified.
+    let output = Command::new(objcopy()?)
+        .arg("--dump-section")
+        .arg(format!("{}={}", MANIFEST_SECTION, dump_path.display()))
+        .arg(binary_path)
+        .arg(&copy_path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .context("objcopy failed to spawn");
+    std::fs::remove_file(&copy_path).ok();
+    let output = output?;
+
+    let contents = std::fs::read_to_string(&dump_path);
+    std::fs::remove_file(&dump_path).ok();
+    if !output.status.success() {
+        bail!(
+            "No embedded manifest in {}: {}",
+            binary_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let contents = contents.map_err(|_| {
+        anyhow::anyhow!("no {MANIFEST_SECTION} section in {}", binary_path.display())
+    })?;
+    BuildManifest::from_json(&contents)
+}
+
+/// Read a manifest for `path`: a `.json` manifest itself, the binary's embedded section,
+/// or its sidecar file (in that order).
+pub fn load(path: &Path) -> Result<(BuildManifest, ManifestSource)> {
+    if path.extension().is_some_and(|e| e == "json") {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let manifest = BuildManifest::from_json(&contents)?;
+        return Ok((manifest, ManifestSource::Sidecar(path.to_path_buf())));
+    }
+    if !path.exists() {
+        bail!("No such file: {}", path.display());
+    }
+
+    let embedded_err = match read_embedded(path) {
+        Ok(manifest) => return Ok((manifest, ManifestSource::Embedded)),
+        Err(e) => e,
+    };
+
+    let sidecar = sidecar_path(path);
+    match std::fs::read_to_string(&sidecar) {
+        Ok(contents) => Ok((
+            BuildManifest::from_json(&contents)?,
+            ManifestSource::Sidecar(sidecar),
+        )),
+        Err(_) => bail!(
+            "No build manifest found for {} ({embedded_err:#}; no {})",
+            path.display(),
+            sidecar.display()
+        ),
+    }
+}
+
+/// Format a Unix timestamp as `YYYY-MM-DD HH:MM:SS UTC`.
+pub fn format_utc(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Civil-from-days (proleptic Gregorian), see Howard Hinnant's date algorithms.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        rem / 3600,
+        (rem / 60) % 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_round_trips_through_json() {
+        let manifest = BuildManifest {
+            manifest_version: MANIFEST_VERSION,
+            builder_version: "0.1.0".to_string(),
+            tag: "rust-v0.105.0".to_string(),
+            commit: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+            cherry_picks: vec!["abc1234".to_string()],
+            patches: vec![PatchFileRecord {
+                name: "privacy".to_string(),
+                path: PathBuf::from("/patches/privacy.toml"),
+                sha256: "00".repeat(32),
+                disabled: vec!["statsig-endpoint".to_string()],
+                results: vec![PatchRecord {
+                    id: "disable-telemetry".to_string(),
+                    status: PatchStatus::Applied,
+                    file: Some(PathBuf::from("core/src/lib.rs")),
+                    reason: None,
+                }],
+            }],
+            profile: "xtreme".to_string(),
+            cpu_target: Some("native".to_string()),
+            rustflags: "-C target-cpu=native".to_string(),
+            rustc_version: Some("rustc 1.85.0".to_string()),
+            cargo_version: None,
+            mold: false,
+            bolt: true,
+            stripped: true,
+            built_at: 1_760_000_000,
+            build_secs: 812.5,
+            cache_key: Some("ab".repeat(32)),
+        };
+        let json = manifest.to_json().unwrap();
+        assert!(!json.contains("\"reason\""));
+        // Statuses keep their snake_case names, so older manifests still load.
+        assert!(json.contains("\"status\": \"applied\""));
+        assert_eq!(BuildManifest::from_json(&json).unwrap(), manifest);
+    }
+
+    #[test]
+    fn sidecar_sits_next_to_binary() {
+        assert_eq!(
+            sidecar_path(Path::new("/t/xtreme/codex-bolt")),
+            PathBuf::from("/t/xtreme/codex-bolt.manifest.json")
+        );
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_utc(1_709_210_096), "2024-02-29 12:34:56 UTC");
+    }
+}
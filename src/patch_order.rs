@@ -0,0 +1,517 @@
+//! Declared relationships between patch files: what a file requires, what it must run
+//! after, and what it cannot be combined with; plus whether the build needs every patch in
+//! it to apply.
+//!
+//! Relations come from two places, merged per file:
+//!
+//! - the patch file itself, in an `[xtreme]` table (`[meta]` belongs to codex-patcher);
+//! - an `order.toml` next to the patch files, with one table per patch file.
+//!
+//! ```toml
+//! # order.toml (in a patch file the same keys go under [xtreme])
+//! [privacy]
+//! requires = ["telemetry-base"]      # selected along with it, applied before it
+//! after = ["offline-mode"]           # applied before it when both are selected
+//! conflicts_with = ["privacy-lite"]  # never selected together
+//! required = true                    # a patch that fails or is skipped stops the build
+//! ```
+//!
+//! A reference names a patch file by its `meta.name` or by its family (the file stem
+//! without a `-v<version>` suffix), so it covers every version variant. Selected files are
+//! applied in topological order; files without relations keep their selection order.
+
+use crate::core::{patch_family, pick_patch_variants};
+use anyhow::{bail, Context, Result};
+use codex_patcher::{load_from_path, PatchConfig};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Sidecar file with relations for the patch files in its directory.
+pub const ORDER_FILE: &str = "order.toml";
+
+/// Relations declared for one patch file (each entry is a reference, see the module docs).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PatchRelations {
+    pub requires: Vec<String>,
+    pub after: Vec<String>,
+    pub conflicts_with: Vec<String>,
+    /// Every patch in the file has to apply (or already be applied) for the build to go on.
+    pub required: bool,
+}
+
+impl PatchRelations {
+    fn is_empty(&self) -> bool {
+        self.requires.is_empty()
+            && self.after.is_empty()
+            && self.conflicts_with.is_empty()
+            && !self.required
+    }
+
+    fn extend(&mut self, other: PatchRelations) {
+        self.required |= other.required;
+        for (ours, theirs) in [
+            (&mut self.requires, other.requires),
+            (&mut self.after, other.after),
+            (&mut self.conflicts_with, other.conflicts_with),
+        ] {
+            for reference in theirs {
+                if !ours.contains(&reference) {
+                    ours.push(reference);
+                }
+            }
+        }
+    }
+}
+
+/// A patch file as far as ordering is concerned.
+#[derive(Debug, Clone)]
+struct PatchNode {
+    path: PathBuf,
+    name: String,
+    family: String,
+    version_range: Option<String>,
+    relations: PatchRelations,
+}
+
+impl PatchNode {
+    fn is(&self, reference: &str) -> bool {
+        self.name == reference || self.family == reference
+    }
+}
+
+/// Relations between a set of patch files.
+#[derive(Debug, Clone, Default)]
+pub struct PatchGraph {
+    nodes: Vec<PatchNode>,
+}
+
+impl PatchGraph {
+    /// Collect relations for loaded patch files from their `[xtreme]` tables and the
+    /// `order.toml` of each directory involved.
+    pub fn new(patches: &[(PathBuf, PatchConfig)]) -> Result<Self> {
+        let mut sidecars: BTreeMap<PathBuf, BTreeMap<String, PatchRelations>> = BTreeMap::new();
+        let mut nodes = Vec::with_capacity(patches.len());
+        for (path, config) in patches {
+            let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+            if !sidecars.contains_key(&dir) {
+                sidecars.insert(dir.clone(), read_order_file(&dir)?);
+            }
+            let mut node = PatchNode {
+                path: path.clone(),
+                name: config.meta.name.clone(),
+                family: patch_family(path),
+                version_range: config.meta.version_range.clone(),
+                relations: inline_relations(path)?,
+            };
+            for (reference, relations) in &sidecars[&dir] {
+                if node.is(reference) {
+                    node.relations.extend(relations.clone());
+                }
+            }
+            nodes.push(node);
+        }
+        Ok(Self { nodes })
+    }
+
+    /// Load `paths` and collect their relations (for builds from a fixed list of files).
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let patches = paths
+            .iter()
+            .map(|path| {
+                let config = load_from_path(path)
+                    .with_context(|| format!("Failed to load patch: {}", path.display()))?;
+                Ok((path.clone(), config))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(&patches)
+    }
+
+    fn node(&self, path: &Path) -> Option<&PatchNode> {
+        self.nodes.iter().find(|n| n.path == path)
+    }
+
+    /// References that match no known patch file, e.g. `privacy: requires "telemetry-bas"`.
+    pub fn dangling_references(&self) -> Vec<String> {
+        let mut dangling = Vec::new();
+        for node in &self.nodes {
+            let relations = [
+                ("requires", &node.relations.requires),
+                ("after", &node.relations.after),
+                ("conflicts_with", &node.relations.conflicts_with),
+            ];
+            for (kind, references) in relations {
+                for reference in references {
+                    if !self.nodes.iter().any(|n| n.is(reference)) {
+                        dangling.push(format!("{}: {} \"{}\"", node.name, kind, reference));
+                    }
+                }
+            }
+        }
+        dangling
+    }
+
+    /// True when `path` is declared `required` (see [`PatchRelations::required`]).
+    pub fn is_required(&self, path: &Path) -> bool {
+        self.node(path).is_some_and(|n| n.relations.required)
+    }
+
+    /// One line about the declared relations of `path`, e.g.
+    /// `required · requires base · after offline`.
+    pub fn describe(&self, path: &Path) -> Option<String> {
+        let relations = &self.node(path)?.relations;
+        if relations.is_empty() {
+            return None;
+        }
+        let mut parts: Vec<String> = Vec::new();
+        if relations.required {
+            parts.push("required".to_string());
+        }
+        parts.extend(
+            [
+                ("requires", &relations.requires),
+                ("after", &relations.after),
+                ("conflicts with", &relations.conflicts_with),
+            ]
+            .into_iter()
+            .filter(|(_, references)| !references.is_empty())
+            .map(|(kind, references)| format!("{} {}", kind, references.join(", "))),
+        );
+        Some(parts.join(" · "))
+    }
+
+    /// Files to add to `selected` so every requirement is met, each with a reason such as
+    /// `base (required by privacy)`.
+    ///
+    /// Requirements are followed transitively. Among the variants of a required family, a
+    /// selected one wins, otherwise the one [`pick_patch_variants`] would choose for `target`.
+    /// A requirement that matches no known file is an error.
+    pub fn requirements(
+        &self,
+        selected: &[PathBuf],
+        target: &str,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        let mut chosen: Vec<PathBuf> = selected.to_vec();
+        let mut added = Vec::new();
+        let mut idx = 0;
+        while idx < chosen.len() {
+            let Some(node) = self.node(&chosen[idx]) else {
+                idx += 1;
+                continue;
+            };
+            for reference in &node.relations.requires {
+                let candidates: Vec<&PatchNode> =
+                    self.nodes.iter().filter(|n| n.is(reference)).collect();
+                if candidates.is_empty() {
+                    bail!(
+                        "{} requires \"{}\", which matches no patch file",
+                        node.name,
+                        reference
+                    );
+                }
+                if candidates.iter().any(|c| chosen.contains(&c.path)) {
+                    continue;
+                }
+                let variants: Vec<(&Path, Option<&str>)> = candidates
+                    .iter()
+                    .map(|c| (c.path.as_path(), c.version_range.as_deref()))
+                    .collect();
+                let (picked, _) =
+                    pick_patch_variants(&variants, &[candidates[0].path.clone()], target);
+                let pick = picked
+                    .first()
+                    .and_then(|path| self.node(path))
+                    .unwrap_or(candidates[0]);
+                chosen.push(pick.path.clone());
+                added.push((
+                    pick.path.clone(),
+                    format!("{} (required by {})", pick.name, node.name),
+                ));
+            }
+            idx += 1;
+        }
+        Ok(added)
+    }
+
+    /// `selected` in the order to apply it: requirements and `after` targets first, otherwise
+    /// as given.
+    ///
+    /// Fails when a requirement is not selected, two selected files conflict, or the
+    /// relations form a cycle.
+    pub fn order(&self, selected: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let nodes: Vec<Option<&PatchNode>> = selected.iter().map(|p| self.node(p)).collect();
+        let matching = |reference: &str| -> Vec<usize> {
+            (0..selected.len())
+                .filter(|&i| nodes[i].is_some_and(|n| n.is(reference)))
+                .collect()
+        };
+
+        // Indices each selected file has to come after.
+        let mut before: Vec<Vec<usize>> = vec![Vec::new(); selected.len()];
+        for (i, node) in nodes.iter().enumerate() {
+            let Some(node) = node else { continue };
+            for reference in &node.relations.requires {
+                let found = matching(reference);
+                if found.is_empty() {
+                    bail!(
+                        "{} requires \"{}\", which is not selected",
+                        node.name,
+                        reference
+                    );
+                }
+                before[i].extend(found);
+            }
+            for reference in &node.relations.after {
+                before[i].extend(matching(reference));
+            }
+            for reference in &node.relations.conflicts_with {
+                if let Some(&j) = matching(reference).iter().find(|&&j| j != i) {
+                    bail!(
+                        "{} conflicts with {}; select only one of them",
+                        node.name,
+                        nodes[j].map_or(reference.as_str(), |n| n.name.as_str())
+                    );
+                }
+            }
+            before[i].retain(|&j| j != i);
+        }
+
+        // Kahn's algorithm, always taking the earliest ready file to keep the given order.
+        let mut placed = vec![false; selected.len()];
+        let mut ordered = Vec::with_capacity(selected.len());
+        while ordered.len() < selected.len() {
+            let ready =
+                (0..selected.len()).find(|&i| !placed[i] && before[i].iter().all(|&j| placed[j]));
+            match ready {
+                Some(i) => {
+                    placed[i] = true;
+                    ordered.push(selected[i].clone());
+                }
+                None => {
+                    let stuck: Vec<usize> = (0..selected.len()).filter(|&i| !placed[i]).collect();
+                    bail!("Patch files depend on each other in a cycle: {}", {
+                        let cycle = find_cycle(&before, &stuck);
+                        cycle
+                            .iter()
+                            .map(|&i| {
+                                nodes[i].map_or_else(
+                                    || selected[i].display().to_string(),
+                                    |n| n.name.clone(),
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                            .join(" → ")
+                    });
+                }
+            }
+        }
+        Ok(ordered)
+    }
+}
+
+/// A cycle among `stuck` (files whose predecessors are never all placed), closed at both ends.
+fn find_cycle(before: &[Vec<usize>], stuck: &[usize]) -> Vec<usize> {
+    // Every stuck file waits on another stuck file, so walking back must revisit one.
+    let mut path = vec![stuck[0]];
+    loop {
+        let current = *path.last().unwrap_or(&stuck[0]);
+        let Some(&next) = before[current].iter().find(|j| stuck.contains(j)) else {
+            return path;
+        };
+        if let Some(start) = path.iter().position(|&i| i == next) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(next);
+            cycle.reverse();
+            return cycle;
+        }
+        path.push(next);
+    }
+}
+
+/// Relations declared in the `[xtreme]` table of a patch file.
+fn inline_relations(path: &Path) -> Result<PatchRelations> {
+    #[derive(Deserialize)]
+    struct File {
+        #[serde(default)]
+        xtreme: PatchRelations,
+    }
+
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let file: File = toml::from_str(&contents)
+        .with_context(|| format!("Invalid [xtreme] table in {}", path.display()))?;
+    Ok(file.xtreme)
+}
+
+/// The `order.toml` of `dir`, keyed by reference (empty when there is none).
+fn read_order_file(dir: &Path) -> Result<BTreeMap<String, PatchRelations>> {
+    let path = dir.join(ORDER_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&contents).with_context(|| format!("Invalid {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Name, requires, after, conflicts_with.
+    type Node<'a> = (&'a str, &'a [&'a str], &'a [&'a str], &'a [&'a str]);
+
+    fn graph(nodes: &[Node]) -> PatchGraph {
+        let list = |refs: &[&str]| refs.iter().map(|r| r.to_string()).collect();
+        PatchGraph {
+            nodes: nodes
+                .iter()
+                .map(|(name, requires, after, conflicts_with)| PatchNode {
+                    path: PathBuf::from(format!("patches/{}.toml", name)),
+                    name: name.to_string(),
+                    family: patch_family(Path::new(&format!("{}.toml", name))),
+                    version_range: None,
+                    relations: PatchRelations {
+                        requires: list(requires),
+                        after: list(after),
+                        conflicts_with: list(conflicts_with),
+                        required: false,
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names
+            .iter()
+            .map(|n| PathBuf::from(format!("patches/{}.toml", n)))
+            .collect()
+    }
+
+    #[test]
+    fn orders_by_declared_relations_and_reports_problems() {
+        let g = graph(&[
+            ("base", &[], &[], &[]),
+            ("offline", &[], &[], &[]),
+            ("privacy", &["base"], &["offline"], &["privacy-lite"]),
+            ("privacy-lite", &[], &[], &[]),
+        ]);
+
+        // Requirements are pulled in; `after` alone does not select anything.
+        let added = g.requirements(&paths(&["privacy"]), "0.105.0").unwrap();
+        assert_eq!(
+            added,
+            vec![(
+                PathBuf::from("patches/base.toml"),
+                "base (required by privacy)".to_string()
+            )]
+        );
+
+        assert_eq!(
+            g.order(&paths(&["privacy", "offline", "base"])).unwrap(),
+            paths(&["offline", "base", "privacy"])
+        );
+        let missing = g.order(&paths(&["privacy"])).unwrap_err();
+        assert_eq!(
+            missing.to_string(),
+            "privacy requires \"base\", which is not selected"
+        );
+        let conflict = g
+            .order(&paths(&["base", "privacy", "privacy-lite"]))
+            .unwrap_err();
+        assert!(conflict.to_string().contains("conflicts with privacy-lite"));
+
+        let cyclic = graph(&[("a", &["b"], &[], &[]), ("b", &[], &["a"], &[])]);
+        let cycle = cyclic.order(&paths(&["a", "b"])).unwrap_err();
+        assert_eq!(
+            cycle.to_string(),
+            "Patch files depend on each other in a cycle: a → b → a"
+        );
+    }
+
+    #[test]
+    fn reads_relations_from_the_xtreme_table() {
+        let dir = crate::test_support::TestRepo::new("patch-relations");
+        let path = dir.join("privacy.toml");
+        std::fs::write(
+            &path,
+            "[meta]\nname = \"privacy\"\n\n[xtreme]\nrequires = [\"base\"]\nrequired = true\n\n\
+             [[patches]]\nid = \"telemetry-off\"\nfile = \"core/src/otel.rs\"\n",
+        )
+        .unwrap();
+        let relations = inline_relations(&path).unwrap();
+        assert_eq!(relations.requires, vec!["base".to_string()]);
+        assert!(relations.required);
+
+        // A file without the table has no relations; unknown keys in it are an error.
+        std::fs::write(&path, "[meta]\nname = \"privacy\"\n").unwrap();
+        assert!(inline_relations(&path).unwrap().is_empty());
+        std::fs::write(&path, "[xtreme]\nrequire = [\"base\"]\n").unwrap();
+        assert!(inline_relations(&path).is_err());
+    }
+
+    #[test]
+    fn patch_files_with_an_xtreme_table_still_load() {
+        // codex-patcher parses the same file; the extra table must not make it reject it.
+        let dir = crate::test_support::TestRepo::new("patch-xtreme-load");
+        let path = dir.join("privacy.toml");
+        std::fs::write(
+            &path,
+            r#"[meta]
+name = "privacy"
+version_range = ">=0.100.0"
+workspace_relative = true
+
+[xtreme]
+requires = ["base"]
+required = true
+
+[[patches]]
+id = "telemetry-off"
+file = "core/src/otel.rs"
+
+[patches.query]
+type = "text"
+search = "telemetry: true"
+
+[patches.operation]
+type = "replace"
+text = "telemetry: false"
+"#,
+        )
+        .unwrap();
+        let config = load_from_path(&path).unwrap();
+        assert_eq!(config.meta.name, "privacy");
+        assert_eq!(config.patches.len(), 1);
+        assert_eq!(config.patches[0].id, "telemetry-off");
+        assert!(inline_relations(&path).unwrap().required);
+    }
+
+    #[test]
+    fn requirements_pick_the_variant_the_build_would() {
+        let mut g = graph(&[
+            ("privacy", &["base"], &[], &[]),
+            ("base-v0.98", &[], &[], &[]),
+            ("base-v0.99", &[], &[], &[]),
+            ("base", &[], &[], &[]),
+        ]);
+        for (node, range) in g.nodes[1..].iter_mut().zip(["<0.100", "<0.100", ">=0.100"]) {
+            node.version_range = Some(range.to_string());
+        }
+
+        let required = |target| {
+            let added = g.requirements(&paths(&["privacy"]), target).unwrap();
+            added.into_iter().map(|(path, _)| path).collect::<Vec<_>>()
+        };
+        // The newest compatible variant, not the first one listed.
+        assert_eq!(required("rust-v0.99.0"), paths(&["base-v0.99"]));
+        assert_eq!(required("rust-v0.105.0"), paths(&["base"]));
+        // A selected variant satisfies the requirement as it is.
+        assert!(g
+            .requirements(&paths(&["privacy", "base-v0.98"]), "rust-v0.105.0")
+            .unwrap()
+            .is_empty());
+    }
+}
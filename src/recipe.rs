@@ -0,0 +1,378 @@
+//! Recipe files for non-interactive builds.
+//!
+//! A recipe captures every choice the wizard would otherwise prompt for, so the same
+//! build can be reproduced from scripts or CI with `codex-xtreme build --recipe <file>`.
+//!
+//! ```toml
+//! repo = "~/dev/codex"
+//! tag = "rust-v0.105.0"
+//! cherry_picks = ["abc1234"]
+//! patches = ["~/.config/codex-patcher/patches/privacy-v0.105-alpha13.toml"]
+//! run_tests = true
+//! setup_alias = false
+//! cache_dir = "/mnt/team/codex-xtreme-cache"   # optional; shared build cache
+//! worktree = true                              # build in ~/.cache/codex-xtreme/worktrees/<tag>
+//! stash = false                                # in-place builds: stash a dirty clone first
+//! restore = false                              # in-place builds: restore branch + pop stash after
+//! cleanup = true                               # in-place builds: restore touched files after
+//! strict = true                                # refuse to build when selected patches overlap
+//! require_all = true                           # stop when any selected patch fails to apply
+//!
+//! [disabled_patches]                           # optional; patch ids to leave out, per file
+//! "~/.config/codex-patcher/patches/privacy-v0.105-alpha13.toml" = ["statsig-endpoint"]
+//!
+//! [cherry_pick]                                # optional; retries for conflicting picks
+//! retry_strategies = ["patience", "theirs"]    # git cherry-pick -X <strategy>
+//! include_prerequisites = true                 # pick earlier commits touching the same files
+//!
+//! [build]
+//! profile = "xtreme"
+//! cpu_target = "native"
+//! use_mold = false
+//! use_bolt = true
+//! strip_symbols = true
+//! cargo_jobs = 8
+//! ```
+//!
+//! Relative paths are resolved against the directory containing the recipe.
+
+use crate::core::CherryPickOptions;
+use crate::workflow::{BuildOptions, DisabledPatches, OptimizationFlags, PipelineParams};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Recipe {
+    /// Path to the Codex repo root (not codex-rs).
+    pub repo: PathBuf,
+    /// Tag (or branch) to build, e.g. `rust-v0.105.0`.
+    pub tag: String,
+    #[serde(default)]
+    pub cherry_picks: Vec<String>,
+    /// Retry strategies for conflicting cherry-picks (`[cherry_pick]` table).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub cherry_pick: CherryPickOptions,
+    #[serde(default)]
+    pub patches: Vec<PathBuf>,
+    /// Patch ids to leave out, keyed by an entry of `patches`.
+    #[serde(default, skip_serializing_if = "DisabledPatches::is_empty")]
+    pub disabled_patches: DisabledPatches,
+    #[serde(default = "default_true")]
+    pub run_tests: bool,
+    #[serde(default)]
+    pub setup_alias: bool,
+    /// Build cache directory (defaults to `$CODEX_XTREME_CACHE_DIR` or the user cache dir).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<PathBuf>,
+    /// Build in a dedicated git worktree instead of checking out the tag in `repo`.
+    #[serde(default)]
+    pub worktree: bool,
+    /// Stash uncommitted changes in `repo` before checking out the tag (in-place builds).
+    /// Without this a dirty clone is an error.
+    #[serde(default)]
+    pub stash: bool,
+    /// Check the previous branch out again and pop the stash once the build finishes.
+    #[serde(default)]
+    pub restore: bool,
+    /// Restore the files the build touched once the binary is installed (in-place builds).
+    #[serde(default = "default_true")]
+    pub cleanup: bool,
+    /// Fail instead of warning when two selected patch files edit the same lines.
+    #[serde(default)]
+    pub strict: bool,
+    /// Fail before compiling when any patch of a selected file fails or is skipped, as if
+    /// every file were declared `required`.
+    #[serde(default)]
+    pub require_all: bool,
+    #[serde(default)]
+    pub build: RecipeBuild,
+}
+
+/// Mirrors `workflow::BuildOptions` in a serializable form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecipeBuild {
+    pub profile: String,
+    /// Value for `-C target-cpu`; omit to build for the generic target.
+    pub cpu_target: Option<String>,
+    pub use_mold: bool,
+    pub use_bolt: bool,
+    pub strip_symbols: bool,
+    pub cargo_jobs: Option<usize>,
+}
+
+impl Default for RecipeBuild {
+    fn default() -> Self {
+        Self {
+            profile: "xtreme".to_string(),
+            cpu_target: None,
+            use_mold: false,
+            use_bolt: false,
+            strip_symbols: true,
+            cargo_jobs: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+impl Recipe {
+    /// Load a recipe from a TOML file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read recipe: {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse recipe: {}", path.display()))
+    }
+
+    /// Write the recipe as TOML.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = toml::to_string_pretty(self).context("Failed to serialize recipe")?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write recipe: {}", path.display()))
+    }
+
+    /// Capture a wizard run as a recipe.
+    pub fn from_choices(
+        repo: &Path,
+        tag: &str,
+        cherry_picks: &[String],
+        patches: &[PathBuf],
+        options: &BuildOptions,
+        run_tests: bool,
+        setup_alias: bool,
+    ) -> Self {
+        Self {
+            repo: repo.to_path_buf(),
+            tag: tag.to_string(),
+            cherry_picks: cherry_picks.to_vec(),
+            cherry_pick: CherryPickOptions::default(),
+            patches: patches.to_vec(),
+            disabled_patches: DisabledPatches::new(),
+            run_tests,
+            setup_alias,
+            cache_dir: None,
+            worktree: false,
+            stash: false,
+            restore: false,
+            cleanup: true,
+            strict: false,
+            require_all: false,
+            build: RecipeBuild {
+                profile: options.profile.clone(),
+                cpu_target: options.cpu_target.clone(),
+                use_mold: options.optimization.use_mold,
+                use_bolt: options.optimization.use_bolt,
+                strip_symbols: options.strip_symbols,
+                cargo_jobs: options.cargo_jobs,
+            },
+        }
+    }
+
+    /// Validate the recipe and turn it into pipeline parameters.
+    ///
+    /// `base_dir` is the directory containing the recipe; `cargo_jobs` (from `--jobs`)
+    /// overrides the recipe's own value when set.
+    pub fn into_pipeline_params(
+        self,
+        base_dir: &Path,
+        cargo_jobs: Option<usize>,
+    ) -> Result<PipelineParams> {
+        if self.build.profile != "xtreme" && self.build.profile != "release" {
+            bail!(
+                "Unsupported profile '{}' in recipe (expected \"xtreme\" or \"release\")",
+                self.build.profile
+            );
+        }
+        if self.build.cargo_jobs == Some(0) {
+            bail!("Invalid cargo_jobs in recipe: must be >= 1");
+        }
+
+        let invalid: Vec<&String> = self
+            .cherry_picks
+            .iter()
+            .filter(|sha| !crate::core::is_valid_sha(sha))
+            .collect();
+        if !invalid.is_empty() {
+            bail!(
+                "Invalid SHA(s) in recipe: {}",
+                invalid
+                    .iter()
+                    .map(|s| s.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        let repo_path = resolve_path(base_dir, &self.repo);
+        if !repo_path.join(crate::core::CODEX_RS_SUBDIR).exists() {
+            bail!(
+                "Codex workspace not found at {}. Is this a valid Codex repo?",
+                repo_path.join(crate::core::CODEX_RS_SUBDIR).display()
+            );
+        }
+
+        let mut patches = Vec::with_capacity(self.patches.len());
+        for patch in &self.patches {
+            let path = resolve_path(base_dir, patch);
+            if !path.is_file() {
+                bail!("Patch file not found: {}", path.display());
+            }
+            patches.push(path);
+        }
+
+        let mut disabled_patches = DisabledPatches::new();
+        for (patch, ids) in self.disabled_patches {
+            let path = resolve_path(base_dir, &patch);
+            if !patches.contains(&path) {
+                bail!(
+                    "disabled_patches lists {}, which is not in patches",
+                    patch.display()
+                );
+            }
+            disabled_patches.insert(path, ids);
+        }
+
+        let mut optimization = OptimizationFlags {
+            use_mold: self.build.use_mold,
+            use_bolt: self.build.use_bolt,
+        };
+        optimization.enforce_invariants();
+
+        Ok(PipelineParams {
+            repo_path,
+            version: self.tag,
+            cherry_pick_shas: self.cherry_picks,
+            cherry_pick_options: self.cherry_pick,
+            patches,
+            disabled_patches,
+            build_options: BuildOptions {
+                profile: self.build.profile,
+                cpu_target: self.build.cpu_target,
+                optimization,
+                strip_symbols: self.build.strip_symbols,
+                cargo_jobs: cargo_jobs.or(self.build.cargo_jobs),
+                offline: false,
+            },
+            run_tests: self.run_tests,
+            setup_alias: self.setup_alias,
+            cache_dir: self
+                .cache_dir
+                .map(|dir| resolve_path(base_dir, &dir))
+                .or_else(crate::cache::default_dir),
+            use_worktree: self.worktree,
+            allow_stash: self.stash,
+            restore_checkout: self.restore,
+            cleanup: self.cleanup,
+            strict: self.strict,
+            require_all: self.require_all,
+        })
+    }
+}
+
+/// Expand `~` and resolve relative paths against `base_dir`.
+fn resolve_path(base_dir: &Path, path: &Path) -> PathBuf {
+    let expanded = PathBuf::from(shellexpand::tilde(&path.to_string_lossy()).as_ref());
+    if expanded.is_absolute() {
+        expanded
+    } else {
+        base_dir.join(expanded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::CherryPickStrategy;
+
+    #[test]
+    fn minimal_recipe_uses_defaults() {
+        let recipe: Recipe = toml::from_str(
+            r#"
+repo = "~/dev/codex"
+tag = "rust-v0.105.0"
+"#,
+        )
+        .unwrap();
+        assert!(recipe.cherry_picks.is_empty());
+        assert!(recipe.run_tests);
+        assert!(!recipe.setup_alias);
+        assert!(recipe.cleanup);
+        assert!(!recipe.strict);
+        assert!(!recipe.require_all);
+        assert_eq!(recipe.build.profile, "xtreme");
+        assert!(recipe.build.strip_symbols);
+    }
+
+    #[test]
+    fn recipe_round_trips_through_toml() {
+        let recipe: Recipe = toml::from_str(
+            r#"
+repo = "/src/codex"
+tag = "rust-v0.105.0"
+cherry_picks = ["abc1234"]
+patches = ["patches/privacy.toml"]
+
+[disabled_patches]
+"patches/privacy.toml" = ["statsig-endpoint"]
+
+[cherry_pick]
+retry_strategies = ["patience", "theirs"]
+
+[build]
+profile = "release"
+use_bolt = true
+cargo_jobs = 4
+"#,
+        )
+        .unwrap();
+        let text = toml::to_string_pretty(&recipe).unwrap();
+        let parsed: Recipe = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.cherry_picks, vec!["abc1234".to_string()]);
+        assert_eq!(
+            parsed.cherry_pick.retry_strategies,
+            vec![CherryPickStrategy::Patience, CherryPickStrategy::Theirs]
+        );
+        assert!(!parsed.cherry_pick.include_prerequisites);
+        assert_eq!(parsed.patches, vec![PathBuf::from("patches/privacy.toml")]);
+        assert_eq!(
+            parsed.disabled_patches[Path::new("patches/privacy.toml")],
+            vec!["statsig-endpoint".to_string()]
+        );
+        assert_eq!(parsed.build.profile, "release");
+        assert_eq!(parsed.build.cargo_jobs, Some(4));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let result: std::result::Result<Recipe, _> = toml::from_str(
+            r#"
+repo = "/src/codex"
+tag = "rust-v0.105.0"
+stirp_symbols = true
+"#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn relative_paths_resolve_against_recipe_dir() {
+        let base = Path::new("/team/recipes");
+        assert_eq!(
+            resolve_path(base, Path::new("patches/a.toml")),
+            PathBuf::from("/team/recipes/patches/a.toml")
+        );
+        assert_eq!(
+            resolve_path(base, Path::new("/abs/a.toml")),
+            PathBuf::from("/abs/a.toml")
+        );
+    }
+}
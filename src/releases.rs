@@ -0,0 +1,178 @@
+//! Semver-aware release filtering and grouping shared by both frontends.
+//!
+//! The Codex repo carries hundreds of `rust-v*` tags, most of them alphas. Versions are
+//! parsed as semver (tags that do not parse sort last), optionally limited to stable
+//! releases, searched by substring, and grouped by `major.minor` line so an older stable
+//! line is one step away.
+//!
+//! Functions take version strings (`0.99.0-alpha.6`, `rust-v0.98.1`) and return indices into
+//! the caller's list, so `core::Release` and the TUI's `VersionInfo` can share them.
+
+use semver::Version;
+use std::cmp::Ordering;
+
+/// Which releases to show.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReleaseFilter {
+    /// Show alpha/beta/rc builds too.
+    pub include_prereleases: bool,
+    /// Collapse each `major.minor` line to its newest release.
+    pub latest_per_minor: bool,
+    /// Case-insensitive substring match on the version.
+    pub query: String,
+}
+
+/// Parse a version or tag (`rust-v0.99.0`, `v0.99.0`, `0.99.0`).
+pub fn parse_version(version: &str) -> Option<Version> {
+    let trimmed = version
+        .strip_prefix("rust-v")
+        .or_else(|| version.strip_prefix('v'))
+        .unwrap_or(version);
+    Version::parse(trimmed).ok()
+}
+
+/// True for alpha/beta/rc builds. Unparseable versions fall back to a substring check.
+pub fn is_prerelease(version: &str) -> bool {
+    match parse_version(version) {
+        Some(v) => !v.pre.is_empty(),
+        None => ["alpha", "beta", "rc"].iter().any(|p| version.contains(p)),
+    }
+}
+
+/// `major.minor` line of a version (`0.99`), if it parses.
+pub fn minor_line(version: &str) -> Option<String> {
+    parse_version(version).map(|v| format!("{}.{}", v.major, v.minor))
+}
+
+/// Newest first; unparseable versions last, by name.
+fn compare_desc(a: &str, b: &str) -> Ordering {
+    match (parse_version(a), parse_version(b)) {
+        (Some(a), Some(b)) => b.cmp(&a),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+/// Indices of `versions` that pass `filter`, newest first.
+pub fn select_releases(versions: &[&str], filter: &ReleaseFilter) -> Vec<usize> {
+    let query = filter.query.trim().to_lowercase();
+    let mut indices: Vec<usize> = (0..versions.len())
+        .filter(|&i| filter.include_prereleases || !is_prerelease(versions[i]))
+        .filter(|&i| query.is_empty() || versions[i].to_lowercase().contains(&query))
+        .collect();
+    indices.sort_by(|&a, &b| compare_desc(versions[a], versions[b]));
+
+    if filter.latest_per_minor {
+        let mut seen = std::collections::HashSet::new();
+        indices.retain(|&i| seen.insert(minor_line(versions[i]).unwrap_or_default()));
+    }
+    indices
+}
+
+/// Group already-sorted indices by `major.minor` line, preserving order.
+/// Versions that do not parse share an `other` group.
+pub fn group_by_minor(versions: &[&str], indices: &[usize]) -> Vec<(String, Vec<usize>)> {
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    for &i in indices {
+        let line = minor_line(versions[i]).unwrap_or_else(|| "other".to_string());
+        match groups.iter_mut().find(|(l, _)| *l == line) {
+            Some((_, members)) => members.push(i),
+            None => groups.push((line, vec![i])),
+        }
+    }
+    groups
+}
+
+/// The release a changelog for `versions[index]` should start from: the next older stable
+/// release for a stable version, or the next older release of any kind for a pre-release.
+/// Versions that do not parse have no predecessor and are never one.
+pub fn predecessor(versions: &[&str], index: usize) -> Option<usize> {
+    let current = parse_version(versions.get(index)?)?;
+    let stable_only = current.pre.is_empty();
+    (0..versions.len())
+        .filter(|&i| parse_version(versions[i]).is_some_and(|v| v < current))
+        .filter(|&i| !stable_only || !is_prerelease(versions[i]))
+        .min_by(|&a, &b| compare_desc(versions[a], versions[b]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSIONS: &[&str] = &[
+        "0.99.0-alpha.6",
+        "0.98.1",
+        "0.100.0-alpha.1",
+        "0.98.0",
+        "0.99.0-alpha.10",
+        "0.97.3",
+        "not-a-version",
+    ];
+
+    fn names(indices: &[usize]) -> Vec<&'static str> {
+        indices.iter().map(|&i| VERSIONS[i]).collect()
+    }
+
+    #[test]
+    fn sorts_by_semver_and_hides_prereleases_by_default() {
+        let stable = select_releases(VERSIONS, &ReleaseFilter::default());
+        assert_eq!(
+            names(&stable),
+            vec!["0.98.1", "0.98.0", "0.97.3", "not-a-version"]
+        );
+
+        let all = select_releases(
+            VERSIONS,
+            &ReleaseFilter {
+                include_prereleases: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            names(&all)[..4],
+            [
+                "0.100.0-alpha.1",
+                "0.99.0-alpha.10",
+                "0.99.0-alpha.6",
+                "0.98.1"
+            ]
+        );
+    }
+
+    #[test]
+    fn latest_per_minor_and_search() {
+        let latest = select_releases(
+            VERSIONS,
+            &ReleaseFilter {
+                latest_per_minor: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(names(&latest), vec!["0.98.1", "0.97.3", "not-a-version"]);
+
+        let search = select_releases(
+            VERSIONS,
+            &ReleaseFilter {
+                include_prereleases: true,
+                query: "0.99".to_string(),
+                ..Default::default()
+            },
+        );
+        assert_eq!(names(&search), vec!["0.99.0-alpha.10", "0.99.0-alpha.6"]);
+
+        let groups = group_by_minor(VERSIONS, &select_releases(VERSIONS, &Default::default()));
+        let lines: Vec<&str> = groups.iter().map(|(line, _)| line.as_str()).collect();
+        assert_eq!(lines, vec!["0.98", "0.97", "other"]);
+        assert_eq!(groups[0].1.len(), 2);
+    }
+
+    #[test]
+    fn predecessor_skips_prereleases_for_stable_versions() {
+        // 0.98.0 (stable) -> 0.97.3; 0.99.0-alpha.10 -> 0.99.0-alpha.6
+        assert_eq!(predecessor(VERSIONS, 3), Some(5));
+        assert_eq!(predecessor(VERSIONS, 4), Some(0));
+        assert_eq!(predecessor(VERSIONS, 5), None);
+        assert_eq!(predecessor(VERSIONS, 6), None);
+    }
+}
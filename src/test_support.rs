@@ -0,0 +1,65 @@
+//! Scratch git repositories for unit tests.
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A directory under the system temp dir that is removed on drop, even when an assert in
+/// the test panics. Derefs to its path.
+pub(crate) struct TestRepo {
+    path: PathBuf,
+}
+
+impl TestRepo {
+    /// An empty directory named `codex-xtreme-<name>-<pid>`; `name` has to be unique per test.
+    pub(crate) fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("codex-xtreme-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&path).ok();
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    /// [`TestRepo::new`] with a git repository on `branch`.
+    pub(crate) fn init(name: &str, branch: &str) -> Self {
+        let repo = Self::new(name);
+        repo.git(&["init", "-q", "-b", branch]);
+        repo
+    }
+
+    /// Run git in the repo; see [`git_in`].
+    pub(crate) fn git(&self, args: &[&str]) -> String {
+        git_in(&self.path, args)
+    }
+}
+
+impl Deref for TestRepo {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.path).ok();
+    }
+}
+
+/// Run git in `dir` with a throwaway identity, panicking when it fails; returns trimmed stdout.
+pub(crate) fn git_in(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {:?}: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
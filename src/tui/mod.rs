@@ -110,12 +110,28 @@ pub fn spawn_event_reader() -> mpsc::UnboundedReceiver<TermEvent> {
 /// Run the full-screen TUI application.
 ///
 /// This is shared by both the `cx` binary and `codex-xtreme --tui` so behavior stays aligned.
-pub async fn run_app(dev_mode: bool, cargo_jobs: Option<usize>) -> Result<()> {
+pub async fn run_app(
+    dev_mode: bool,
+    cargo_jobs: Option<usize>,
+    use_worktree: bool,
+    offline: bool,
+    strict: bool,
+    require_all: bool,
+    event_stream: Option<crate::events::EventWriter>,
+) -> Result<()> {
     use crate::app::App;
     use ratatui::widgets::Widget;
 
     let mut tui = Tui::new()?;
-    let mut app = App::new(dev_mode, cargo_jobs);
+    let mut app = App::new(
+        dev_mode,
+        cargo_jobs,
+        use_worktree,
+        offline,
+        strict,
+        require_all,
+    );
+    app.event_stream = event_stream;
     let mut events = spawn_event_reader();
 
     loop {
@@ -137,6 +153,7 @@ pub async fn run_app(dev_mode: bool, cargo_jobs: Option<usize>) -> Result<()> {
         }
     }
 
+    app.shutdown();
     tui.restore()?;
     Ok(())
 }
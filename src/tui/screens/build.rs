@@ -75,6 +75,8 @@ pub struct BuildScreen {
     log_lines: Vec<String>,
     patches_applied: Vec<String>,
     patches_skipped: Vec<(String, String)>, // (name, reason)
+    patches_partial: Vec<(String, usize, usize)>, // (name, applied, total)
+    required_failures: Vec<String>,
     error_message: Option<String>,
     binary_path: Option<String>,
     build_time: Option<String>,
@@ -93,6 +95,8 @@ impl BuildScreen {
             log_lines: Vec::new(),
             patches_applied: Vec::new(),
             patches_skipped: Vec::new(),
+            patches_partial: Vec::new(),
+            required_failures: Vec::new(),
             error_message: None,
             binary_path: None,
             build_time: None,
@@ -134,6 +138,16 @@ impl BuildScreen {
         self.patches_skipped.push((name.into(), reason.into()));
     }
 
+    /// A patch file where only `applied` of its `total` patches applied.
+    pub fn add_partial_patch(&mut self, name: impl Into<String>, applied: usize, total: usize) {
+        self.patches_partial.push((name.into(), applied, total));
+    }
+
+    /// A patch in a required file that did not apply, listed on the failure screen.
+    pub fn add_required_failure(&mut self, failure: impl Into<String>) {
+        self.required_failures.push(failure.into());
+    }
+
     pub fn set_version(&mut self, version: impl Into<String>) {
         self.version = version.into();
     }
@@ -356,7 +370,7 @@ fn render_complete(screen: &BuildScreen, area: Rect, buf: &mut Buffer) {
         height: chunks[4].height,
     };
 
-    let title = if screen.patches_skipped.is_empty() {
+    let title = if screen.patches_skipped.is_empty() && screen.patches_partial.is_empty() {
         "INSTALLED PATCHES"
     } else {
         "PATCH RESULTS"
@@ -379,6 +393,19 @@ fn render_complete(screen: &BuildScreen, area: Rect, buf: &mut Buffer) {
         y_offset += 1;
     }
 
+    // Partly applied patch files
+    let remaining_lines = max_lines.saturating_sub(y_offset as usize);
+    for (name, applied, total) in screen.patches_partial.iter().take(remaining_lines) {
+        let line = format!("  ◐ {} ({} of {} applied)", name, applied, total);
+        buf.set_string(
+            patches_area.x + 2,
+            patches_area.y + 1 + y_offset,
+            &line,
+            theme::warning(),
+        );
+        y_offset += 1;
+    }
+
     // Skipped patches
     let remaining_lines = max_lines.saturating_sub(y_offset as usize);
     for (name, reason) in screen.patches_skipped.iter().take(remaining_lines) {
@@ -436,13 +463,20 @@ fn render_error(screen: &BuildScreen, area: Rect, buf: &mut Buffer) {
     let title_x = banner_x + (banner_width.saturating_sub(title.len() as u16)) / 2;
     buf.set_string(title_x, banner_area.y + 1, title, theme::error());
 
-    // Error message
+    // Error message, with the required patches that stopped the build below it
+    let (msg_chunk, failures_chunk) = if screen.required_failures.is_empty() {
+        (chunks[2], None)
+    } else {
+        let parts = Layout::vertical([Constraint::Length(4), Constraint::Min(3)]).split(chunks[2]);
+        (parts[0], Some(parts[1]))
+    };
+
     if let Some(ref msg) = screen.error_message {
         let msg_area = Rect {
             x: area.x + 4,
-            y: chunks[2].y,
+            y: msg_chunk.y,
             width: area.width.saturating_sub(8),
-            height: chunks[2].height,
+            height: msg_chunk.height,
         };
 
         let error_panel = Panel::new().title("ERROR");
@@ -466,6 +500,36 @@ fn render_error(screen: &BuildScreen, area: Rect, buf: &mut Buffer) {
         }
     }
 
+    if let Some(chunk) = failures_chunk {
+        let failures_area = Rect {
+            x: area.x + 4,
+            y: chunk.y,
+            width: area.width.saturating_sub(8),
+            height: chunk.height,
+        };
+        Panel::new()
+            .title("REQUIRED PATCHES")
+            .render(failures_area, buf);
+
+        let max_lines = failures_area.height.saturating_sub(2) as usize;
+        let max_width = failures_area.width.saturating_sub(4) as usize;
+        for (i, failure) in screen.required_failures.iter().take(max_lines).enumerate() {
+            let line = format!("✗ {}", failure);
+            let display = if line.chars().count() > max_width {
+                let cut: String = line.chars().take(max_width.saturating_sub(3)).collect();
+                format!("{}...", cut)
+            } else {
+                line
+            };
+            buf.set_string(
+                failures_area.x + 2,
+                failures_area.y + 1 + i as u16,
+                &display,
+                theme::error(),
+            );
+        }
+    }
+
     // Help
     let help = "Press [Q] to exit or [R] to retry";
     let help_x = area.x + (area.width.saturating_sub(help.len() as u16)) / 2;
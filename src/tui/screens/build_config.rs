@@ -1,5 +1,6 @@
 //! Build configuration screen for CPU target, linker, and optimization options
 
+use crate::config::BuildDefaults;
 use crate::tui::theme::{self, center_x};
 use crate::tui::widgets::Panel;
 use crate::workflow::{OptimizationFlags, OptimizationMode};
@@ -113,6 +114,14 @@ impl BuildConfigScreen {
                 available: true,
                 detail: "recommended".to_string(),
             },
+            ConfigOption {
+                name: "Restore workspace".to_string(),
+                description: "Undo patches, cherry-picks and profile in your clone after install"
+                    .to_string(),
+                enabled: true,
+                available: true,
+                detail: "recommended".to_string(),
+            },
         ];
 
         let mut s = Self {
@@ -129,6 +138,28 @@ impl BuildConfigScreen {
         s
     }
 
+    /// Pre-fill the toggles from the user config's `[defaults]`.
+    pub fn with_defaults(mut self, defaults: &BuildDefaults) -> Self {
+        self.optimization_mode = defaults.optimization_mode(self.has_mold, self.has_bolt);
+        let toggles = [
+            (1, defaults.optimize_for_cpu),
+            (2, defaults.use_mold && self.has_mold),
+            (3, defaults.use_bolt && self.has_bolt),
+            (4, defaults.profile != "release"),
+            (5, defaults.strip_symbols),
+            (6, defaults.run_tests),
+            (7, defaults.setup_alias),
+            (8, defaults.cleanup),
+        ];
+        for (idx, enabled) in toggles {
+            if let Some(opt) = self.options.get_mut(idx) {
+                opt.enabled = enabled;
+            }
+        }
+        self.sync_from_mode();
+        self
+    }
+
     pub fn tick(&mut self) {
         self.frame += 1;
     }
@@ -218,6 +249,10 @@ impl BuildConfigScreen {
         self.options.get(7).map(|o| o.enabled).unwrap_or(true)
     }
 
+    pub fn cleanup(&self) -> bool {
+        self.options.get(8).map(|o| o.enabled).unwrap_or(true)
+    }
+
     pub fn frame(&self) -> u64 {
         self.frame
     }
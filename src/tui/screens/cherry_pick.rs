@@ -1,5 +1,6 @@
-//! Dev-mode cherry-pick screen (comma-separated SHAs)
+//! Dev-mode commit browser for cherry-picks (`git log <tag>..origin/main`)
 
+use crate::core::{CherryPickOptions, CherryPickStrategy, CommitInfo};
 use crate::tui::theme::{self, center_x, jp};
 use crate::tui::widgets::Panel;
 use ratatui::{
@@ -8,93 +9,257 @@ use ratatui::{
     style::{Modifier, Style},
     widgets::Widget,
 };
+use std::collections::HashSet;
 use unicode_width::UnicodeWidthStr;
 
-/// Text input screen for cherry-picking commit SHAs.
+/// Which text field (if any) is receiving keystrokes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CherryPickInput {
+    Browse,
+    PathFilter,
+    Manual,
+}
+
+/// Commit browser: multi-select commits, filter by path, or type SHAs / `A..B` ranges.
 pub struct CherryPickScreen {
     frame: u64,
     target_tag: String,
-    value: String,
-    cursor_pos: usize,
-    placeholder: String,
+    /// Branch the commits are listed from (`None` when no upstream branch exists).
+    upstream: Option<String>,
+    commits: Vec<CommitInfo>,
+    cursor: usize,
+    selected: HashSet<String>,
+    path_filter: String,
+    /// Extra comma-separated SHAs / ranges
+    manual: String,
+    input: CherryPickInput,
+    /// Filter text being edited (applied on Enter)
+    filter_draft: String,
+    preview_open: bool,
+    /// (sha, diff lines) for the preview pane
+    preview: Option<(String, Vec<String>)>,
+    preview_scroll: usize,
+    /// What to do when a pick conflicts (cycled with [R])
+    options: CherryPickOptions,
     status: Option<String>,
 }
 
 impl CherryPickScreen {
-    pub fn new(target_tag: impl Into<String>) -> Self {
+    pub fn new(target_tag: impl Into<String>, upstream: Option<String>) -> Self {
         Self {
             frame: 0,
             target_tag: target_tag.into(),
-            value: String::new(),
-            cursor_pos: 0,
-            placeholder: "abc1234, def5678".to_string(),
+            upstream,
+            commits: Vec::new(),
+            cursor: 0,
+            selected: HashSet::new(),
+            path_filter: String::new(),
+            manual: String::new(),
+            input: CherryPickInput::Browse,
+            filter_draft: String::new(),
+            preview_open: false,
+            preview: None,
+            preview_scroll: 0,
+            options: CherryPickOptions::default(),
             status: None,
         }
     }
 
-    pub fn set_value(&mut self, text: impl Into<String>) {
-        self.value = text.into();
-        self.cursor_pos = self.value.chars().count();
-    }
-
     pub fn tick(&mut self) {
         self.frame += 1;
     }
 
-    /// Convert character position to byte index.
-    fn char_to_byte_index(&self, char_pos: usize) -> usize {
-        self.value
-            .char_indices()
-            .nth(char_pos)
-            .map(|(i, _)| i)
-            .unwrap_or(self.value.len())
+    /// Replace the listed commits (after loading or changing the path filter).
+    /// Selections survive even if the commit is filtered out of view.
+    pub fn set_commits(&mut self, commits: Vec<CommitInfo>) {
+        self.commits = commits;
+        self.cursor = 0;
+        self.preview = None;
+        self.preview_scroll = 0;
+    }
+
+    pub fn target_tag(&self) -> &str {
+        &self.target_tag
+    }
+
+    pub fn upstream(&self) -> Option<&str> {
+        self.upstream.as_deref()
+    }
+
+    pub fn path_filter(&self) -> &str {
+        &self.path_filter
+    }
+
+    /// True while a text field is being edited (keys go to the field, not app shortcuts).
+    pub fn is_editing(&self) -> bool {
+        self.input != CherryPickInput::Browse
+    }
+
+    pub fn select_prev(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.preview_scroll = 0;
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.cursor + 1 < self.commits.len() {
+            self.cursor += 1;
+            self.preview_scroll = 0;
+        }
+    }
+
+    pub fn toggle_current(&mut self) {
+        if let Some(commit) = self.commits.get(self.cursor) {
+            if !self.selected.remove(&commit.sha) {
+                self.selected.insert(commit.sha.clone());
+            }
+        }
+    }
+
+    /// Pre-select commits (e.g. when navigating back). SHAs not in the list, or short ones,
+    /// go to the manual field.
+    pub fn set_selected(&mut self, shas: &[String]) {
+        let mut manual = Vec::new();
+        for sha in shas {
+            if self.commits.iter().any(|c| c.sha == *sha) {
+                self.selected.insert(sha.clone());
+            } else {
+                manual.push(sha.clone());
+            }
+        }
+        self.manual = manual.join(", ");
+    }
+
+    /// Selected full SHAs in list order.
+    pub fn selected_shas(&self) -> Vec<String> {
+        let mut shas: Vec<String> = self
+            .commits
+            .iter()
+            .filter(|c| self.selected.contains(&c.sha))
+            .map(|c| c.sha.clone())
+            .collect();
+        let mut hidden: Vec<String> = self
+            .selected
+            .iter()
+            .filter(|sha| !shas.contains(sha))
+            .cloned()
+            .collect();
+        hidden.sort();
+        shas.extend(hidden);
+        shas
+    }
+
+    pub fn manual(&self) -> &str {
+        &self.manual
+    }
+
+    pub fn start_filter_edit(&mut self) {
+        self.filter_draft = self.path_filter.clone();
+        self.input = CherryPickInput::PathFilter;
+    }
+
+    pub fn start_manual_edit(&mut self) {
+        self.input = CherryPickInput::Manual;
+    }
+
+    /// Leave the text field. Returns the new path filter if it changed and should be applied.
+    pub fn finish_edit(&mut self, apply: bool) -> Option<String> {
+        let mode = std::mem::replace(&mut self.input, CherryPickInput::Browse);
+        if mode == CherryPickInput::PathFilter && apply && self.filter_draft != self.path_filter {
+            self.path_filter = self.filter_draft.trim().to_string();
+            return Some(self.path_filter.clone());
+        }
+        None
     }
 
     pub fn insert_char(&mut self, c: char) {
-        let byte_idx = self.char_to_byte_index(self.cursor_pos);
-        self.value.insert(byte_idx, c);
-        self.cursor_pos += 1;
+        match self.input {
+            CherryPickInput::PathFilter => self.filter_draft.push(c),
+            CherryPickInput::Manual => self.manual.push(c),
+            CherryPickInput::Browse => {}
+        }
     }
 
     pub fn delete_char(&mut self) {
-        if self.cursor_pos > 0 {
-            self.cursor_pos -= 1;
-            let byte_idx = self.char_to_byte_index(self.cursor_pos);
-            self.value.remove(byte_idx);
+        match self.input {
+            CherryPickInput::PathFilter => {
+                self.filter_draft.pop();
+            }
+            CherryPickInput::Manual => {
+                self.manual.pop();
+            }
+            CherryPickInput::Browse => {}
         }
     }
 
-    pub fn delete_forward(&mut self) {
-        let char_count = self.value.chars().count();
-        if self.cursor_pos < char_count {
-            let byte_idx = self.char_to_byte_index(self.cursor_pos);
-            self.value.remove(byte_idx);
-        }
+    pub fn toggle_preview(&mut self) {
+        self.preview_open = !self.preview_open;
+        self.preview_scroll = 0;
     }
 
-    pub fn move_left(&mut self) {
-        if self.cursor_pos > 0 {
-            self.cursor_pos -= 1;
+    /// SHA whose diff the preview pane needs but does not have yet.
+    pub fn preview_needed(&self) -> Option<String> {
+        if !self.preview_open {
+            return None;
+        }
+        let commit = self.commits.get(self.cursor)?;
+        match &self.preview {
+            Some((sha, _)) if *sha == commit.sha => None,
+            _ => Some(commit.sha.clone()),
         }
     }
 
-    pub fn move_right(&mut self) {
-        let char_count = self.value.chars().count();
-        if self.cursor_pos < char_count {
-            self.cursor_pos += 1;
+    pub fn set_preview(&mut self, sha: String, diff: &str) {
+        self.preview = Some((sha, diff.lines().map(|l| l.replace('\t', "    ")).collect()));
+    }
+
+    pub fn scroll_preview(&mut self, delta: isize) {
+        self.preview_scroll = self.preview_scroll.saturating_add_signed(delta);
+        if let Some((_, lines)) = &self.preview {
+            self.preview_scroll = self.preview_scroll.min(lines.len().saturating_sub(1));
         }
     }
 
-    pub fn move_home(&mut self) {
-        self.cursor_pos = 0;
+    /// Cycle skip → retry with -X patience/theirs → retry + prerequisites.
+    pub fn cycle_retry(&mut self) {
+        self.options = if self.options.include_prerequisites {
+            CherryPickOptions::default()
+        } else if self.options.retry_strategies.is_empty() {
+            CherryPickOptions {
+                retry_strategies: vec![CherryPickStrategy::Patience, CherryPickStrategy::Theirs],
+                include_prerequisites: false,
+            }
+        } else {
+            CherryPickOptions {
+                include_prerequisites: true,
+                ..self.options.clone()
+            }
+        };
+    }
+
+    pub fn cherry_pick_options(&self) -> &CherryPickOptions {
+        &self.options
     }
 
-    pub fn move_end(&mut self) {
-        self.cursor_pos = self.value.chars().count();
+    pub fn set_cherry_pick_options(&mut self, options: CherryPickOptions) {
+        self.options = options;
     }
 
-    pub fn value(&self) -> &str {
-        &self.value
+    fn retry_label(&self) -> String {
+        let strategies = self
+            .options
+            .retry_strategies
+            .iter()
+            .map(|s| format!("-X {}", s.name()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        match (strategies.is_empty(), self.options.include_prerequisites) {
+            (true, false) => "skip".to_string(),
+            (true, true) => "add prerequisites".to_string(),
+            (false, false) => format!("retry {}", strategies),
+            (false, true) => format!("retry {}, add prerequisites", strategies),
+        }
     }
 
     pub fn status(&self) -> Option<&str> {
@@ -104,6 +269,209 @@ impl CherryPickScreen {
     pub fn set_status(&mut self, status: Option<String>) {
         self.status = status;
     }
+
+    fn render_list(&self, area: Rect, buf: &mut Buffer) {
+        let range = match &self.upstream {
+            Some(upstream) => format!("{}..{}", self.target_tag, upstream),
+            None => "no upstream branch".to_string(),
+        };
+        let title = if self.path_filter.is_empty() {
+            format!("COMMITS {} ({})", range, self.commits.len())
+        } else {
+            format!(
+                "COMMITS {} -- {} ({})",
+                range,
+                self.path_filter,
+                self.commits.len()
+            )
+        };
+        Panel::new()
+            .title(&title)
+            .title_jp(jp::CHERRY_PICK)
+            .focused(self.input == CherryPickInput::Browse)
+            .render(area, buf);
+
+        let inner_x = area.x + 2;
+        let rows = area.height.saturating_sub(2) as usize;
+        let width = area.width.saturating_sub(4) as usize;
+
+        if self.commits.is_empty() {
+            buf.set_string(
+                inner_x,
+                area.y + 1,
+                "No commits to show — type SHAs or ranges with [M]",
+                theme::muted(),
+            );
+            return;
+        }
+
+        // Keep the cursor visible
+        let scroll = (self.cursor + 1).saturating_sub(rows);
+
+        for (row, (idx, commit)) in self
+            .commits
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(rows)
+            .enumerate()
+        {
+            let y = area.y + 1 + row as u16;
+            let is_cursor = idx == self.cursor;
+            let is_selected = self.selected.contains(&commit.sha);
+
+            let cursor_char = if is_cursor {
+                let chars = ['▸', '▹'];
+                chars[(self.frame / 8) as usize % chars.len()]
+            } else {
+                ' '
+            };
+            buf.set_string(inner_x, y, cursor_char.to_string(), theme::cursor());
+
+            let checkbox = if is_selected { "[x]" } else { "[ ]" };
+            let checkbox_style = if is_selected {
+                theme::success()
+            } else {
+                theme::secondary()
+            };
+            buf.set_string(inner_x + 2, y, checkbox, checkbox_style);
+            buf.set_string(inner_x + 6, y, &commit.short_sha, theme::code());
+
+            let text_x = inner_x + 7 + commit.short_sha.len() as u16;
+            let avail = width.saturating_sub(7 + commit.short_sha.len());
+            let meta = format!("  {} {}", commit.date, commit.author);
+            let subject_w = avail.saturating_sub(meta.chars().count());
+            let subject = truncate_chars(&commit.subject, subject_w);
+            let subject_style = if is_cursor {
+                theme::focused()
+            } else {
+                theme::normal()
+            };
+            buf.set_string(text_x, y, &subject, subject_style);
+            if avail > meta.chars().count() + 8 {
+                let meta_x = text_x + subject.chars().count() as u16;
+                buf.set_string(meta_x, y, &meta, theme::muted());
+            }
+        }
+    }
+
+    fn render_detail(&self, area: Rect, buf: &mut Buffer) {
+        let Some(commit) = self.commits.get(self.cursor) else {
+            Panel::new().title("DETAILS").render(area, buf);
+            return;
+        };
+
+        if self.preview_open {
+            Panel::new()
+                .title("DIFF [PGUP/PGDN]")
+                .focused(true)
+                .render(area, buf);
+            let rows = area.height.saturating_sub(2) as usize;
+            let width = area.width.saturating_sub(4) as usize;
+            let Some((_, lines)) = &self.preview else {
+                buf.set_string(area.x + 2, area.y + 1, "Loading diff...", theme::muted());
+                return;
+            };
+            for (row, line) in lines
+                .iter()
+                .skip(self.preview_scroll)
+                .take(rows)
+                .enumerate()
+            {
+                let style = if line.starts_with("+++") || line.starts_with("---") {
+                    theme::secondary()
+                } else if line.starts_with('+') {
+                    theme::success()
+                } else if line.starts_with('-') {
+                    theme::error()
+                } else if line.starts_with("@@") {
+                    theme::active()
+                } else {
+                    theme::normal()
+                };
+                buf.set_string(
+                    area.x + 2,
+                    area.y + 1 + row as u16,
+                    truncate_chars(line, width),
+                    style,
+                );
+            }
+            return;
+        }
+
+        Panel::new().title("DETAILS").render(area, buf);
+        let width = area.width.saturating_sub(4) as usize;
+        let mut lines = vec![
+            (
+                format!("{} {}", commit.short_sha, commit.subject),
+                theme::normal(),
+            ),
+            (
+                format!("{} · {}", commit.author, commit.date),
+                theme::secondary(),
+            ),
+        ];
+        for file in &commit.files {
+            lines.push((format!("  {}", file), theme::muted()));
+        }
+        let rows = area.height.saturating_sub(2) as usize;
+        if lines.len() > rows && rows > 0 {
+            let hidden = lines.len() - rows + 1;
+            lines.truncate(rows - 1);
+            lines.push((format!("  … {} more files", hidden), theme::muted()));
+        }
+        for (row, (line, style)) in lines.iter().enumerate() {
+            buf.set_string(
+                area.x + 2,
+                area.y + 1 + row as u16,
+                truncate_chars(line, width),
+                *style,
+            );
+        }
+    }
+
+    fn render_input(
+        &self,
+        x: u16,
+        y: u16,
+        label: &str,
+        value: &str,
+        active: bool,
+        buf: &mut Buffer,
+    ) {
+        buf.set_string(x, y, label, theme::secondary());
+        let value_x = x + label.len() as u16;
+        let (text, style) = if value.is_empty() && !active {
+            ("(none)", theme::muted())
+        } else {
+            (value, theme::normal())
+        };
+        buf.set_string(value_x, y, text, style);
+        if active && (self.frame / 30).is_multiple_of(2) {
+            let cursor_x = value_x + UnicodeWidthStr::width(value) as u16;
+            buf.set_string(
+                cursor_x,
+                y,
+                "▎",
+                Style::default()
+                    .fg(theme::CYAN)
+                    .add_modifier(Modifier::BOLD),
+            );
+        }
+    }
+}
+
+/// Truncate to `max` characters (commit subjects and diffs are not ASCII-only).
+fn truncate_chars(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else if max > 1 {
+        let mut out: String = s.chars().take(max - 1).collect();
+        out.push('…');
+        out
+    } else {
+        String::new()
+    }
 }
 
 impl Widget for &CherryPickScreen {
@@ -117,10 +485,8 @@ impl Widget for &CherryPickScreen {
 
         let chunks = Layout::vertical([
             Constraint::Length(4), // Header
-            Constraint::Length(1), // Spacer
-            Constraint::Length(5), // Input panel
-            Constraint::Length(3), // Info
-            Constraint::Min(2),    // Spacer
+            Constraint::Min(8),    // Commit list + details/diff
+            Constraint::Length(3), // Filter + manual input
             Constraint::Length(2), // Help
         ])
         .split(area);
@@ -131,102 +497,63 @@ impl Widget for &CherryPickScreen {
         let header_x = center_x(area.x, area.width, header_w);
         buf.set_string(header_x, chunks[0].y + 1, &header_line, theme::title());
 
-        // Input panel
-        let input_area = Rect {
-            x: chunks[2].x + 4,
-            y: chunks[2].y,
-            width: chunks[2].width.saturating_sub(8),
-            height: chunks[2].height,
+        let body = Rect {
+            x: chunks[1].x + 2,
+            y: chunks[1].y,
+            width: chunks[1].width.saturating_sub(4),
+            height: chunks[1].height,
         };
-
-        let panel = Panel::new()
-            .title("Cherry-pick commits (comma-separated SHAs, empty to skip)")
-            .focused(true);
-        panel.render(input_area, buf);
-
-        // Input value
-        let value_y = input_area.y + 2;
-        let value_x = input_area.x + 3;
-        let max_visible = input_area.width.saturating_sub(6) as usize;
-
-        let display_value = if self.value.is_empty() {
-            &self.placeholder
+        let panes = if self.preview_open {
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).split(body)
         } else {
-            &self.value
+            Layout::vertical([Constraint::Min(5), Constraint::Length(7)]).split(body)
         };
+        self.render_list(panes[0], buf);
+        self.render_detail(panes[1], buf);
 
-        let value_style = if self.value.is_empty() {
-            theme::muted()
+        // Inputs
+        let input_x = chunks[2].x + 4;
+        let filter_value = if self.input == CherryPickInput::PathFilter {
+            &self.filter_draft
         } else {
-            theme::normal()
+            &self.path_filter
         };
-
-        // Truncate if needed, keeping cursor visible (using character counts).
-        let char_count = display_value.chars().count();
-        let (display, cursor_offset) = if char_count > max_visible {
-            let start_char = self.cursor_pos.saturating_sub(max_visible / 2);
-            let end_char = (start_char + max_visible).min(char_count);
-            let start_char = end_char.saturating_sub(max_visible);
-
-            let start_byte = display_value
-                .char_indices()
-                .nth(start_char)
-                .map(|(i, _)| i)
-                .unwrap_or(0);
-            let end_byte = display_value
-                .char_indices()
-                .nth(end_char)
-                .map(|(i, _)| i)
-                .unwrap_or(display_value.len());
-
-            (
-                &display_value[start_byte..end_byte],
-                self.cursor_pos - start_char,
-            )
-        } else {
-            (display_value.as_str(), self.cursor_pos)
-        };
-
-        buf.set_string(value_x, value_y, display, value_style);
-
-        // Cursor
-        let cursor_visible = (self.frame / 30).is_multiple_of(2);
-        if cursor_visible {
-            let cursor_x = value_x + cursor_offset as u16;
-            buf.set_string(
-                cursor_x,
-                value_y,
-                "▎",
-                Style::default()
-                    .fg(theme::CYAN)
-                    .add_modifier(Modifier::BOLD),
-            );
-        }
-
-        // Info text
-        let url = format!(
-            "Compare: https://github.com/openai/codex/compare/{}...main",
-            self.target_tag
+        self.render_input(
+            input_x,
+            chunks[2].y,
+            "Path filter: ",
+            filter_value,
+            self.input == CherryPickInput::PathFilter,
+            buf,
+        );
+        self.render_input(
+            input_x,
+            chunks[2].y + 1,
+            "Extra SHAs/ranges: ",
+            &self.manual,
+            self.input == CherryPickInput::Manual,
+            buf,
         );
-        let url_w = UnicodeWidthStr::width(url.as_str()) as u16;
-        let url_x = center_x(area.x, area.width, url_w);
-        buf.set_string(url_x, chunks[3].y, &url, theme::secondary());
-
         if let Some(status) = &self.status {
-            let status_w = UnicodeWidthStr::width(status.as_str()) as u16;
-            let status_x = center_x(area.x, area.width, status_w);
-            buf.set_string(status_x, chunks[3].y + 1, status, theme::warning());
+            buf.set_string(input_x, chunks[2].y + 2, status, theme::warning());
         } else {
-            let hint = "Tip: use 7+ hex chars per SHA; invalid entries will be ignored";
-            let hint_w = UnicodeWidthStr::width(hint) as u16;
-            let hint_x = center_x(area.x, area.width, hint_w);
-            buf.set_string(hint_x, chunks[3].y + 1, hint, theme::muted());
+            let summary = format!(
+                "{} selected · applied oldest-first · on conflict: {} [R]",
+                self.selected.len(),
+                self.retry_label()
+            );
+            buf.set_string(input_x, chunks[2].y + 2, summary, theme::muted());
         }
 
         // Help text
-        let help = "[ENTER] Continue  [ESC] Back";
+        let help = match self.input {
+            CherryPickInput::Browse => {
+                "[SPACE] Toggle  [D] Diff  [/] Path  [M] SHAs/ranges  [ENTER] Continue  [ESC] Back"
+            }
+            _ => "[ENTER] Apply  [ESC] Cancel",
+        };
         let help_w = UnicodeWidthStr::width(help) as u16;
         let help_x = center_x(area.x, area.width, help_w);
-        buf.set_string(help_x, chunks[5].y, help, theme::muted());
+        buf.set_string(help_x, chunks[3].y, help, theme::muted());
     }
 }
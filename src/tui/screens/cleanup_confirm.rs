@@ -0,0 +1,160 @@
+//! Offer to clean up what an interrupted build left in the selected clone
+
+use crate::cleanup::Leftovers;
+use crate::tui::theme::{self, center_x, jp};
+use crate::tui::widgets::Panel;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    widgets::Widget,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Lists leftover build state and asks before restoring it.
+pub struct CleanupConfirmScreen {
+    frame: u64,
+    repo: String,
+    leftovers: Leftovers,
+    /// Also check the pre-build branch out and pop the stash (journalled builds only).
+    restore_after: bool,
+    error: Option<String>,
+}
+
+impl CleanupConfirmScreen {
+    pub fn new(repo: impl Into<String>, leftovers: Leftovers) -> Self {
+        let restore_after = leftovers
+            .journal
+            .as_ref()
+            .is_some_and(|j| j.stash.is_some());
+        Self {
+            frame: 0,
+            repo: repo.into(),
+            leftovers,
+            restore_after,
+            error: None,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn leftovers(&self) -> &Leftovers {
+        &self.leftovers
+    }
+
+    /// True when the journal knows where the clone was before the build.
+    pub fn can_restore(&self) -> bool {
+        self.leftovers.journal.is_some()
+    }
+
+    pub fn toggle_restore(&mut self) {
+        if self.can_restore() {
+            self.restore_after = !self.restore_after;
+        }
+    }
+
+    pub fn restore_after(&self) -> bool {
+        self.restore_after && self.can_restore()
+    }
+
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+}
+
+impl Widget for &CleanupConfirmScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Clear background
+        for y in area.y..(area.y + area.height) {
+            for x in area.x..(area.x + area.width) {
+                buf.set_string(x, y, " ", Style::default().bg(theme::BG_VOID));
+            }
+        }
+
+        let chunks = Layout::vertical([
+            Constraint::Length(4), // Header
+            Constraint::Length(1), // Spacer
+            Constraint::Min(5),    // Leftover list
+            Constraint::Length(3), // Restore toggle / error
+            Constraint::Length(2), // Help
+        ])
+        .split(area);
+
+        // Header
+        let header_line = format!("░▒▓█ INTERRUPTED BUILD //{} █▓▒░", jp::MODIFIED);
+        let header_w = UnicodeWidthStr::width(header_line.as_str()) as u16;
+        let header_x = center_x(area.x, area.width, header_w);
+        buf.set_string(header_x, chunks[0].y + 1, &header_line, theme::warning());
+
+        // Leftover list: summary line, then what a cleanup would undo
+        let list_area = Rect {
+            x: chunks[2].x + 4,
+            y: chunks[2].y,
+            width: chunks[2].width.saturating_sub(8),
+            height: chunks[2].height,
+        };
+        Panel::new()
+            .title(&self.repo)
+            .focused(true)
+            .render(list_area, buf);
+
+        let lines = self.leftovers.describe();
+        let rows = list_area.height.saturating_sub(2) as usize;
+        let max_w = list_area.width.saturating_sub(4) as usize;
+        let shown = if lines.len() > rows {
+            rows.saturating_sub(1)
+        } else {
+            lines.len()
+        };
+        for (i, line) in lines.iter().take(shown).enumerate() {
+            let text: String = line.chars().take(max_w).collect();
+            let style = if i == 0 {
+                theme::normal()
+            } else {
+                theme::secondary()
+            };
+            buf.set_string(list_area.x + 2, list_area.y + 1 + i as u16, &text, style);
+        }
+        if shown < lines.len() {
+            buf.set_string(
+                list_area.x + 2,
+                list_area.y + 1 + shown as u16,
+                format!("… and {} more", lines.len() - shown),
+                theme::muted(),
+            );
+        }
+
+        // Restore toggle (or the error from the last attempt)
+        let (line, style) = match (&self.error, &self.leftovers.journal) {
+            (Some(error), _) => (error.clone(), theme::error()),
+            (None, Some(journal)) => {
+                let checkbox = if self.restore_after { "[✓]" } else { "[ ]" };
+                let what = if journal.stash.is_some() {
+                    format!("Then check out {} and pop the stash", journal.previous_ref)
+                } else {
+                    format!("Then check out {}", journal.previous_ref)
+                };
+                (format!("{} {}", checkbox, what), theme::focused())
+            }
+            (None, None) => (
+                "Aborts the cherry-pick and removes the injected profile".to_string(),
+                theme::muted(),
+            ),
+        };
+        let line_w = UnicodeWidthStr::width(line.as_str()) as u16;
+        let line_x = center_x(area.x, area.width, line_w);
+        buf.set_string(line_x, chunks[3].y + 1, &line, style);
+
+        // Help text
+        let help = if self.can_restore() {
+            "[ENTER] Clean up  [SPACE] Toggle restore  [S] Skip  [ESC] Back"
+        } else {
+            "[ENTER] Clean up  [S] Skip  [ESC] Back"
+        };
+        let help_w = UnicodeWidthStr::width(help) as u16;
+        let help_x = center_x(area.x, area.width, help_w);
+        buf.set_string(help_x, chunks[4].y, help, theme::muted());
+    }
+}
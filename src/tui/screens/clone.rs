@@ -1,7 +1,7 @@
 //! Clone progress screen
 
 use crate::tui::theme::{self, center_x, jp};
-use crate::tui::widgets::Panel;
+use crate::tui::widgets::{Panel, ProgressBar};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Layout, Rect},
@@ -22,8 +22,13 @@ pub enum CloneStatus {
 pub struct CloneScreen {
     frame: u64,
     destination: String,
+    source: String,
     status: CloneStatus,
     progress_text: String,
+    /// Overall clone progress (0.0..=1.0) from git's object counts
+    fraction: f64,
+    /// Cancel requested; waiting for git to stop and the partial clone to be removed
+    cancelling: bool,
     error_message: Option<String>,
     /// Frames since completion (for auto-advance)
     complete_frames: u64,
@@ -34,13 +39,22 @@ impl CloneScreen {
         Self {
             frame: 0,
             destination: destination.into(),
+            source: crate::core::CODEX_REPO_URL.to_string(),
             status: CloneStatus::Cloning,
             progress_text: "Initializing...".to_string(),
+            fraction: 0.0,
+            cancelling: false,
             error_message: None,
             complete_frames: 0,
         }
     }
 
+    /// Source shown in the status panel (URL, local path or bundle, plus branch/history)
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.source = source.into();
+        self
+    }
+
     pub fn tick(&mut self) {
         self.frame += 1;
         if self.status == CloneStatus::Complete {
@@ -70,8 +84,23 @@ impl CloneScreen {
         self.progress_text = text.into();
     }
 
+    /// Progress only moves forward (git reports each phase from 0%).
+    pub fn set_fraction(&mut self, fraction: f64) {
+        self.fraction = self.fraction.max(fraction.clamp(0.0, 1.0));
+    }
+
+    pub fn set_cancelling(&mut self) {
+        self.cancelling = true;
+        self.progress_text = "Cancelling, removing partial clone...".to_string();
+    }
+
+    pub fn is_cancelling(&self) -> bool {
+        self.cancelling
+    }
+
     pub fn set_complete(&mut self) {
         self.status = CloneStatus::Complete;
+        self.fraction = 1.0;
         self.progress_text = "Clone complete!".to_string();
     }
 
@@ -84,6 +113,10 @@ impl CloneScreen {
         self.status == CloneStatus::Complete
     }
 
+    pub fn is_cloning(&self) -> bool {
+        self.status == CloneStatus::Cloning
+    }
+
     pub fn is_error(&self) -> bool {
         self.status == CloneStatus::Error
     }
@@ -109,7 +142,7 @@ impl Widget for &CloneScreen {
         let chunks = Layout::vertical([
             Constraint::Length(4), // Header
             Constraint::Length(1), // Spacer
-            Constraint::Length(6), // Status panel
+            Constraint::Length(7), // Status panel
             Constraint::Min(4),    // Log/progress
             Constraint::Length(2), // Help
         ])
@@ -157,19 +190,29 @@ impl Widget for &CloneScreen {
         buf.set_string(
             status_area.x + 2,
             status_area.y + 2,
-            "Source: https://github.com/openai/codex.git",
+            format!("Source: {}", self.source),
             theme::secondary(),
         );
 
         // Progress or error
         match self.status {
             CloneStatus::Cloning => {
+                let bar_area = Rect {
+                    x: status_area.x + 2,
+                    y: status_area.y + 4,
+                    width: status_area.width.saturating_sub(4),
+                    height: 1,
+                };
+                ProgressBar::new(self.fraction)
+                    .frame(self.frame)
+                    .render(bar_area, buf);
+
                 let spinner_chars = theme::spinners::BRAILLE;
                 let spinner = spinner_chars[(self.frame / 4) as usize % spinner_chars.len()];
                 let progress_line = format!("{} {}", spinner, self.progress_text);
                 buf.set_string(
                     status_area.x + 2,
-                    status_area.y + 4,
+                    status_area.y + 5,
                     &progress_line,
                     theme::active(),
                 );
@@ -196,7 +239,8 @@ impl Widget for &CloneScreen {
 
         // Help
         let help = match self.status {
-            CloneStatus::Cloning => "Cloning repository... Press [Q] to cancel".to_string(),
+            CloneStatus::Cloning if self.cancelling => "Cancelling...".to_string(),
+            CloneStatus::Cloning => "Cloning repository... Press [ESC] to cancel".to_string(),
             CloneStatus::Complete => {
                 let countdown = self.countdown();
                 if countdown > 0 {
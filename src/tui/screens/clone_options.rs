@@ -0,0 +1,336 @@
+//! Clone options screen: source, destination, branch, history depth and sparse checkout
+
+use crate::core::{CloneHistory, CloneOptions, CODEX_RS_SUBDIR};
+use crate::tui::theme::{self, center_x, jp};
+use crate::tui::widgets::Panel;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    widgets::Widget,
+};
+use unicode_width::UnicodeWidthStr;
+
+const SOURCE: usize = 0;
+const DESTINATION: usize = 1;
+const BRANCH: usize = 2;
+const HISTORY: usize = 3;
+const DEPTH: usize = 4;
+const SPARSE: usize = 5;
+const FIELD_COUNT: usize = 6;
+
+/// History choices, cycled with Space / ←→
+#[derive(Clone, Copy, PartialEq)]
+enum HistoryKind {
+    Shallow,
+    Blobless,
+    Full,
+}
+
+/// Clone options screen
+pub struct CloneOptionsScreen {
+    frame: u64,
+    source: String,
+    destination: String,
+    branch: String,
+    history: HistoryKind,
+    depth: String,
+    sparse: bool,
+    cursor: usize,
+    /// Validation error for the last submit
+    error: Option<String>,
+}
+
+impl CloneOptionsScreen {
+    pub fn new(destination: impl Into<String>, options: &CloneOptions) -> Self {
+        let (history, depth) = match options.history {
+            CloneHistory::Shallow(depth) => (HistoryKind::Shallow, depth),
+            CloneHistory::Blobless => (HistoryKind::Blobless, 100),
+            CloneHistory::Full => (HistoryKind::Full, 100),
+        };
+        Self {
+            frame: 0,
+            source: options.source.clone(),
+            destination: destination.into(),
+            branch: options.branch.clone().unwrap_or_default(),
+            history,
+            depth: depth.to_string(),
+            sparse: options.sparse,
+            cursor: DESTINATION,
+            error: None,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.frame += 1;
+    }
+
+    fn field_enabled(&self, idx: usize) -> bool {
+        idx != DEPTH || self.history == HistoryKind::Shallow
+    }
+
+    pub fn select_next(&mut self) {
+        let mut next = self.cursor + 1;
+        while next < FIELD_COUNT && !self.field_enabled(next) {
+            next += 1;
+        }
+        if next < FIELD_COUNT {
+            self.cursor = next;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        let mut prev = self.cursor;
+        while prev > 0 {
+            prev -= 1;
+            if self.field_enabled(prev) {
+                self.cursor = prev;
+                return;
+            }
+        }
+    }
+
+    fn current_text(&mut self) -> Option<&mut String> {
+        match self.cursor {
+            SOURCE => Some(&mut self.source),
+            DESTINATION => Some(&mut self.destination),
+            BRANCH => Some(&mut self.branch),
+            DEPTH => Some(&mut self.depth),
+            _ => None,
+        }
+    }
+
+    /// Type into the focused text field; Space toggles the history/sparse fields.
+    pub fn insert_char(&mut self, c: char) {
+        self.error = None;
+        let digits_only = self.cursor == DEPTH;
+        match self.current_text() {
+            Some(_) if digits_only && !c.is_ascii_digit() => {}
+            Some(text) => text.push(c),
+            None if c == ' ' => self.toggle_current(),
+            None => {}
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        self.error = None;
+        if let Some(text) = self.current_text() {
+            text.pop();
+        }
+    }
+
+    /// Cycle the history mode or flip sparse checkout.
+    pub fn toggle_current(&mut self) {
+        self.error = None;
+        match self.cursor {
+            HISTORY => {
+                self.history = match self.history {
+                    HistoryKind::Shallow => HistoryKind::Blobless,
+                    HistoryKind::Blobless => HistoryKind::Full,
+                    HistoryKind::Full => HistoryKind::Shallow,
+                }
+            }
+            SPARSE => self.sparse = !self.sparse,
+            _ => {}
+        }
+    }
+
+    /// True when ←→ should cycle the focused field instead of being ignored.
+    pub fn is_selector_focused(&self) -> bool {
+        self.cursor == HISTORY
+    }
+
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
+    pub fn destination(&self) -> &str {
+        self.destination.trim()
+    }
+
+    /// The entered options, validated (source exists or is a URL, depth parses).
+    pub fn options(&self) -> anyhow::Result<CloneOptions> {
+        if self.destination().is_empty() {
+            anyhow::bail!("Enter a destination directory");
+        }
+        let history = match self.history {
+            HistoryKind::Shallow => match self.depth.parse() {
+                Ok(depth) => CloneHistory::Shallow(depth),
+                Err(_) => anyhow::bail!("Enter a depth (number of commits)"),
+            },
+            HistoryKind::Blobless => CloneHistory::Blobless,
+            HistoryKind::Full => CloneHistory::Full,
+        };
+        let branch = self.branch.trim();
+        let options = CloneOptions {
+            source: self.source.trim().to_string(),
+            branch: (!branch.is_empty()).then(|| branch.to_string()),
+            history,
+            sparse: self.sparse,
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    fn rows(&self) -> [(&'static str, String, &'static str); FIELD_COUNT] {
+        let history = match self.history {
+            HistoryKind::Shallow => "Shallow",
+            HistoryKind::Blobless => "Blobless",
+            HistoryKind::Full => "Full",
+        };
+        [
+            (
+                "Source",
+                self.source.clone(),
+                "URL (fork/mirror), local repo path, or .bundle file",
+            ),
+            (
+                "Destination",
+                self.destination.clone(),
+                "Directory to clone into",
+            ),
+            (
+                "Branch",
+                self.branch.clone(),
+                "Blank for the source's default branch",
+            ),
+            (
+                "History",
+                format!("< {} >", history),
+                "Shallow: last N commits · Blobless: all commits, files on demand",
+            ),
+            (
+                "Depth",
+                self.depth.clone(),
+                "Commits to fetch (shallow only)",
+            ),
+            (
+                "Sparse checkout",
+                if self.sparse { "[✓]" } else { "[ ]" }.to_string(),
+                "Only check out codex-rs/",
+            ),
+        ]
+    }
+}
+
+impl Widget for &CloneOptionsScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Clear background
+        for y in area.y..(area.y + area.height) {
+            for x in area.x..(area.x + area.width) {
+                buf.set_string(x, y, " ", Style::default().bg(theme::BG_VOID));
+            }
+        }
+
+        let chunks = Layout::vertical([
+            Constraint::Length(4),  // Header
+            Constraint::Length(1),  // Spacer
+            Constraint::Length(14), // Options panel
+            Constraint::Length(2),  // Info / error
+            Constraint::Min(1),     // Spacer
+            Constraint::Length(2),  // Help
+        ])
+        .split(area);
+
+        // Header
+        let header_line = format!("░▒▓█ CLONE REPOSITORY //{} █▓▒░", jp::TARGET_SELECT);
+        let header_w = UnicodeWidthStr::width(header_line.as_str()) as u16;
+        let header_x = center_x(area.x, area.width, header_w);
+        buf.set_string(header_x, chunks[0].y + 1, &header_line, theme::title());
+
+        let panel_area = Rect {
+            x: chunks[2].x + 4,
+            y: chunks[2].y,
+            width: chunks[2].width.saturating_sub(8),
+            height: chunks[2].height,
+        };
+        Panel::new()
+            .title("CLONE OPTIONS")
+            .focused(true)
+            .render(panel_area, buf);
+
+        let inner_x = panel_area.x + 2;
+        let value_x = inner_x + 20;
+        let max_value = panel_area.width.saturating_sub(24) as usize;
+        let cursor_visible = (self.frame / 30).is_multiple_of(2);
+
+        for (idx, (label, value, description)) in self.rows().into_iter().enumerate() {
+            let y = panel_area.y + 1 + idx as u16 * 2;
+            if y + 1 >= panel_area.y + panel_area.height {
+                break;
+            }
+            let is_cursor = idx == self.cursor;
+            let enabled = self.field_enabled(idx);
+
+            if is_cursor {
+                let chars = ['▸', '▹'];
+                let c = chars[(self.frame / 8) as usize % chars.len()];
+                buf.set_string(inner_x, y, c.to_string(), theme::cursor());
+            }
+            let label_style = if !enabled {
+                theme::muted()
+            } else if is_cursor {
+                theme::focused()
+            } else {
+                theme::normal()
+            };
+            buf.set_string(inner_x + 2, y, label, label_style);
+
+            // Long values (URLs, paths) keep their tail visible.
+            let chars = value.chars().count();
+            let shown: String = if chars > max_value {
+                value.chars().skip(chars - max_value).collect()
+            } else {
+                value
+            };
+            let value_style = match idx {
+                _ if !enabled => theme::muted(),
+                SPARSE if self.sparse => theme::success(),
+                HISTORY | SPARSE => theme::secondary(),
+                _ => theme::normal(),
+            };
+            buf.set_string(value_x, y, &shown, value_style);
+            let is_text = !matches!(idx, HISTORY | SPARSE);
+            if is_cursor && is_text && cursor_visible {
+                let cursor_x = value_x + UnicodeWidthStr::width(shown.as_str()) as u16;
+                buf.set_string(cursor_x, y, "▎", theme::cursor());
+            }
+
+            buf.set_string(
+                inner_x + 4,
+                y + 1,
+                format!("└─ {}", description),
+                theme::muted(),
+            );
+        }
+
+        // Info text (or the validation error)
+        let info = match &self.error {
+            Some(error) => (error.clone(), theme::error()),
+            None if self.sparse => (
+                format!("Sparse: only {}/ will be checked out", CODEX_RS_SUBDIR),
+                theme::secondary(),
+            ),
+            None => (
+                "Bundles and local mirrors work offline".to_string(),
+                theme::secondary(),
+            ),
+        };
+        let info_w = UnicodeWidthStr::width(info.0.as_str()) as u16;
+        let info_x = center_x(area.x, area.width, info_w);
+        buf.set_string(info_x, chunks[3].y, &info.0, info.1);
+
+        let help = match self.cursor {
+            HISTORY => "[↑↓] Field  [←→/SPACE] Change  [ENTER] Clone  [ESC] Cancel",
+            SPARSE => "[↑↓] Field  [SPACE] Toggle  [ENTER] Clone  [ESC] Cancel",
+            _ => "[↑↓] Field  [TYPE] Edit  [ENTER] Clone  [ESC] Cancel",
+        };
+        let help_w = UnicodeWidthStr::width(help) as u16;
+        let help_x = center_x(area.x, area.width, help_w);
+        buf.set_string(help_x, chunks[5].y, help, theme::muted());
+    }
+}
@@ -1,4 +1,4 @@
-//! Text input screen for clone destination
+//! Text input screen (clone destination, custom repo path)
 
 use crate::tui::theme::{self, center_x, jp};
 use crate::tui::widgets::Panel;
@@ -13,23 +13,52 @@ use unicode_width::UnicodeWidthStr;
 /// Text input screen
 pub struct InputScreen {
     frame: u64,
+    header: String,
     prompt: String,
     value: String,
     cursor_pos: usize,
     placeholder: String,
+    info: String,
+    action: String,
+    /// Validation error for the last submitted value
+    error: Option<String>,
 }
 
 impl InputScreen {
     pub fn new(prompt: impl Into<String>) -> Self {
         Self {
             frame: 0,
+            header: "CLONE REPOSITORY".to_string(),
             prompt: prompt.into(),
             value: String::new(),
             cursor_pos: 0,
             placeholder: String::new(),
+            info: "Will clone: https://github.com/openai/codex.git".to_string(),
+            action: "Clone".to_string(),
+            error: None,
         }
     }
 
+    pub fn header(mut self, text: impl Into<String>) -> Self {
+        self.header = text.into();
+        self
+    }
+
+    pub fn info(mut self, text: impl Into<String>) -> Self {
+        self.info = text.into();
+        self
+    }
+
+    /// Label for the Enter key in the help line
+    pub fn action(mut self, text: impl Into<String>) -> Self {
+        self.action = text.into();
+        self
+    }
+
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
     pub fn placeholder(mut self, text: impl Into<String>) -> Self {
         self.placeholder = text.into();
         self
@@ -55,12 +84,14 @@ impl InputScreen {
     }
 
     pub fn insert_char(&mut self, c: char) {
+        self.error = None;
         let byte_idx = self.char_to_byte_index(self.cursor_pos);
         self.value.insert(byte_idx, c);
         self.cursor_pos += 1;
     }
 
     pub fn delete_char(&mut self) {
+        self.error = None;
         if self.cursor_pos > 0 {
             self.cursor_pos -= 1;
             let byte_idx = self.char_to_byte_index(self.cursor_pos);
@@ -126,7 +157,7 @@ impl Widget for &InputScreen {
         .split(area);
 
         // Header
-        let header_line = format!("░▒▓█ CLONE REPOSITORY //{} █▓▒░", jp::TARGET_SELECT);
+        let header_line = format!("░▒▓█ {} //{} █▓▒░", self.header, jp::TARGET_SELECT);
         let header_w = UnicodeWidthStr::width(header_line.as_str()) as u16;
         let header_x = center_x(area.x, area.width, header_w);
         buf.set_string(header_x, chunks[0].y + 1, &header_line, theme::title());
@@ -211,13 +242,17 @@ impl Widget for &InputScreen {
             );
         }
 
-        // Info text
-        let info = "Will clone: https://github.com/openai/codex.git";
-        let info_x = area.x + (area.width.saturating_sub(info.len() as u16)) / 2;
-        buf.set_string(info_x, chunks[3].y, info, theme::secondary());
+        // Info text (or the validation error)
+        let (info, info_style) = match &self.error {
+            Some(error) => (error.as_str(), theme::error()),
+            None => (self.info.as_str(), theme::secondary()),
+        };
+        let info_w = UnicodeWidthStr::width(info) as u16;
+        let info_x = center_x(area.x, area.width, info_w);
+        buf.set_string(info_x, chunks[3].y, info, info_style);
 
         // Help text
-        let help = "[ENTER] Clone  [ESC] Cancel";
+        let help = format!("[ENTER] {}  [ESC] Cancel", self.action);
         let help_x = area.x + (area.width.saturating_sub(help.len() as u16)) / 2;
         buf.set_string(help_x, chunks[5].y, help, theme::muted());
     }
@@ -4,18 +4,24 @@ mod boot;
 mod build;
 mod build_config;
 mod cherry_pick;
+mod cleanup_confirm;
 mod clone;
+mod clone_options;
 mod input;
 mod patch_select;
 mod repo_select;
+mod stash_confirm;
 mod version_select;
 
 pub use boot::BootScreen;
 pub use build::{BuildPhase, BuildScreen};
 pub use build_config::BuildConfigScreen;
 pub use cherry_pick::CherryPickScreen;
+pub use cleanup_confirm::CleanupConfirmScreen;
 pub use clone::{CloneScreen, CloneStatus};
+pub use clone_options::CloneOptionsScreen;
 pub use input::InputScreen;
-pub use patch_select::{PatchInfo, PatchSelectScreen};
+pub use patch_select::{PatchEntry, PatchInfo, PatchSelectScreen};
 pub use repo_select::{RepoInfo, RepoSelectScreen};
-pub use version_select::{VersionInfo, VersionSelectScreen};
+pub use stash_confirm::StashConfirmScreen;
+pub use version_select::{FetchState, VersionInfo, VersionSelectScreen};
@@ -1,14 +1,18 @@
-//! Patch selection screen with checkboxes
+//! Patch selection screen with checkboxes, per-patch toggles, a dry-run preview and
+//! conflict resolution
 
-use crate::tui::theme::{self, center_x, jp, truncate_str};
+use crate::manifest::PatchStatus;
+use crate::patch_order::PatchGraph;
+use crate::tui::theme::{self, center_x, jp};
 use crate::tui::widgets::Panel;
+use crate::workflow::{preview_summary, DisabledPatches, PatchConflict, PatchFilePreview};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Layout, Rect},
     style::Style,
     widgets::Widget,
 };
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use unicode_width::UnicodeWidthStr;
 
 /// Patch file information
@@ -20,14 +24,77 @@ pub struct PatchInfo {
     pub patch_count: usize,
     pub selected: bool,
     pub compatible: bool,
+    /// `version_range` from the patch file (`None`: any version).
+    pub version_range: Option<String>,
+    /// Why the file does not match the target version (set when not `compatible`).
+    pub incompatible_reason: Option<String>,
+    /// Version-specific variants share a family (`privacy-v0.105-alpha13` → `privacy`).
+    pub family: String,
+    /// Patch directory the file was loaded from, with its layer (e.g. `~/team (project)`).
+    pub source: String,
+    /// Individual patches in the file, shown when it is expanded.
+    pub entries: Vec<PatchEntry>,
+    /// Ids of `entries` switched off for this build.
+    pub disabled: Vec<String>,
+}
+
+/// One patch inside a patch file.
+#[derive(Clone)]
+pub struct PatchEntry {
+    pub id: String,
+    /// Target file, relative to the workspace.
+    pub file: String,
+}
+
+impl PatchInfo {
+    /// Patches that would be applied if the file is selected.
+    pub fn enabled_count(&self) -> usize {
+        self.patch_count.saturating_sub(self.disabled.len())
+    }
+}
+
+/// A line in the patch list: a patch file, or one patch of an expanded file.
+#[derive(Clone, Copy, PartialEq)]
+enum Row {
+    File(usize),
+    Patch(usize, usize),
 }
 
 /// Patch selection screen
 pub struct PatchSelectScreen {
     frame: u64,
     patches: Vec<PatchInfo>,
+    /// Index into `rows()`.
     cursor: usize,
+    /// Patch files whose individual patches are listed.
+    expanded: Vec<usize>,
     target_version: String,
+    /// Shadowed patch files and duplicate names found while loading.
+    warnings: Vec<String>,
+    /// Dry-run output (title, styled lines, summary) while the preview is open.
+    preview: Option<PreviewView>,
+    /// Overlapping patches left to resolve before continuing.
+    conflicts: Option<ConflictView>,
+    /// Declared `requires` / `after` / `conflicts_with` relations between the files.
+    order: PatchGraph,
+    /// Every file is required (see [`PatchSelectScreen::is_required`]).
+    require_all: bool,
+    /// Result of the last toggle or ordering check (auto-selected requirements, errors).
+    status: Option<(String, Style)>,
+}
+
+struct ConflictView {
+    /// Unresolved conflicts; the first one is being asked about.
+    remaining: Vec<PatchConflict>,
+    /// Strict mode: keeping both sides is not offered.
+    strict: bool,
+}
+
+struct PreviewView {
+    title: String,
+    lines: Vec<(String, Style)>,
+    summary: (String, Style),
+    scroll: usize,
 }
 
 impl PatchSelectScreen {
@@ -36,16 +103,54 @@ impl PatchSelectScreen {
             frame: 0,
             patches,
             cursor: 0,
+            expanded: Vec::new(),
             target_version,
+            warnings: Vec::new(),
+            preview: None,
+            conflicts: None,
+            order: PatchGraph::default(),
+            require_all: false,
+            status: None,
         }
     }
 
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    pub fn with_order(mut self, order: PatchGraph) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn with_require_all(mut self, require_all: bool) -> Self {
+        self.require_all = require_all;
+        self
+    }
+
+    /// True when every patch in `path` has to apply, so none can be switched off.
+    fn is_required(&self, path: &Path) -> bool {
+        self.require_all || self.order.is_required(path)
+    }
+
     pub fn tick(&mut self) {
         self.frame += 1;
     }
 
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (idx, patch) in self.patches.iter().enumerate() {
+            rows.push(Row::File(idx));
+            if self.expanded.contains(&idx) {
+                rows.extend((0..patch.entries.len()).map(|entry| Row::Patch(idx, entry)));
+            }
+        }
+        rows
+    }
+
     pub fn select_next(&mut self) {
-        if self.cursor < self.patches.len().saturating_sub(1) {
+        if self.cursor < self.rows().len().saturating_sub(1) {
             self.cursor += 1;
         }
     }
@@ -57,16 +162,118 @@ impl PatchSelectScreen {
     }
 
     pub fn toggle_current(&mut self) {
-        if let Some(patch) = self.patches.get_mut(self.cursor) {
-            // Match CLI behavior: allow selecting "incompatible" patches too.
-            // The UI will surface incompatibility in the compatibility panel styling.
-            patch.selected = !patch.selected;
+        match self.rows().get(self.cursor) {
+            Some(&Row::File(idx)) => {
+                // Match CLI behavior: allow selecting "incompatible" patches too.
+                // The UI will surface incompatibility in the compatibility panel styling.
+                let selected = !self.patches[idx].selected;
+                self.status = None;
+                if selected {
+                    self.select_file(idx);
+                    self.select_requirements();
+                } else {
+                    self.patches[idx].selected = false;
+                }
+            }
+            Some(&Row::Patch(idx, entry)) => {
+                let required = self.is_required(&self.patches[idx].path);
+                let patch = &mut self.patches[idx];
+                let id = &patch.entries[entry].id;
+                if let Some(pos) = patch.disabled.iter().position(|d| d == id) {
+                    // Turning a patch back on also selects its file.
+                    patch.disabled.remove(pos);
+                    patch.selected = true;
+                } else if required {
+                    self.status = Some((
+                        format!(
+                            "{} is required: its patches cannot be switched off",
+                            patch.name
+                        ),
+                        theme::error(),
+                    ));
+                } else {
+                    patch.disabled.push(id.clone());
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Select one file; picking a member of a family drops its siblings.
+    fn select_file(&mut self, idx: usize) {
+        let family = self.patches[idx].family.clone();
+        for (other, patch) in self.patches.iter_mut().enumerate() {
+            if patch.family == family {
+                patch.selected = other == idx;
+            }
         }
     }
 
+    /// Also select what the selected files declare they require.
+    fn select_requirements(&mut self) {
+        let selected: Vec<PathBuf> = self.selected_patch_paths().into_iter().cloned().collect();
+        match self.order.requirements(&selected, &self.target_version) {
+            Ok(added) if added.is_empty() => {}
+            Ok(added) => {
+                for (path, _) in &added {
+                    if let Some(idx) = self.patches.iter().position(|p| &p.path == path) {
+                        self.select_file(idx);
+                    }
+                }
+                let reasons: Vec<String> = added.into_iter().map(|(_, reason)| reason).collect();
+                self.status = Some((
+                    format!("Also selected {}", reasons.join(", ")),
+                    theme::active(),
+                ));
+            }
+            Err(e) => self.status = Some((e.to_string(), theme::error())),
+        }
+    }
+
+    /// The selected files in the order they will be applied, or why there is none.
+    pub fn ordered_selection(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let selected: Vec<PathBuf> = self.selected_patch_paths().into_iter().cloned().collect();
+        self.order.order(&selected)
+    }
+
+    /// Show `message` in the compatibility panel (e.g. why the selection cannot be applied).
+    pub fn set_status_error(&mut self, message: impl Into<String>) {
+        self.status = Some((message.into(), theme::error()));
+    }
+
+    /// List the individual patches of the file under the cursor.
+    pub fn expand_current(&mut self) {
+        if let Some(&Row::File(idx)) = self.rows().get(self.cursor) {
+            if !self.patches[idx].entries.is_empty() && !self.expanded.contains(&idx) {
+                self.expanded.push(idx);
+            }
+        }
+    }
+
+    /// Fold the file under the cursor (or the one its patch belongs to) back up.
+    pub fn collapse_current(&mut self) {
+        let idx = match self.rows().get(self.cursor) {
+            Some(&Row::File(idx)) | Some(&Row::Patch(idx, _)) => idx,
+            None => return,
+        };
+        self.expanded.retain(|&e| e != idx);
+        self.cursor = self
+            .rows()
+            .iter()
+            .position(|&row| row == Row::File(idx))
+            .unwrap_or(0);
+    }
+
+    /// Select everything, except variants whose family has a compatible member.
     pub fn select_all(&mut self) {
+        let families_with_match: Vec<String> = self
+            .patches
+            .iter()
+            .filter(|p| p.compatible)
+            .map(|p| p.family.clone())
+            .collect();
         for patch in &mut self.patches {
-            patch.selected = true;
+            patch.selected = patch.compatible || !families_with_match.contains(&patch.family);
         }
     }
 
@@ -88,9 +295,152 @@ impl PatchSelectScreen {
             .collect()
     }
 
+    /// Switched-off patch ids of the selected files.
+    pub fn disabled_patches(&self) -> DisabledPatches {
+        self.patches
+            .iter()
+            .filter(|p| p.selected && !p.disabled.is_empty())
+            .map(|p| (p.path.clone(), p.disabled.clone()))
+            .collect()
+    }
+
     pub fn frame(&self) -> u64 {
         self.frame
     }
+
+    pub fn is_previewing(&self) -> bool {
+        self.preview.is_some()
+    }
+
+    pub fn close_preview(&mut self) {
+        self.preview = None;
+    }
+
+    /// Show a dry run of the selected patches against `source` (what was patched).
+    pub fn set_preview(&mut self, source: &str, previews: &[PatchFilePreview]) {
+        let mut lines = Vec::new();
+        for file in previews {
+            lines.push((
+                format!("── {} ({} patches)", file.name, file.patches.len()),
+                theme::title(),
+            ));
+            for patch in &file.patches {
+                let style = match patch.record.status {
+                    PatchStatus::Applied => theme::success(),
+                    PatchStatus::AlreadyApplied
+                    | PatchStatus::SkippedVersion
+                    | PatchStatus::Disabled => theme::muted(),
+                    PatchStatus::Failed | PatchStatus::Error => theme::error(),
+                };
+                lines.push((patch.summary(), style));
+                for line in patch.diff.lines() {
+                    let style = if line.starts_with("+++")
+                        || line.starts_with("---")
+                        || line.starts_with("diff ")
+                        || line.starts_with("index ")
+                    {
+                        theme::secondary()
+                    } else if line.starts_with('+') {
+                        theme::success()
+                    } else if line.starts_with('-') {
+                        theme::error()
+                    } else if line.starts_with("@@") {
+                        theme::active()
+                    } else {
+                        theme::normal()
+                    };
+                    lines.push((format!("  {}", line.replace('\t', "    ")), style));
+                }
+            }
+            lines.push((String::new(), theme::normal()));
+        }
+        let problems = previews
+            .iter()
+            .flat_map(|p| &p.patches)
+            .any(|p| p.is_problem());
+        let summary_style = if problems {
+            theme::warning()
+        } else {
+            theme::success()
+        };
+        self.preview = Some(PreviewView {
+            title: format!("PREVIEW · {}", source),
+            lines,
+            summary: (preview_summary(previews), summary_style),
+            scroll: 0,
+        });
+    }
+
+    /// Open the preview with just an error (nothing selected, preview failed).
+    pub fn set_preview_error(&mut self, error: impl Into<String>) {
+        self.preview = Some(PreviewView {
+            title: "PREVIEW".to_string(),
+            lines: Vec::new(),
+            summary: (error.into(), theme::error()),
+            scroll: 0,
+        });
+    }
+
+    pub fn is_resolving_conflicts(&self) -> bool {
+        self.conflicts.is_some()
+    }
+
+    pub fn close_conflicts(&mut self) {
+        self.conflicts = None;
+    }
+
+    /// Ask about `conflicts` one at a time (nothing to ask when empty).
+    pub fn set_conflicts(&mut self, conflicts: Vec<PatchConflict>, strict: bool) {
+        self.conflicts = (!conflicts.is_empty()).then_some(ConflictView {
+            remaining: conflicts,
+            strict,
+        });
+    }
+
+    /// Switch off one side of the current conflict (`first`: the side applied first).
+    ///
+    /// A required file cannot apply in part, so its whole file is deselected instead.
+    /// Conflicts that involved the dropped patch (or file) are resolved with it; the view
+    /// closes once none are left.
+    pub fn drop_conflict_side(&mut self, first: bool) {
+        let Some(conflict) = self.conflicts.as_ref().and_then(|v| v.remaining.first()) else {
+            return;
+        };
+        let edit = if first {
+            conflict.first.clone()
+        } else {
+            conflict.second.clone()
+        };
+        let whole_file = self.is_required(&edit.patch_file);
+        if let Some(patch) = self.patches.iter_mut().find(|p| p.path == edit.patch_file) {
+            if whole_file {
+                patch.selected = false;
+                patch.disabled.clear();
+            } else if !patch.disabled.contains(&edit.patch_id) {
+                patch.disabled.push(edit.patch_id.clone());
+            }
+        }
+        let Some(view) = &mut self.conflicts else {
+            return;
+        };
+        let involves = |e: &crate::workflow::PatchEdit| {
+            e.patch_file == edit.patch_file && (whole_file || e.patch_id == edit.patch_id)
+        };
+        view.remaining
+            .retain(|c| !involves(&c.first) && !involves(&c.second));
+        if view.remaining.is_empty() {
+            self.conflicts = None;
+        }
+    }
+
+    pub fn scroll_preview(&mut self, delta: isize) {
+        if let Some(preview) = &mut self.preview {
+            preview.scroll = preview
+                .scroll
+                .saturating_add_signed(delta)
+                .min(preview.lines.len().saturating_sub(1));
+        }
+    }
 }
 
 impl Widget for &PatchSelectScreen {
@@ -106,7 +456,7 @@ impl Widget for &PatchSelectScreen {
             Constraint::Length(4), // Header
             Constraint::Length(1), // Spacer
             Constraint::Min(10),   // Patch list
-            Constraint::Length(4), // Compatibility info
+            Constraint::Length(6), // Compatibility info, version match, source, warnings
             Constraint::Length(2), // Help
         ])
         .split(area);
@@ -117,6 +467,15 @@ impl Widget for &PatchSelectScreen {
         let header_x = center_x(area.x, area.width, header_w);
         buf.set_string(header_x, chunks[0].y + 1, &header_line, theme::title());
 
+        if let Some(preview) = &self.preview {
+            render_preview(preview, area, &chunks, buf);
+            return;
+        }
+        if let Some(conflicts) = &self.conflicts {
+            render_conflicts(conflicts, |path| self.is_required(path), area, &chunks, buf);
+            return;
+        }
+
         // Patch list panel
         let list_area = Rect {
             x: chunks[2].x + 2,
@@ -128,16 +487,15 @@ impl Widget for &PatchSelectScreen {
         let panel = Panel::new().title("PATCHES").focused(true);
         panel.render(list_area, buf);
 
-        // Patch list content (compact 1-line per patch)
+        // Patch list content (compact 1-line per patch file, expanded files list their patches)
         let inner_y = list_area.y + 1;
         let inner_x = list_area.x + 2;
+        let visible = list_area.height.saturating_sub(2) as usize;
+        let rows = self.rows();
+        let offset = self.cursor.saturating_sub(visible.saturating_sub(1));
 
-        for (idx, patch) in self.patches.iter().enumerate() {
-            let y = inner_y + idx as u16;
-            if y >= list_area.y + list_area.height - 1 {
-                break;
-            }
-
+        for (idx, row) in rows.iter().enumerate().skip(offset).take(visible) {
+            let y = inner_y + (idx - offset) as u16;
             let is_cursor = idx == self.cursor;
 
             // Cursor indicator
@@ -149,19 +507,53 @@ impl Widget for &PatchSelectScreen {
             };
             buf.set_string(inner_x, y, cursor_char.to_string(), theme::cursor());
 
-            // Checkbox
-            let checkbox = if patch.selected { "[x]" } else { "[ ]" };
-            let checkbox_style = if !patch.compatible {
+            let (patch, entry) = match *row {
+                Row::File(file) => (&self.patches[file], None),
+                Row::Patch(file, entry) => (
+                    &self.patches[file],
+                    Some(&self.patches[file].entries[entry]),
+                ),
+            };
+
+            // Checkbox (patches are indented under their file)
+            let (checkbox_x, checked) = match entry {
+                None => (inner_x + 2, patch.selected),
+                Some(entry) => (inner_x + 6, !patch.disabled.contains(&entry.id)),
+            };
+            let checkbox = if checked { "[x]" } else { "[ ]" };
+            let checkbox_style = if !patch.compatible || (entry.is_some() && !patch.selected) {
                 theme::muted()
-            } else if patch.selected {
+            } else if checked {
                 theme::success()
             } else {
                 theme::secondary()
             };
-            buf.set_string(inner_x + 2, y, checkbox, checkbox_style);
+            buf.set_string(checkbox_x, y, checkbox, checkbox_style);
 
-            // Name with patch count
-            let name_with_count = format!("{} ({})", patch.name, patch.patch_count);
+            // Name with patch count (enabled/total once some are switched off)
+            let count = if patch.disabled.is_empty() {
+                patch.patch_count.to_string()
+            } else {
+                format!("{}/{}", patch.enabled_count(), patch.patch_count)
+            };
+            // Later members of a family hang off the first one.
+            let branch = match *row {
+                Row::File(file) if file > 0 && self.patches[file - 1].family == patch.family => {
+                    "└ "
+                }
+                _ => "",
+            };
+            let (label, detail) = match (entry, &patch.incompatible_reason) {
+                (None, Some(reason)) => (
+                    format!("{}{} ({})", branch, patch.name, count),
+                    format!("✗ {}", reason),
+                ),
+                (None, None) => (
+                    format!("{}{} ({})", branch, patch.name, count),
+                    patch.description.clone(),
+                ),
+                (Some(entry), _) => (entry.id.clone(), entry.file.clone()),
+            };
             let name_style = if !patch.compatible {
                 theme::muted()
             } else if is_cursor {
@@ -169,16 +561,17 @@ impl Widget for &PatchSelectScreen {
             } else {
                 theme::normal()
             };
-            buf.set_string(inner_x + 6, y, &name_with_count, name_style);
+            let name_x = checkbox_x + 4;
+            buf.set_string(name_x, y, &label, name_style);
 
-            // Description (truncate to fit remaining width)
-            let name_end = inner_x + 6 + name_with_count.len() as u16 + 2;
+            // Description or target file (truncate to fit remaining width)
+            let name_end = name_x + UnicodeWidthStr::width(label.as_str()) as u16 + 2;
             let available_width = list_area
                 .x
                 .saturating_add(list_area.width)
                 .saturating_sub(name_end + 2) as usize;
             if available_width > 4 {
-                let truncated = truncate_str(&patch.description, available_width);
+                let truncated: String = detail.chars().take(available_width).collect();
                 buf.set_string(name_end, y, truncated, theme::muted());
             }
         }
@@ -203,7 +596,7 @@ impl Widget for &PatchSelectScreen {
             .patches
             .iter()
             .filter(|p| p.selected)
-            .map(|p| p.patch_count)
+            .map(|p| p.enabled_count())
             .sum();
         let compat_msg = format!(
             "  {} files / {} patches selected / {} compatible with {}",
@@ -229,9 +622,229 @@ impl Widget for &PatchSelectScreen {
             compat_style,
         );
 
+        // Where the file under the cursor comes from, then load warnings
+        let max_w = compat_area.width.saturating_sub(6) as usize;
+        let cursor_file = match self.rows().get(self.cursor) {
+            Some(&Row::File(idx)) | Some(&Row::Patch(idx, _)) => self.patches.get(idx),
+            None => None,
+        };
+        if let Some(patch) = cursor_file {
+            let range = match &patch.version_range {
+                Some(range) => format!("version_range \"{}\"", range),
+                None => "no version_range".to_string(),
+            };
+            let (line, style) = match &patch.incompatible_reason {
+                Some(reason) => (
+                    format!(
+                        "  {}: {} vs {} ✗ {}",
+                        patch.name, range, self.target_version, reason
+                    ),
+                    theme::warning(),
+                ),
+                None => (
+                    format!("  {}: {} vs {} ✓", patch.name, range, self.target_version),
+                    theme::muted(),
+                ),
+            };
+            buf.set_string(
+                compat_area.x + 2,
+                compat_area.y + 2,
+                line.chars().take(max_w).collect::<String>(),
+                style,
+            );
+            let relations = self.order.describe(&patch.path);
+            if !patch.source.is_empty() || relations.is_some() {
+                let line = match (&patch.source, relations) {
+                    (source, Some(relations)) if source.is_empty() => format!("  {}", relations),
+                    (source, Some(relations)) => format!("  from {} · {}", source, relations),
+                    (source, None) => format!("  from {}", source),
+                };
+                buf.set_string(
+                    compat_area.x + 2,
+                    compat_area.y + 3,
+                    line.chars().take(max_w).collect::<String>(),
+                    theme::muted(),
+                );
+            }
+        }
+        if let Some((status, style)) = &self.status {
+            let line = format!("  {}", status);
+            buf.set_string(
+                compat_area.x + 2,
+                compat_area.y + 4,
+                line.chars().take(max_w).collect::<String>(),
+                *style,
+            );
+        } else if let Some(first) = self.warnings.first() {
+            let mut line = format!("  ⚠ {}", first);
+            if self.warnings.len() > 1 {
+                line.push_str(&format!(" (+{} more)", self.warnings.len() - 1));
+            }
+            buf.set_string(
+                compat_area.x + 2,
+                compat_area.y + 4,
+                line.chars().take(max_w).collect::<String>(),
+                theme::warning(),
+            );
+        }
+
         // Help text
-        let help = "[SPACE] Toggle  [A] All  [N] None  [ENTER] Apply  [ESC] Back  [Q] Quit";
-        let help_x = area.x + (area.width.saturating_sub(help.len() as u16)) / 2;
+        let help = "[SPACE] Toggle  [→/←] Expand  [A] All  [N] None  [P] Preview  [ENTER] Apply  [ESC] Back  [Q] Quit";
+        let help_w = UnicodeWidthStr::width(help) as u16;
+        let help_x = center_x(area.x, area.width, help_w);
         buf.set_string(help_x, chunks[4].y, help, theme::muted());
     }
 }
+
+/// Full-width dry-run view in place of the patch list and compatibility panel.
+fn render_preview(preview: &PreviewView, area: Rect, chunks: &[Rect], buf: &mut Buffer) {
+    let diff_area = Rect {
+        x: chunks[2].x + 2,
+        y: chunks[2].y,
+        width: chunks[2].width.saturating_sub(4),
+        height: chunks[2].height,
+    };
+    Panel::new()
+        .title(&preview.title)
+        .focused(true)
+        .render(diff_area, buf);
+    let rows = diff_area.height.saturating_sub(2) as usize;
+    let width = diff_area.width.saturating_sub(4) as usize;
+    for (row, (line, style)) in preview
+        .lines
+        .iter()
+        .skip(preview.scroll)
+        .take(rows)
+        .enumerate()
+    {
+        let shown: String = line.chars().take(width).collect();
+        buf.set_string(diff_area.x + 2, diff_area.y + 1 + row as u16, shown, *style);
+    }
+
+    let summary_area = Rect {
+        x: chunks[3].x + 2,
+        y: chunks[3].y,
+        width: chunks[3].width.saturating_sub(4),
+        height: chunks[3].height,
+    };
+    Panel::new().title("DRY RUN").render(summary_area, buf);
+    let (summary, style) = &preview.summary;
+    buf.set_string(summary_area.x + 2, summary_area.y + 1, summary, *style);
+    if !preview.lines.is_empty() {
+        let position = format!(
+            "line {}/{}",
+            (preview.scroll + 1).min(preview.lines.len()),
+            preview.lines.len()
+        );
+        buf.set_string(
+            summary_area.x + 2,
+            summary_area.y + 2,
+            &position,
+            theme::muted(),
+        );
+    }
+
+    let help = "[↑↓/PGUP/PGDN] Scroll  [P/ESC] Close preview";
+    let help_w = UnicodeWidthStr::width(help) as u16;
+    let help_x = center_x(area.x, area.width, help_w);
+    buf.set_string(help_x, chunks[4].y, help, theme::muted());
+}
+
+/// Conflict list in place of the patch list; the first conflict is resolved by dropping a side.
+/// `required` tells which files are deselected as a whole when their side is dropped.
+fn render_conflicts(
+    view: &ConflictView,
+    required: impl Fn(&Path) -> bool,
+    area: Rect,
+    chunks: &[Rect],
+    buf: &mut Buffer,
+) {
+    let list_area = Rect {
+        x: chunks[2].x + 2,
+        y: chunks[2].y,
+        width: chunks[2].width.saturating_sub(4),
+        height: chunks[2].height,
+    };
+    let title = format!("CONFLICTS · {} left", view.remaining.len());
+    Panel::new()
+        .title(&title)
+        .focused(true)
+        .render(list_area, buf);
+    let rows = list_area.height.saturating_sub(2) as usize;
+    let width = list_area.width.saturating_sub(6) as usize;
+    for (row, conflict) in view.remaining.iter().take(rows).enumerate() {
+        let (marker, style) = if row == 0 {
+            ("▸ ", theme::focused())
+        } else {
+            ("  ", theme::secondary())
+        };
+        let line: String = conflict.describe().chars().take(width).collect();
+        let y = list_area.y + 1 + row as u16;
+        buf.set_string(list_area.x + 2, y, marker, theme::cursor());
+        buf.set_string(list_area.x + 4, y, line, style);
+    }
+
+    let choice_area = Rect {
+        x: chunks[3].x + 2,
+        y: chunks[3].y,
+        width: chunks[3].width.saturating_sub(4),
+        height: chunks[3].height,
+    };
+    Panel::new().title("RESOLVE").render(choice_area, buf);
+    let max_w = choice_area.width.saturating_sub(6) as usize;
+    if let Some(conflict) = view.remaining.first() {
+        let side = |key: char, edit: &crate::workflow::PatchEdit, when: &str| {
+            let file = edit
+                .patch_file
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if required(&edit.patch_file) {
+                format!(
+                    "  [{}] Deselect {} (required, drops {}, applied {})",
+                    key, file, edit.patch_id, when
+                )
+            } else {
+                format!(
+                    "  [{}] Drop {} ({}, applied {})",
+                    key, edit.patch_id, file, when
+                )
+            }
+            .chars()
+            .take(max_w)
+            .collect::<String>()
+        };
+        buf.set_string(
+            choice_area.x + 2,
+            choice_area.y + 1,
+            side('1', &conflict.first, "first"),
+            theme::normal(),
+        );
+        buf.set_string(
+            choice_area.x + 2,
+            choice_area.y + 2,
+            side('2', &conflict.second, "second"),
+            theme::normal(),
+        );
+    }
+    let note = if view.strict {
+        "  Strict mode: one file of the pair has to go before the build can start"
+    } else {
+        "  Keeping both: the second may fail or rewrite the first"
+    };
+    buf.set_string(
+        choice_area.x + 2,
+        choice_area.y + 4,
+        note.chars().take(max_w).collect::<String>(),
+        theme::warning(),
+    );
+
+    let help = if view.strict {
+        "[1/2] Drop a side  [ESC] Back to patches"
+    } else {
+        "[1/2] Drop a side  [C] Keep both and continue  [ESC] Back to patches"
+    };
+    let help_w = UnicodeWidthStr::width(help) as u16;
+    let help_x = center_x(area.x, area.width, help_w);
+    buf.set_string(help_x, chunks[4].y, help, theme::muted());
+}
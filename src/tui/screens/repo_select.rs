@@ -43,12 +43,20 @@ impl RepoSelectScreen {
         }
     }
 
+    /// Move the cursor to the repo at `path`, if listed.
+    pub fn select_path(&mut self, path: &std::path::Path) {
+        if let Some(idx) = self.repos.iter().position(|r| r.path == path) {
+            self.cursor = idx;
+        }
+    }
+
     pub fn tick(&mut self) {
         self.frame += 1;
     }
 
     pub fn select_next(&mut self) {
-        let max = self.repos.len() + if self.show_clone_option { 1 } else { 0 };
+        // Repos, "open path", then (optionally) "clone"
+        let max = self.repos.len() + 1 + if self.show_clone_option { 1 } else { 0 };
         if self.cursor < max.saturating_sub(1) {
             self.cursor += 1;
         }
@@ -60,8 +68,12 @@ impl RepoSelectScreen {
         }
     }
 
+    pub fn is_path_selected(&self) -> bool {
+        self.cursor == self.repos.len()
+    }
+
     pub fn is_clone_selected(&self) -> bool {
-        self.show_clone_option && self.cursor == self.repos.len()
+        self.show_clone_option && self.cursor == self.repos.len() + 1
     }
 
     pub fn selected_repo(&self) -> Option<&RepoInfo> {
@@ -127,6 +139,12 @@ impl Widget for &RepoSelectScreen {
             })
             .collect();
 
+        items.push(
+            ListItem::new("+ OPEN PATH")
+                .description("Use a checkout that was not found automatically".to_string())
+                .status(ListStatus::None),
+        );
+
         // Add clone option
         if self.show_clone_option {
             items.push(
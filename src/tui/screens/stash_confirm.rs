@@ -0,0 +1,124 @@
+//! Confirmation screen shown before stashing a dirty clone for an in-place build
+
+use crate::tui::theme::{self, center_x, jp};
+use crate::tui::widgets::Panel;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    widgets::Widget,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Lists uncommitted changes and asks before `git stash`-ing them.
+pub struct StashConfirmScreen {
+    frame: u64,
+    repo: String,
+    /// `git status --porcelain` lines
+    files: Vec<String>,
+    restore_after: bool,
+}
+
+impl StashConfirmScreen {
+    pub fn new(repo: impl Into<String>, files: Vec<String>) -> Self {
+        Self {
+            frame: 0,
+            repo: repo.into(),
+            files,
+            restore_after: true,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn toggle_restore(&mut self) {
+        self.restore_after = !self.restore_after;
+    }
+
+    /// Check the previous branch out and pop the stash once the build finishes.
+    pub fn restore_after(&self) -> bool {
+        self.restore_after
+    }
+}
+
+impl Widget for &StashConfirmScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Clear background
+        for y in area.y..(area.y + area.height) {
+            for x in area.x..(area.x + area.width) {
+                buf.set_string(x, y, " ", Style::default().bg(theme::BG_VOID));
+            }
+        }
+
+        let chunks = Layout::vertical([
+            Constraint::Length(4), // Header
+            Constraint::Length(1), // Spacer
+            Constraint::Min(5),    // File list
+            Constraint::Length(3), // Restore toggle
+            Constraint::Length(2), // Help
+        ])
+        .split(area);
+
+        // Header
+        let header_line = format!("░▒▓█ UNCOMMITTED CHANGES //{} █▓▒░", jp::MODIFIED);
+        let header_w = UnicodeWidthStr::width(header_line.as_str()) as u16;
+        let header_x = center_x(area.x, area.width, header_w);
+        buf.set_string(header_x, chunks[0].y + 1, &header_line, theme::warning());
+
+        // File list
+        let list_area = Rect {
+            x: chunks[2].x + 4,
+            y: chunks[2].y,
+            width: chunks[2].width.saturating_sub(8),
+            height: chunks[2].height,
+        };
+        let title = format!("{} ({} changed)", self.repo, self.files.len());
+        Panel::new()
+            .title(&title)
+            .focused(true)
+            .render(list_area, buf);
+
+        let rows = list_area.height.saturating_sub(2) as usize;
+        let max_w = list_area.width.saturating_sub(4) as usize;
+        let shown = if self.files.len() > rows {
+            rows.saturating_sub(1)
+        } else {
+            self.files.len()
+        };
+        for (i, file) in self.files.iter().take(shown).enumerate() {
+            let line: String = file.chars().take(max_w).collect();
+            buf.set_string(
+                list_area.x + 2,
+                list_area.y + 1 + i as u16,
+                &line,
+                theme::secondary(),
+            );
+        }
+        if shown < self.files.len() {
+            buf.set_string(
+                list_area.x + 2,
+                list_area.y + 1 + shown as u16,
+                format!("… and {} more", self.files.len() - shown),
+                theme::muted(),
+            );
+        }
+
+        // Restore toggle
+        let checkbox = if self.restore_after { "[✓]" } else { "[ ]" };
+        let toggle = format!(
+            "{} Restore the previous branch and pop the stash after the build",
+            checkbox
+        );
+        let toggle_w = UnicodeWidthStr::width(toggle.as_str()) as u16;
+        let toggle_x = center_x(area.x, area.width, toggle_w);
+        buf.set_string(toggle_x, chunks[3].y + 1, &toggle, theme::focused());
+
+        // Help text
+        let help = "[ENTER] Stash & build  [SPACE] Toggle restore  [ESC] Back";
+        let help_w = UnicodeWidthStr::width(help) as u16;
+        let help_x = center_x(area.x, area.width, help_w);
+        buf.set_string(help_x, chunks[4].y, help, theme::muted());
+    }
+}
@@ -1,13 +1,15 @@
 //! Version/tag selection screen
 
+use crate::releases::{self, ReleaseFilter};
 use crate::tui::theme::{self, center_x, jp};
 use crate::tui::widgets::{ListItem, ListStatus, Panel, SelectList};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Layout, Rect},
-    style::Style,
+    style::{Modifier, Style},
     widgets::Widget,
 };
+use std::collections::HashSet;
 use unicode_width::UnicodeWidthStr;
 
 /// Version/release information
@@ -17,22 +19,80 @@ pub struct VersionInfo {
     pub date: String,
     pub is_latest: bool,
     pub is_current: bool,
+    /// `core::format_changelog` lines (headings, then `- entry` lines)
     pub changelog: Vec<String>,
 }
 
+/// State of the background `git fetch --tags` behind the list
+#[derive(Clone, PartialEq)]
+pub enum FetchState {
+    /// Fetching; the list shows local tags meanwhile
+    Fetching,
+    /// Tags are up to date as of the given label (e.g. "fetched 5s ago")
+    Fetched(String),
+    /// The fetch failed or timed out; the list may be stale
+    Failed { error: String, label: String },
+    /// `--offline`: no fetch attempted
+    Offline(String),
+}
+
 /// Version selection screen
 pub struct VersionSelectScreen {
     frame: u64,
+    /// Every release, in the order they were loaded
     versions: Vec<VersionInfo>,
+    /// Indices into `versions` that pass `filter`, newest first
+    visible: Vec<usize>,
+    filter: ReleaseFilter,
+    /// True while `/` search is receiving keystrokes
+    searching: bool,
+    /// Position in `visible`
     cursor: usize,
+    /// Versions whose changelog has been loaded (loading is lazy, per highlighted version)
+    changelog_loaded: HashSet<usize>,
+    changelog_scroll: usize,
+    fetch_state: Option<FetchState>,
 }
 
 impl VersionSelectScreen {
     pub fn new(versions: Vec<VersionInfo>) -> Self {
-        Self {
+        let changelog_loaded = versions
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| !v.changelog.is_empty())
+            .map(|(i, _)| i)
+            .collect();
+        let mut screen = Self {
             frame: 0,
             versions,
+            visible: Vec::new(),
+            filter: ReleaseFilter::default(),
+            searching: false,
             cursor: 0,
+            changelog_loaded,
+            changelog_scroll: 0,
+            fetch_state: None,
+        };
+        screen.apply_filter();
+        // Nothing but pre-releases: show them rather than an empty list.
+        if screen.visible.is_empty() {
+            screen.filter.include_prereleases = true;
+            screen.apply_filter();
+        }
+        screen
+    }
+
+    /// Highlight `tag`, showing pre-releases if that is what it takes.
+    pub fn select_tag(&mut self, tag: &str) {
+        let Some(index) = self.versions.iter().position(|v| v.tag == tag) else {
+            return;
+        };
+        if !self.visible.contains(&index) && !self.filter.include_prereleases {
+            self.toggle_prereleases();
+        }
+        if let Some(pos) = self.visible.iter().position(|&i| i == index) {
+            self.cursor = pos;
+            self.changelog_scroll = 0;
         }
     }
 
@@ -40,25 +100,147 @@ impl VersionSelectScreen {
         self.frame += 1;
     }
 
+    pub fn set_fetch_state(&mut self, state: FetchState) {
+        self.fetch_state = Some(state);
+    }
+
+    /// Swap in a freshly loaded release list, keeping the filter and highlighted tag.
+    pub fn replace_versions(&mut self, versions: Vec<VersionInfo>) {
+        let selected = self.selected_version().map(|v| v.tag.clone());
+        self.changelog_loaded = versions
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| !v.changelog.is_empty())
+            .map(|(i, _)| i)
+            .collect();
+        self.versions = versions;
+        self.visible.clear();
+        self.apply_filter();
+        if let Some(tag) = selected {
+            self.select_tag(&tag);
+        }
+    }
+
+    fn tags(&self) -> Vec<&str> {
+        self.versions.iter().map(|v| v.tag.as_str()).collect()
+    }
+
+    /// Recompute the visible list, keeping the highlighted release if it is still shown.
+    fn apply_filter(&mut self) {
+        let current = self.visible.get(self.cursor).copied();
+        self.visible = releases::select_releases(&self.tags(), &self.filter);
+        self.cursor = current
+            .and_then(|idx| self.visible.iter().position(|&i| i == idx))
+            .unwrap_or(0);
+        self.changelog_scroll = 0;
+    }
+
+    pub fn toggle_prereleases(&mut self) {
+        self.filter.include_prereleases = !self.filter.include_prereleases;
+        self.apply_filter();
+    }
+
+    pub fn toggle_latest_per_minor(&mut self) {
+        self.filter.latest_per_minor = !self.filter.latest_per_minor;
+        self.apply_filter();
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.searching
+    }
+
+    pub fn start_search(&mut self) {
+        self.searching = true;
+    }
+
+    /// Leave the search field; `keep` = false clears the query.
+    pub fn finish_search(&mut self, keep: bool) {
+        self.searching = false;
+        if !keep && !self.filter.query.is_empty() {
+            self.filter.query.clear();
+            self.apply_filter();
+        }
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.filter.query.push(c);
+        self.apply_filter();
+    }
+
+    pub fn delete_char(&mut self) {
+        if self.filter.query.pop().is_some() {
+            self.apply_filter();
+        }
+    }
+
     pub fn select_next(&mut self) {
-        if self.cursor < self.versions.len().saturating_sub(1) {
+        if self.cursor < self.visible.len().saturating_sub(1) {
             self.cursor += 1;
+            self.changelog_scroll = 0;
         }
     }
 
     pub fn select_prev(&mut self) {
         if self.cursor > 0 {
             self.cursor -= 1;
+            self.changelog_scroll = 0;
+        }
+    }
+
+    pub fn scroll_changelog(&mut self, delta: isize) {
+        let len = self.selected_version().map_or(0, |v| v.changelog.len());
+        self.changelog_scroll = self
+            .changelog_scroll
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    /// Release the highlighted version's changelog starts from (see `releases::predecessor`).
+    fn previous_version(&self) -> Option<&VersionInfo> {
+        let index = *self.visible.get(self.cursor)?;
+        releases::predecessor(&self.tags(), index).map(|i| &self.versions[i])
+    }
+
+    /// `(index, tag, previous tag)` of the highlighted version if its changelog still needs
+    /// loading.
+    pub fn changelog_needed(&self) -> Option<(usize, String, String)> {
+        let index = *self.visible.get(self.cursor)?;
+        if self.changelog_loaded.contains(&index) {
+            return None;
+        }
+        let previous = self.previous_version()?;
+        Some((
+            index,
+            self.versions[index].tag.clone(),
+            previous.tag.clone(),
+        ))
+    }
+
+    pub fn set_changelog(&mut self, index: usize, changelog: Vec<String>) {
+        if let Some(version) = self.versions.get_mut(index) {
+            version.changelog = changelog;
+            self.changelog_loaded.insert(index);
         }
     }
 
     pub fn selected_version(&self) -> Option<&VersionInfo> {
-        self.versions.get(self.cursor)
+        self.visible.get(self.cursor).map(|&i| &self.versions[i])
     }
 
     pub fn frame(&self) -> u64 {
         self.frame
     }
+
+    fn list_title(&self) -> String {
+        let mut title = format!("VERSIONS ({}/{})", self.visible.len(), self.versions.len());
+        if !self.filter.include_prereleases {
+            title.push_str(" · stable");
+        }
+        if self.filter.latest_per_minor {
+            title.push_str(" · latest per minor");
+        }
+        title
+    }
 }
 
 impl Widget for &VersionSelectScreen {
@@ -71,11 +253,12 @@ impl Widget for &VersionSelectScreen {
         }
 
         let chunks = Layout::vertical([
-            Constraint::Length(4), // Header
-            Constraint::Length(1), // Spacer
-            Constraint::Min(8),    // Version list
-            Constraint::Length(6), // Changelog panel
-            Constraint::Length(2), // Help
+            Constraint::Length(4),  // Header
+            Constraint::Length(1),  // Spacer
+            Constraint::Min(8),     // Version list
+            Constraint::Length(1),  // Search
+            Constraint::Length(12), // Changelog panel
+            Constraint::Length(2),  // Help
         ])
         .split(area);
 
@@ -85,12 +268,67 @@ impl Widget for &VersionSelectScreen {
         let header_x = center_x(area.x, area.width, header_w);
         buf.set_string(header_x, chunks[0].y + 1, &header_line, theme::title());
 
+        // Where the tags came from
+        if let Some(state) = &self.fetch_state {
+            let (text, style) = match state {
+                FetchState::Fetching => {
+                    let spinner_chars = theme::spinners::BRAILLE;
+                    let spinner = spinner_chars[(self.frame / 4) as usize % spinner_chars.len()];
+                    (
+                        format!("{} Fetching tags… (showing local tags)", spinner),
+                        theme::active(),
+                    )
+                }
+                FetchState::Fetched(label) => (format!("Tags {}", label), theme::muted()),
+                FetchState::Failed { error, label } => (
+                    format!("⚠ Fetch failed: {} · local tags {}", error, label),
+                    theme::error(),
+                ),
+                FetchState::Offline(label) => (
+                    format!("OFFLINE · local tags {}", label),
+                    theme::secondary(),
+                ),
+            };
+            let text: String = text.chars().take(area.width as usize).collect();
+            let text_w = UnicodeWidthStr::width(text.as_str()) as u16;
+            let text_x = center_x(area.x, area.width, text_w);
+            buf.set_string(text_x, chunks[0].y + 2, &text, style);
+        }
+
+        // Release counts per minor line, shown when the list is collapsed to one per line
+        let line_counts: Vec<(String, usize)> = if self.filter.latest_per_minor {
+            let tags = self.tags();
+            let all = releases::select_releases(
+                &tags,
+                &ReleaseFilter {
+                    latest_per_minor: false,
+                    ..self.filter.clone()
+                },
+            );
+            releases::group_by_minor(&tags, &all)
+                .into_iter()
+                .map(|(line, members)| (line, members.len()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
         // Build list items
         let items: Vec<ListItem> = self
-            .versions
+            .visible
             .iter()
-            .map(|ver| {
-                let mut item = ListItem::new(&ver.tag).secondary(ver.date.clone());
+            .map(|&i| {
+                let ver = &self.versions[i];
+                let line = releases::minor_line(&ver.tag);
+                let count = line_counts
+                    .iter()
+                    .find(|(l, _)| Some(l) == line.as_ref())
+                    .map(|(_, n)| *n);
+                let secondary = match (line, count) {
+                    (Some(line), Some(n)) => format!("{} · {} in {}.x", ver.date, n, line),
+                    _ => ver.date.clone(),
+                };
+                let mut item = ListItem::new(&ver.tag).secondary(secondary);
 
                 if ver.is_latest {
                     item = item.status(ListStatus::Latest);
@@ -110,7 +348,8 @@ impl Widget for &VersionSelectScreen {
             height: chunks[2].height,
         };
 
-        let panel = Panel::new().title("VERSIONS").focused(true);
+        let title = self.list_title();
+        let panel = Panel::new().title(&title).focused(!self.searching);
         panel.render(list_area, buf);
 
         let inner_area = Rect {
@@ -125,29 +364,115 @@ impl Widget for &VersionSelectScreen {
             .frame(self.frame);
         list.render(inner_area, buf);
 
+        if self.visible.is_empty() {
+            buf.set_string(
+                inner_area.x,
+                inner_area.y,
+                "No releases match — [P] pre-releases, [/] search",
+                theme::muted(),
+            );
+        }
+
+        // Search
+        let search_x = chunks[3].x + 4;
+        buf.set_string(search_x, chunks[3].y, "Search: ", theme::secondary());
+        let (query, style) = if self.filter.query.is_empty() && !self.searching {
+            ("(none)", theme::muted())
+        } else {
+            (self.filter.query.as_str(), theme::normal())
+        };
+        buf.set_string(search_x + 8, chunks[3].y, query, style);
+        if self.searching && (self.frame / 30).is_multiple_of(2) {
+            let cursor_x = search_x + 8 + UnicodeWidthStr::width(query) as u16;
+            buf.set_string(
+                cursor_x,
+                chunks[3].y,
+                "▎",
+                Style::default()
+                    .fg(theme::CYAN)
+                    .add_modifier(Modifier::BOLD),
+            );
+        }
+
         // Changelog panel
         let changelog_area = Rect {
-            x: chunks[3].x + 2,
-            y: chunks[3].y,
-            width: chunks[3].width.saturating_sub(4),
-            height: chunks[3].height,
+            x: chunks[4].x + 2,
+            y: chunks[4].y,
+            width: chunks[4].width.saturating_sub(4),
+            height: chunks[4].height,
         };
 
-        let changelog_panel = Panel::new().title("CHANGELOG").title_jp(jp::CHANGELOG);
+        let title = match self.selected_version() {
+            Some(version) => match self.previous_version() {
+                Some(previous) => format!("CHANGELOG {}..{}", previous.tag, version.tag),
+                None => "CHANGELOG".to_string(),
+            },
+            None => "CHANGELOG".to_string(),
+        };
+        let changelog_panel = Panel::new().title(&title).title_jp(jp::CHANGELOG);
         changelog_panel.render(changelog_area, buf);
 
         // Changelog content
         if let Some(version) = self.selected_version() {
-            for (i, line) in version.changelog.iter().take(4).enumerate() {
+            let rows = changelog_area.height.saturating_sub(2) as usize;
+            let width = changelog_area.width.saturating_sub(6) as usize;
+            let x = changelog_area.x + 2;
+            if version.changelog.is_empty() {
+                let loaded = self
+                    .visible
+                    .get(self.cursor)
+                    .is_some_and(|i| self.changelog_loaded.contains(i));
+                let note = if self.previous_version().is_none() {
+                    "  No earlier release to compare against"
+                } else if loaded {
+                    "  No changes"
+                } else {
+                    "  Loading..."
+                };
+                buf.set_string(x, changelog_area.y + 1, note, theme::muted());
+            }
+            let remaining = version
+                .changelog
+                .len()
+                .saturating_sub(self.changelog_scroll);
+            let shown = if remaining > rows {
+                rows.saturating_sub(1)
+            } else {
+                remaining
+            };
+            for (i, line) in version
+                .changelog
+                .iter()
+                .skip(self.changelog_scroll)
+                .take(shown)
+                .enumerate()
+            {
                 let y = changelog_area.y + 1 + i as u16;
-                let text = format!("  • {}", line);
-                buf.set_string(changelog_area.x + 2, y, &text, theme::secondary());
+                let (text, style) = match line.strip_prefix("- ") {
+                    Some(entry) => (format!("  • {}", entry), theme::secondary()),
+                    None => (line.clone(), theme::active()),
+                };
+                let text: String = text.chars().take(width).collect();
+                buf.set_string(x, y, &text, style);
+            }
+            if shown < remaining {
+                buf.set_string(
+                    x,
+                    changelog_area.y + 1 + shown as u16,
+                    format!("  … {} more [PGDN]", remaining - shown),
+                    theme::muted(),
+                );
             }
         }
 
         // Help text
-        let help = "[↑↓] Navigate  [ENTER] Select  [ESC] Back  [Q] Quit";
-        let help_x = area.x + (area.width.saturating_sub(help.len() as u16)) / 2;
-        buf.set_string(help_x, chunks[4].y, help, theme::muted());
+        let help = if self.searching {
+            "[ENTER] Keep search  [ESC] Clear search"
+        } else {
+            "[↑↓] Navigate  [/] Search  [P] Pre-releases  [L] Per minor  [PGUP/PGDN] Changelog  [ENTER] Select"
+        };
+        let help_w = UnicodeWidthStr::width(help) as u16;
+        let help_x = center_x(area.x, area.width, help_w);
+        buf.set_string(help_x, chunks[5].y, help, theme::muted());
     }
 }
@@ -2,6 +2,10 @@
 //!
 //! Goal: keep behavior identical across frontends; only presentation differs.
 
+use crate::cache::{BuildCache, CachedBuild};
+use crate::cleanup::BuildJournal;
+use crate::install::{InstallStore, InstalledBuild};
+use crate::manifest::{BuildManifest, PatchFileRecord, PatchRecord, PatchStatus};
 use anyhow::{bail, Context, Result};
 use cargo_metadata::Message;
 use codex_patcher::{
@@ -9,6 +13,8 @@ use codex_patcher::{
     compiler::{try_autofix_all, CompileDiagnostic},
     load_from_path, Edit, PatchConfig, PatchResult,
 };
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::io::BufRead;
 use std::path::{Path, PathBuf};
@@ -30,8 +36,22 @@ pub enum Phase {
     Installing,
 }
 
+impl Phase {
+    /// Stable lowercase name (used in machine-readable output).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Patching => "patching",
+            Phase::Compiling => "compiling",
+            Phase::Optimizing => "optimizing",
+            Phase::Testing => "testing",
+            Phase::Installing => "installing",
+        }
+    }
+}
+
 /// Optimization intent: a single selector that maps to concrete knobs.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum OptimizationMode {
     /// Prefer faster *builds* (link with mold). No runtime BOLT pass.
     BuildFast,
@@ -82,6 +102,9 @@ pub struct BuildOptions {
     /// Optional throttle for cargo parallelism (`cargo --jobs N`).
     /// This limits rustc processes spawned concurrently, which reduces peak CPU usage.
     pub cargo_jobs: Option<usize>,
+    /// Offline mode (`--offline`): cargo runs with `--offline` (dependencies must already be
+    /// in the cargo cache) and shallow clones are not deepened.
+    pub offline: bool,
 }
 
 /// Emitted events allow the frontend to keep the user informed without
@@ -93,7 +116,19 @@ pub enum Event {
     CurrentItem(String),
     Log(String),
     PatchFileApplied(String),
-    PatchFileSkipped { name: String, reason: String },
+    PatchFileSkipped {
+        name: String,
+        reason: String,
+    },
+    /// Some patches in the file applied, others did not (`reason`: why the first one failed).
+    PatchFilePartial {
+        name: String,
+        applied: usize,
+        total: usize,
+        reason: String,
+    },
+    /// A patch in a required file did not apply; the build stops before compiling.
+    RequiredPatchFailed(RequiredPatchFailure),
 }
 
 fn resolve_command_path(name: &str) -> Result<PathBuf> {
@@ -162,21 +197,95 @@ pub fn get_available_patches(patches_dir: &Path) -> Result<Vec<(PathBuf, PatchCo
     Ok(patches)
 }
 
+/// Patch ids left out of selected patch files, keyed by patch file path.
+pub type DisabledPatches = BTreeMap<PathBuf, Vec<String>>;
+
+/// `config` without the patches whose ids are in `disabled`, plus the ids actually left out.
+pub fn without_patches(config: &PatchConfig, disabled: &[String]) -> (PatchConfig, Vec<String>) {
+    let mut filtered = config.clone();
+    let mut left_out = Vec::new();
+    filtered.patches.retain(|patch| {
+        let keep = !disabled.contains(&patch.id);
+        if !keep {
+            left_out.push(patch.id.clone());
+        }
+        keep
+    });
+    (filtered, left_out)
+}
+
+/// Display name of a patch file (its stem).
+fn patch_file_name(patch_file: &Path) -> String {
+    patch_file
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| patch_file.display().to_string())
+}
+
+/// Run codex-patcher on one config, turning panics into errors.
+fn apply_config(
+    config: &PatchConfig,
+    workspace: &Path,
+    workspace_version: &str,
+) -> Result<Vec<(String, Result<PatchResult, impl std::fmt::Display>)>> {
+    // Defensive: patch application is user-extensible and has historically had panics.
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        patcher_apply(config, workspace, workspace_version)
+    }))
+    .map_err(|panic_info| {
+        let msg = if let Some(s) = panic_info.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = panic_info.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "Unknown panic".to_string()
+        };
+        anyhow::anyhow!("Patch application panicked: {}", msg)
+    })
+}
+
+/// Manifest record for one patch result.
+fn patch_record(id: &str, result: &Result<PatchResult, impl std::fmt::Display>) -> PatchRecord {
+    let (status, file, reason) = match result {
+        Ok(PatchResult::Applied { file }) => (PatchStatus::Applied, Some(file.clone()), None),
+        Ok(PatchResult::AlreadyApplied { file }) => {
+            (PatchStatus::AlreadyApplied, Some(file.clone()), None)
+        }
+        Ok(PatchResult::SkippedVersion { reason }) => {
+            (PatchStatus::SkippedVersion, None, Some(reason.clone()))
+        }
+        Ok(PatchResult::Failed { file, reason }) => (
+            PatchStatus::Failed,
+            Some(file.clone()),
+            Some(reason.clone()),
+        ),
+        Err(e) => (PatchStatus::Error, None, Some(e.to_string())),
+    };
+    PatchRecord {
+        id: id.to_string(),
+        status,
+        file,
+        reason,
+    }
+}
+
 /// Apply selected patches using codex-patcher library.
+///
+/// Patch ids listed in `disabled` for a file are left out of its config before it is applied.
+/// Returns one record per patch file (content hash + per-patch results) for the build manifest.
 pub fn apply_patches(
     workspace: &Path,
     selected_files: &[PathBuf],
+    disabled: &DisabledPatches,
     mut emit: impl FnMut(Event),
-) -> Result<()> {
+) -> Result<Vec<PatchFileRecord>> {
     emit(Event::Phase(Phase::Patching));
     emit(Event::Progress(0.0));
     let workspace_version = read_workspace_version(workspace)?;
+    let mut records = Vec::with_capacity(selected_files.len());
 
     for (idx, patch_file) in selected_files.iter().enumerate() {
-        let patch_file_name = patch_file
-            .file_stem()
-            .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_else(|| patch_file.display().to_string());
+        let patch_file_name = patch_file_name(patch_file);
         emit(Event::CurrentItem(format!(
             "Applying patch file {}/{}: {}",
             idx + 1,
@@ -186,26 +295,31 @@ pub fn apply_patches(
 
         let config = load_from_path(patch_file)
             .with_context(|| format!("Failed to load patch: {}", patch_file.display()))?;
-        // Defensive: patch application is user-extensible and has historically had panics.
-        let results = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            patcher_apply(&config, workspace, &workspace_version)
-        }))
-        .map_err(|panic_info| {
-            let msg = if let Some(s) = panic_info.downcast_ref::<&str>() {
-                s.to_string()
-            } else if let Some(s) = panic_info.downcast_ref::<String>() {
-                s.clone()
-            } else {
-                "Unknown panic".to_string()
-            };
-            anyhow::anyhow!("Patch application panicked: {}", msg)
-        })?;
+        let (config, left_out) =
+            without_patches(&config, disabled.get(patch_file).map_or(&[], Vec::as_slice));
+        if !left_out.is_empty() {
+            emit(Event::Log(format!(
+                "  ○ Left out {} of {}: {}",
+                left_out.len(),
+                patch_file_name,
+                left_out.join(", ")
+            )));
+        }
+        let mut record = PatchFileRecord {
+            name: patch_file_name.clone(),
+            path: patch_file.clone(),
+            sha256: crate::manifest::hash_file(patch_file)?,
+            disabled: left_out,
+            results: Vec::new(),
+        };
+        let results = apply_config(&config, workspace, &workspace_version)?;
 
         let mut applied_count = 0usize;
         let mut skipped_count = 0usize;
         let mut first_skip_reason: Option<String> = None;
 
         for (patch_id, result) in results {
+            let patch_record = patch_record(&patch_id, &result);
             match result {
                 Ok(PatchResult::Applied { file }) => {
                     emit(Event::Log(format!(
@@ -247,15 +361,24 @@ pub fn apply_patches(
                     first_skip_reason.get_or_insert(e.to_string());
                 }
             }
+            record.results.push(patch_record);
         }
+        records.push(record);
 
-        if applied_count > 0 {
-            emit(Event::PatchFileApplied(patch_file_name.clone()));
-        } else if skipped_count > 0 {
-            emit(Event::PatchFileSkipped {
+        let reason = first_skip_reason.unwrap_or_else(|| "skipped".to_string());
+        match (applied_count, skipped_count) {
+            (0, 0) => {}
+            (_, 0) => emit(Event::PatchFileApplied(patch_file_name.clone())),
+            (0, _) => emit(Event::PatchFileSkipped {
                 name: patch_file_name.clone(),
-                reason: first_skip_reason.unwrap_or_else(|| "skipped".to_string()),
-            });
+                reason,
+            }),
+            (applied, skipped) => emit(Event::PatchFilePartial {
+                name: patch_file_name.clone(),
+                applied,
+                total: applied + skipped,
+                reason,
+            }),
         }
 
         emit(Event::Progress(
@@ -263,9 +386,539 @@ pub fn apply_patches(
         ));
     }
 
-    Ok(())
+    Ok(records)
+}
+
+/// A patch in a required file that did not end up in the workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequiredPatchFailure {
+    /// Name of the patch file, e.g. `privacy`.
+    pub file: String,
+    pub id: String,
+    pub status: PatchStatus,
+    /// File the patch edits, when the patcher reported one.
+    pub target: Option<PathBuf>,
+    pub reason: Option<String>,
+}
+
+impl std::fmt::Display for RequiredPatchFailure {
+    /// e.g. `privacy: statsig-endpoint failed (core/src/statsig.rs): no match`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let outcome = match self.status {
+            PatchStatus::Applied => "applied",
+            PatchStatus::AlreadyApplied => "already applied",
+            PatchStatus::SkippedVersion => "skipped for version",
+            PatchStatus::Failed => "failed",
+            PatchStatus::Error => "errored",
+            PatchStatus::Disabled => "switched off",
+        };
+        write!(f, "{}: {} {}", self.file, self.id, outcome)?;
+        if let Some(target) = &self.target {
+            write!(f, " ({})", target.display())?;
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, ": {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Patches in required files that did not end up in the workspace.
+///
+/// Applied and already-applied patches pass; failed, errored and version-skipped ones do not,
+/// and neither do ids switched off for the build: a required file has to apply in full.
+pub fn required_patch_failures(
+    records: &[PatchFileRecord],
+    is_required: impl Fn(&Path) -> bool,
+) -> Vec<RequiredPatchFailure> {
+    records
+        .iter()
+        .filter(|record| is_required(&record.path))
+        .flat_map(|record| {
+            record
+                .results
+                .iter()
+                .filter(|result| !result.status.is_in_place())
+                .map(|result| RequiredPatchFailure {
+                    file: record.name.clone(),
+                    id: result.id.clone(),
+                    status: result.status,
+                    target: result.file.clone(),
+                    reason: result.reason.clone(),
+                })
+                .chain(record.disabled.iter().map(|id| RequiredPatchFailure {
+                    file: record.name.clone(),
+                    id: id.clone(),
+                    status: PatchStatus::Disabled,
+                    target: None,
+                    reason: None,
+                }))
+        })
+        .collect()
+}
+
+/// What one patch would do in a dry run.
+#[derive(Debug, Clone)]
+pub struct PatchPreview {
+    pub record: PatchRecord,
+    /// Unified diff of the change (paths relative to the workspace); empty when nothing changes.
+    pub diff: String,
+}
+
+/// Dry-run results for one selected patch file.
+#[derive(Debug, Clone)]
+pub struct PatchFilePreview {
+    pub name: String,
+    pub path: PathBuf,
+    pub patches: Vec<PatchPreview>,
+}
+
+impl PatchPreview {
+    /// One-line outcome, e.g. `✓ would apply telemetry-off (core/src/config.rs)`.
+    pub fn summary(&self) -> String {
+        let record = &self.record;
+        let outcome = match record.status {
+            PatchStatus::Applied => "✓ would apply",
+            PatchStatus::AlreadyApplied => "○ already applied",
+            PatchStatus::SkippedVersion => "⊘ skipped for version",
+            PatchStatus::Failed => "✗ would fail",
+            PatchStatus::Error => "✗ error",
+            PatchStatus::Disabled => "– switched off",
+        };
+        let mut line = format!("{} {}", outcome, record.id);
+        if let Some(file) = &record.file {
+            line.push_str(&format!(" ({})", file.display()));
+        }
+        if let Some(reason) = &record.reason {
+            line.push_str(&format!(": {}", reason));
+        }
+        line
+    }
+
+    /// True when the build would not get this patch.
+    pub fn is_problem(&self) -> bool {
+        matches!(self.record.status, PatchStatus::Failed | PatchStatus::Error)
+    }
+}
+
+/// Totals across a preview, e.g. `3 would apply, 1 already applied, 1 would fail`.
+pub fn preview_summary(previews: &[PatchFilePreview]) -> String {
+    let mut counts = [0usize; 5];
+    for patch in previews.iter().flat_map(|p| &p.patches) {
+        let slot = match patch.record.status {
+            PatchStatus::Applied => 0,
+            PatchStatus::AlreadyApplied => 1,
+            PatchStatus::SkippedVersion => 2,
+            PatchStatus::Disabled => 3,
+            PatchStatus::Failed | PatchStatus::Error => 4,
+        };
+        counts[slot] += 1;
+    }
+    let labels = [
+        "would apply",
+        "already applied",
+        "skipped for version",
+        "switched off",
+        "would fail",
+    ];
+    let parts: Vec<String> = counts
+        .iter()
+        .zip(labels)
+        .filter(|(count, _)| **count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect();
+    if parts.is_empty() {
+        "no patches".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Dry-run the selected patch files without touching `workspace`.
+///
+/// The files the patches target (and `Cargo.toml`, for the version) are copied into a scratch
+/// git repo, read from `revision` when given (e.g. the tag about to be checked out) or else from
+/// the working tree. Patches are applied there one at a time, in build order, and each one's
+/// diff is taken before the next runs. Targets outside the workspace are reported, not applied.
+/// Patch ids in `disabled` are left out, as they would be in the build, and reported as
+/// switched off after the file's other patches.
+pub fn preview_patches(
+    workspace: &Path,
+    revision: Option<&str>,
+    selected_files: &[PathBuf],
+    disabled: &DisabledPatches,
+) -> Result<Vec<PatchFilePreview>> {
+    let (configs, left_out): (Vec<_>, Vec<_>) =
+        load_selected(selected_files, disabled)?.into_iter().unzip();
+    let scratch = scratch_dir("preview")?;
+    let result = preview_in(
+        &scratch,
+        workspace,
+        revision,
+        selected_files,
+        &configs,
+        &left_out,
+    );
+    let _ = std::fs::remove_dir_all(&scratch);
+    result
+}
+
+/// Load the selected patch files with their disabled ids left out, along with those ids.
+fn load_selected(
+    selected_files: &[PathBuf],
+    disabled: &DisabledPatches,
+) -> Result<Vec<(PatchConfig, Vec<String>)>> {
+    selected_files
+        .iter()
+        .map(|path| {
+            let config = load_from_path(path)
+                .with_context(|| format!("Failed to load patch: {}", path.display()))?;
+            Ok(without_patches(
+                &config,
+                disabled.get(path).map_or(&[], Vec::as_slice),
+            ))
+        })
+        .collect()
+}
+
+/// True for workspace-relative paths that stay inside the workspace.
+fn is_inside_workspace(file: &Path) -> bool {
+    file.components().all(|c| {
+        matches!(
+            c,
+            std::path::Component::Normal(_) | std::path::Component::CurDir
+        )
+    })
+}
+
+/// A fresh, empty scratch directory such as `codex-xtreme-preview-<pid>-<n>`.
+///
+/// The counter keeps concurrent dry runs in one process (e.g. a TUI preview while a build
+/// runs) from sharing, and deleting, each other's directory.
+fn scratch_dir(purpose: &str) -> Result<PathBuf> {
+    static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let scratch = std::env::temp_dir().join(format!(
+        "codex-xtreme-{}-{}-{}",
+        purpose,
+        std::process::id(),
+        n
+    ));
+    let _ = std::fs::remove_dir_all(&scratch);
+    std::fs::create_dir_all(&scratch)
+        .with_context(|| format!("Failed to create {}", scratch.display()))?;
+    Ok(scratch)
+}
+
+/// Run git in a scratch repo and return its stdout.
+fn scratch_git(scratch: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new(resolve_command_path("git")?)
+        .args(args)
+        .current_dir(scratch)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Copy every file a patch targets (and `Cargo.toml`) into a fresh git repo at `scratch`.
+///
+/// Returns the workspace version read from the copied `Cargo.toml`.
+fn seed_scratch(
+    scratch: &Path,
+    workspace: &Path,
+    revision: Option<&str>,
+    configs: &[PatchConfig],
+) -> Result<String> {
+    let mut targets = vec![PathBuf::from("Cargo.toml")];
+    for config in configs {
+        for patch in &config.patches {
+            let file = PathBuf::from(&patch.file);
+            if is_inside_workspace(&file) && !targets.contains(&file) {
+                targets.push(file);
+            }
+        }
+    }
+    let git = resolve_command_path("git")?;
+    for file in &targets {
+        let contents = match revision {
+            Some(rev) => Command::new(&git)
+                .arg("show")
+                .arg(format!("{}:./{}", rev, file.display()))
+                .current_dir(workspace)
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| o.stdout),
+            None => std::fs::read(workspace.join(file)).ok(),
+        };
+        // Missing targets stay missing; the patcher reports them as failures.
+        if let Some(contents) = contents {
+            let dest = scratch.join(file);
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&dest, contents)?;
+        }
+    }
+    scratch_git(scratch, &["init", "-q"])?;
+    scratch_git(scratch, &["add", "-A"])?;
+    read_workspace_version(scratch)
 }
 
+fn preview_in(
+    scratch: &Path,
+    workspace: &Path,
+    revision: Option<&str>,
+    selected_files: &[PathBuf],
+    configs: &[PatchConfig],
+    left_out: &[Vec<String>],
+) -> Result<Vec<PatchFilePreview>> {
+    let git = |args: &[&str]| scratch_git(scratch, args);
+    let workspace_version = seed_scratch(scratch, workspace, revision, configs)?;
+
+    let mut previews = Vec::with_capacity(configs.len());
+    for ((path, config), left_out) in selected_files.iter().zip(configs).zip(left_out) {
+        let mut preview = PatchFilePreview {
+            name: patch_file_name(path),
+            path: path.clone(),
+            patches: Vec::with_capacity(config.patches.len() + left_out.len()),
+        };
+        for patch in &config.patches {
+            if !is_inside_workspace(Path::new(&patch.file)) {
+                preview.patches.push(PatchPreview {
+                    record: PatchRecord {
+                        id: patch.id.clone(),
+                        status: PatchStatus::Error,
+                        file: Some(PathBuf::from(&patch.file)),
+                        reason: Some("target is outside the workspace; not previewed".into()),
+                    },
+                    diff: String::new(),
+                });
+                continue;
+            }
+            let mut single = config.clone();
+            single.patches = vec![patch.clone()];
+            for (patch_id, result) in apply_config(&single, scratch, &workspace_version)? {
+                let mut record = patch_record(&patch_id, &result);
+                // Report targets relative to the workspace, not the scratch copy.
+                record.file = record
+                    .file
+                    .map(|f| f.strip_prefix(scratch).map(Path::to_path_buf).unwrap_or(f));
+                let diff = git(&["diff", "--no-color", "--no-ext-diff"])?;
+                git(&["add", "-A"])?;
+                preview.patches.push(PatchPreview { record, diff });
+            }
+        }
+        for id in left_out {
+            preview.patches.push(PatchPreview {
+                record: PatchRecord {
+                    id: id.clone(),
+                    status: PatchStatus::Disabled,
+                    file: None,
+                    reason: None,
+                },
+                diff: String::new(),
+            });
+        }
+        previews.push(preview);
+    }
+    Ok(previews)
+}
+
+/// Lines one patch changes in one target, as found by applying it on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchEdit {
+    /// Patch file the patch comes from.
+    pub patch_file: PathBuf,
+    pub patch_id: String,
+    /// Target file, relative to the workspace.
+    pub target: PathBuf,
+    /// First and last line of the original file that the patch rewrites (1-based, inclusive).
+    /// Pure insertions cover the line they follow; new files are `(0, 0)`.
+    pub lines: (usize, usize),
+}
+
+/// Two patches from different selected files that rewrite the same lines of a target.
+///
+/// Applied in build order, the later one either silently edits the earlier one's output or
+/// fails because its anchor is gone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchConflict {
+    /// The side that is applied first.
+    pub first: PatchEdit,
+    pub second: PatchEdit,
+}
+
+impl PatchConflict {
+    /// One line, e.g. `otel-off (privacy) and otel-stub (offline) both edit core/src/otel.rs (line 14)`.
+    pub fn describe(&self) -> String {
+        let (start, end) = (
+            self.first.lines.0.max(self.second.lines.0),
+            self.first.lines.1.min(self.second.lines.1),
+        );
+        let lines = if start == end {
+            format!("line {}", start)
+        } else {
+            format!("lines {}-{}", start, end)
+        };
+        format!(
+            "{} ({}) and {} ({}) both edit {} ({})",
+            self.first.patch_id,
+            patch_file_name(&self.first.patch_file),
+            self.second.patch_id,
+            patch_file_name(&self.second.patch_file),
+            self.first.target.display(),
+            lines
+        )
+    }
+}
+
+/// Find patches in different selected files that rewrite overlapping lines of the same target.
+///
+/// Each patch is applied on its own to a pristine scratch copy (as in [`preview_patches`]) and
+/// the changed line ranges are read back from `git diff`, so the analysis does not depend on how
+/// a patch locates its edit. Patches that fail on their own are not conflicts and are ignored.
+/// Conflicts come back in build order.
+pub fn find_patch_conflicts(
+    workspace: &Path,
+    revision: Option<&str>,
+    selected_files: &[PathBuf],
+    disabled: &DisabledPatches,
+) -> Result<Vec<PatchConflict>> {
+    // Two files can only conflict if they share a target; skip the scratch repo otherwise.
+    let configs: Vec<PatchConfig> = load_selected(selected_files, disabled)?
+        .into_iter()
+        .map(|(config, _)| config)
+        .collect();
+    let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+    let shared = configs.iter().enumerate().any(|(idx, config)| {
+        config
+            .patches
+            .iter()
+            .any(|patch| *seen.entry(patch.file.as_str()).or_insert(idx) != idx)
+    });
+    if !shared {
+        return Ok(Vec::new());
+    }
+
+    let scratch = scratch_dir("conflicts")?;
+    let result = patch_edits_in(&scratch, workspace, revision, selected_files, &configs);
+    let _ = std::fs::remove_dir_all(&scratch);
+    Ok(overlapping_edits(&result?))
+}
+
+fn patch_edits_in(
+    scratch: &Path,
+    workspace: &Path,
+    revision: Option<&str>,
+    selected_files: &[PathBuf],
+    configs: &[PatchConfig],
+) -> Result<Vec<PatchEdit>> {
+    let workspace_version = seed_scratch(scratch, workspace, revision, configs)?;
+    let mut edits = Vec::new();
+    for (path, config) in selected_files.iter().zip(configs) {
+        for patch in &config.patches {
+            if !is_inside_workspace(Path::new(&patch.file)) {
+                continue;
+            }
+            let mut single = config.clone();
+            single.patches = vec![patch.clone()];
+            apply_config(&single, scratch, &workspace_version)?;
+            // `diff_hunks` expects `a/`/`b/` prefixes whatever the user's git config says.
+            let diff = scratch_git(
+                scratch,
+                &[
+                    "-c",
+                    "diff.noprefix=false",
+                    "-c",
+                    "diff.mnemonicPrefix=false",
+                    "diff",
+                    "--no-color",
+                    "--no-ext-diff",
+                    "-U0",
+                ],
+            )?;
+            let created = scratch_git(scratch, &["ls-files", "--others"])?;
+            let hunks = diff_hunks(&diff)
+                .into_iter()
+                .chain(created.lines().map(|file| (PathBuf::from(file), (0, 0))));
+            for (target, lines) in hunks {
+                edits.push(PatchEdit {
+                    patch_file: path.clone(),
+                    patch_id: patch.id.clone(),
+                    target,
+                    lines,
+                });
+            }
+            // Back to the seeded files (the index) for the next patch.
+            scratch_git(scratch, &["checkout", "-q", "--", "."])?;
+            scratch_git(scratch, &["clean", "-fdq"])?;
+        }
+    }
+    Ok(edits)
+}
+
+/// Changed line ranges of the original files in a `-U0` unified diff.
+fn diff_hunks(diff: &str) -> Vec<(PathBuf, (usize, usize))> {
+    let mut hunks = Vec::new();
+    let mut target: Option<PathBuf> = None;
+    for line in diff.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git a/") {
+            target = rest.split_once(" b/").map(|(a, _)| PathBuf::from(a));
+        } else if let Some(rest) = line.strip_prefix("@@ -") {
+            let Some(target) = &target else {
+                continue;
+            };
+            let old = rest.split_whitespace().next().unwrap_or_default();
+            let (start, count) = match old.split_once(',') {
+                Some((start, count)) => (start.parse(), count.parse()),
+                None => (old.parse(), Ok(1)),
+            };
+            if let (Ok(start), Ok(count)) = (start, count) {
+                let end: usize = if count == 0 { start } else { start + count - 1 };
+                hunks.push((target.clone(), (start, end)));
+            }
+        }
+    }
+    hunks
+}
+
+/// Pairs of edits from different patch files whose line ranges overlap in the same target.
+fn overlapping_edits(edits: &[PatchEdit]) -> Vec<PatchConflict> {
+    let mut conflicts: Vec<PatchConflict> = Vec::new();
+    for (i, first) in edits.iter().enumerate() {
+        for second in &edits[i + 1..] {
+            let overlaps = first.patch_file != second.patch_file
+                && first.target == second.target
+                && first.lines.0 <= second.lines.1
+                && second.lines.0 <= first.lines.1;
+            // One conflict per pair of patches, even if several of their hunks collide.
+            let known = conflicts.iter().any(|c| {
+                (c.first.patch_file == first.patch_file && c.first.patch_id == first.patch_id)
+                    && (c.second.patch_file == second.patch_file
+                        && c.second.patch_id == second.patch_id)
+            });
+            if overlaps && !known {
+                conflicts.push(PatchConflict {
+                    first: first.clone(),
+                    second: second.clone(),
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+/// Comment heading the `[profile.xtreme]` block appended by [`inject_xtreme_profile`].
+pub const XTREME_PROFILE_MARKER: &str = "# Injected by codex-xtreme";
+
 pub fn inject_xtreme_profile(workspace: &Path) -> Result<()> {
     let cargo_toml = workspace.join("Cargo.toml");
     let contents = std::fs::read_to_string(&cargo_toml)?;
@@ -298,6 +951,20 @@ opt-level = 3
     Ok(())
 }
 
+/// Strip the block [`inject_xtreme_profile`] appended. Returns `false` if there was none.
+pub fn remove_xtreme_profile(workspace: &Path) -> Result<bool> {
+    let cargo_toml = workspace.join("Cargo.toml");
+    let Ok(contents) = std::fs::read_to_string(&cargo_toml) else {
+        return Ok(false);
+    };
+    let marker = format!("\n\n{}\n[profile.xtreme]", XTREME_PROFILE_MARKER);
+    let Some(start) = contents.find(&marker) else {
+        return Ok(false);
+    };
+    std::fs::write(&cargo_toml, &contents[..start])?;
+    Ok(true)
+}
+
 /// Build error with captured diagnostics for auto-fix.
 #[derive(Debug)]
 pub enum BuildError {
@@ -312,6 +979,7 @@ pub fn build_with_autofix(
     cpu_target: Option<&str>,
     optimization: &OptimizationFlags,
     cargo_jobs: Option<usize>,
+    offline: bool,
     mut emit: impl FnMut(Event),
 ) -> Result<PathBuf> {
     const MAX_FIX_ATTEMPTS: usize = 5;
@@ -323,6 +991,7 @@ pub fn build_with_autofix(
             cpu_target,
             optimization,
             cargo_jobs,
+            offline,
             |msg| emit(Event::CurrentItem(msg)),
         ) {
             Ok(path) => return Ok(path),
@@ -366,12 +1035,29 @@ pub fn build_with_autofix(
     bail!("Build failed after {MAX_FIX_ATTEMPTS} auto-fix attempts.")
 }
 
+/// RUSTFLAGS used for the codex build (also recorded in the build manifest).
+pub fn build_rustflags(cpu_target: Option<&str>, optimization: &OptimizationFlags) -> Vec<String> {
+    let mut rustflags = Vec::new();
+    if let Some(cpu) = cpu_target {
+        rustflags.push(format!("-C target-cpu={}", cpu));
+    }
+    if optimization.use_mold {
+        rustflags.push("-C link-arg=-fuse-ld=mold".into());
+    }
+    if optimization.use_bolt {
+        // Required for BOLT to rewrite the binary.
+        rustflags.push("-C link-arg=-Wl,--emit-relocs".into());
+    }
+    rustflags
+}
+
 fn run_cargo_build(
     workspace: &Path,
     profile: &str,
     cpu_target: Option<&str>,
     optimization: &OptimizationFlags,
     cargo_jobs: Option<usize>,
+    offline: bool,
     mut on_current_item: impl FnMut(String),
 ) -> std::result::Result<PathBuf, BuildError> {
     let mut cmd = Command::new(resolve_command_path("cargo").map_err(BuildError::Other)?);
@@ -391,18 +1077,12 @@ fn run_cargo_build(
     if let Some(jobs) = cargo_jobs {
         cmd.arg("--jobs").arg(jobs.to_string());
     }
-
-    let mut rustflags = Vec::new();
-    if let Some(cpu) = cpu_target {
-        rustflags.push(format!("-C target-cpu={}", cpu));
-    }
-    if optimization.use_mold {
-        rustflags.push("-C link-arg=-fuse-ld=mold".into());
-    }
-    if optimization.use_bolt {
-        // Required for BOLT to rewrite the binary.
-        rustflags.push("-C link-arg=-Wl,--emit-relocs".into());
+    // Not `--frozen`: patches may legitimately touch Cargo.lock (e.g. version bumps).
+    if offline {
+        cmd.arg("--offline");
     }
+
+    let rustflags = build_rustflags(cpu_target, optimization);
     if !rustflags.is_empty() {
         cmd.env("RUSTFLAGS", rustflags.join(" "));
     }
@@ -461,14 +1141,12 @@ fn run_cargo_build(
                         compiler_errors.push(msg.message);
                     }
                 }
-                Message::BuildFinished(fin) => {
-                    if !fin.success {
-                        let diagnostics: Vec<CompileDiagnostic> = compiler_errors
-                            .iter()
-                            .map(|e| CompileDiagnostic::from_cargo(e, workspace))
-                            .collect();
-                        return Err(BuildError::CompileError { diagnostics });
-                    }
+                Message::BuildFinished(fin) if !fin.success => {
+                    let diagnostics: Vec<CompileDiagnostic> = compiler_errors
+                        .iter()
+                        .map(|e| CompileDiagnostic::from_cargo(e, workspace))
+                        .collect();
+                    return Err(BuildError::CompileError { diagnostics });
                 }
                 _ => {}
             }
@@ -669,6 +1347,7 @@ pub fn strip_binary(binary_path: &Path) -> Result<()> {
 pub fn run_verification_tests(
     workspace: &Path,
     cargo_jobs: Option<usize>,
+    offline: bool,
     mut emit: impl FnMut(Event),
 ) -> Result<()> {
     emit(Event::Phase(Phase::Testing));
@@ -687,6 +1366,9 @@ pub fn run_verification_tests(
         if let Some(jobs) = cargo_jobs {
             cmd.arg("--jobs").arg(jobs.to_string());
         }
+        if offline {
+            cmd.arg("--offline");
+        }
         let status = cmd.stdout(Stdio::null()).stderr(Stdio::null()).status()?;
 
         if status.success() {
@@ -734,3 +1416,890 @@ pub fn setup_alias(binary_path: &Path) -> Result<Option<String>> {
 
     Ok(Some(rc_file))
 }
+
+/// Everything needed to run the full build pipeline without prompting.
+///
+/// The TUI fills this from its screens; `codex-xtreme build --recipe` fills it from a recipe file.
+#[derive(Clone, Debug)]
+pub struct PipelineParams {
+    /// Path to the repo root (not codex-rs).
+    pub repo_path: PathBuf,
+    /// Tag (or branch) to check out before patching.
+    pub version: String,
+    pub cherry_pick_shas: Vec<String>,
+    /// Opt-in retry strategies for conflicting cherry-picks.
+    pub cherry_pick_options: crate::core::CherryPickOptions,
+    pub patches: Vec<PathBuf>,
+    /// Patch ids to leave out of `patches`, keyed by patch file.
+    pub disabled_patches: DisabledPatches,
+    pub build_options: BuildOptions,
+    pub run_tests: bool,
+    pub setup_alias: bool,
+    /// Build cache directory (`None` disables the cache).
+    pub cache_dir: Option<PathBuf>,
+    /// Build in a dedicated `git worktree` on branch `xtreme/<tag>` instead of checking out
+    /// the tag in `repo_path` (which detaches HEAD).
+    pub use_worktree: bool,
+    /// Stash uncommitted changes in `repo_path` before an in-place checkout. Without this a
+    /// dirty clone fails the build.
+    pub allow_stash: bool,
+    /// After an in-place build (successful or not), check the previous branch out again and
+    /// pop the stash.
+    pub restore_checkout: bool,
+    /// After a successful in-place build, restore every file the pipeline touched (see
+    /// [`crate::cleanup`]).
+    pub cleanup: bool,
+    /// Refuse to patch when selected patch files edit the same lines (see
+    /// [`find_patch_conflicts`]). Otherwise conflicts are logged.
+    pub strict: bool,
+    /// Treat every selected file as required: a patch that fails or is skipped stops the
+    /// build before compiling. Otherwise only files declared `required` are enforced.
+    pub require_all: bool,
+}
+
+/// Result of a successful pipeline run.
+#[derive(Clone, Debug)]
+pub struct PipelineOutcome {
+    pub binary_path: PathBuf,
+    /// Shell rc file that received the alias (if alias setup ran and succeeded).
+    pub alias_rc_file: Option<String>,
+    /// Sidecar build manifest (`<binary>.manifest.json`), if it could be written; on a cache
+    /// hit, the cache entry's `manifest.json`.
+    pub manifest_path: Option<PathBuf>,
+    /// Copy in the versioned install store, if installing succeeded.
+    pub installed: Option<InstalledBuild>,
+}
+
+/// Run checkout -> cherry-pick -> patches -> build -> BOLT -> strip -> manifest -> tests ->
+/// install -> alias.
+///
+/// Fatal steps (checkout, patching, compiling) return an error; optional post-build steps
+/// log a warning and continue, matching the interactive frontends.
+pub fn run_pipeline(
+    params: &PipelineParams,
+    mut emit: impl FnMut(Event),
+) -> Result<PipelineOutcome> {
+    let mut restore_point = None;
+    let mut journal = None;
+    let result = run_pipeline_steps(params, &mut restore_point, &mut journal, &mut emit);
+
+    // Restoring the checkout needs the build's changes gone, so clean up first even after a
+    // failure; otherwise a failed build stays as-is for inspection.
+    let kept_changes = journal.is_some() && !params.cleanup;
+    if let Some(mut journal) = journal {
+        if params.cleanup && (result.is_ok() || params.restore_checkout) {
+            emit(Event::CurrentItem("Restoring workspace...".to_string()));
+            let tag = journal.tag.clone();
+            match journal.finish(&params.repo_path) {
+                Ok(outcome) => emit(Event::Log(format!("  ✓ {}", outcome.summary(&tag)))),
+                Err(e) => emit(Event::Log(format!("  ⚠ workspace cleanup failed: {}", e))),
+            }
+        } else if params.cleanup {
+            emit(Event::Log(
+                "  Workspace left as-is for inspection; the next run offers to clean it up"
+                    .to_string(),
+            ));
+        } else if result.is_ok() {
+            // Kept on purpose: the next run reports it as such and asks before undoing it.
+            if let Err(e) = journal.record(&params.repo_path, "done") {
+                emit(Event::Log(format!("  ⚠ build journal not updated: {}", e)));
+            }
+        }
+    }
+
+    if let Some(point) = restore_point {
+        if params.restore_checkout && kept_changes {
+            emit(Event::Log(format!(
+                "  Not restoring {}: the patched sources were kept (cleanup is off)",
+                point.previous_ref
+            )));
+        }
+        if params.restore_checkout && !kept_changes {
+            emit(Event::CurrentItem(format!(
+                "Restoring {}...",
+                point.previous_ref
+            )));
+            match crate::core::restore_checkout(&params.repo_path, &point) {
+                Ok(outcome) => emit(Event::Log(format!(
+                    "  {}",
+                    crate::core::describe_restore(&point, &outcome)
+                ))),
+                Err(e) => emit(Event::Log(format!("  ⚠ restore failed: {}", e))),
+            }
+        } else if let Some(stash) = &point.stash {
+            emit(Event::Log(format!(
+                "  Your changes are stashed ({}); was on {}. `git checkout {} && git stash pop` to restore",
+                &stash[..7.min(stash.len())],
+                point.previous_ref,
+                point.previous_ref
+            )));
+        }
+    }
+    result
+}
+
+fn run_pipeline_steps(
+    params: &PipelineParams,
+    restore_point: &mut Option<crate::core::RestorePoint>,
+    journal: &mut Option<BuildJournal>,
+    mut emit: impl FnMut(Event),
+) -> Result<PipelineOutcome> {
+    let options = &params.build_options;
+    let start_time = std::time::Instant::now();
+
+    // Declared relations decide the patch order; a missing requirement or a cycle stops the
+    // build before anything is checked out.
+    let (patches, patch_graph) = ordered_patches(&params.patches, &mut emit)?;
+
+    // Phase 1: Checkout version (in place, or in a dedicated worktree)
+    emit(Event::Phase(Phase::Patching));
+    emit(Event::CurrentItem(format!(
+        "Checking out {}",
+        params.version
+    )));
+    if !options.offline && crate::core::is_shallow(&params.repo_path) {
+        emit(Event::CurrentItem(format!(
+            "Fetching history for {} (shallow clone)",
+            params.version
+        )));
+        let fetch = crate::core::ensure_history(
+            &params.repo_path,
+            &params.version,
+            &params.cherry_pick_shas,
+        )
+        .map_err(|e| anyhow::anyhow!("Fetching history failed: {}", e))?;
+        if let Some(summary) = fetch.summary() {
+            emit(Event::Log(format!("  {}", summary)));
+        }
+        emit(Event::CurrentItem(format!(
+            "Checking out {}",
+            params.version
+        )));
+    }
+    let build_root = if params.use_worktree {
+        let path = crate::core::prepare_worktree(&params.repo_path, &params.version)
+            .map_err(|e| anyhow::anyhow!("Worktree setup failed: {}", e))?;
+        emit(Event::Log(format!(
+            "git worktree {} (branch {})",
+            path.display(),
+            crate::core::worktree_branch(&params.version)
+        )));
+        path
+    } else {
+        // Undoing an earlier build's changes is the user's call (the wizard and TUI ask when
+        // the repo is opened): it may have kept them on purpose, or edited on top of them.
+        if params.cleanup {
+            if let Some(leftovers) =
+                crate::cleanup::detect_leftovers(&params.repo_path).filter(|l| l.journal.is_some())
+            {
+                bail!(
+                    "{}\nRun codex-xtreme interactively to clean it up, or restore the files with git",
+                    leftovers.describe().join("\n  ")
+                );
+            }
+        }
+        emit(Event::Log(format!("git checkout {}", params.version)));
+        let point =
+            crate::core::checkout_version(&params.repo_path, &params.version, params.allow_stash)
+                .map_err(|e| anyhow::anyhow!("Checkout failed: {}", e))?;
+        if let Some(stash) = &point.stash {
+            emit(Event::Log(format!(
+                "  stashed uncommitted changes on {} ({})",
+                point.previous_ref,
+                &stash[..7.min(stash.len())]
+            )));
+        }
+        match BuildJournal::begin(&params.repo_path, &params.version, &point) {
+            Ok(started) => *journal = Some(started),
+            Err(e) => emit(Event::Log(format!("  ⚠ build journal not written: {}", e))),
+        }
+        *restore_point = Some(point);
+        params.repo_path.clone()
+    };
+
+    let workspace = build_root.join(crate::core::CODEX_RS_SUBDIR);
+
+    // Optional: cherry-pick commits (dev mode)
+    let mut applied_cherry_picks = Vec::new();
+    if !params.cherry_pick_shas.is_empty() {
+        record_step(journal, &params.repo_path, "cherry-pick", &mut emit);
+        emit(Event::CurrentItem(format!(
+            "Cherry-picking {} commits...",
+            params.cherry_pick_shas.len()
+        )));
+        match crate::core::cherry_pick_commits(
+            &build_root,
+            &params.cherry_pick_shas,
+            params.use_worktree,
+            &params.cherry_pick_options,
+        ) {
+            Ok(outcome) => {
+                for line in cherry_pick_report(&outcome) {
+                    emit(Event::Log(format!("  {}", line)));
+                }
+                applied_cherry_picks = outcome.applied;
+            }
+            Err(e) => emit(Event::Log(format!(
+                "  ⚠ cherry-pick errored: {} (continuing)",
+                e
+            ))),
+        }
+    }
+
+    // Phase 2: Apply patches
+    let mut patch_records = Vec::new();
+    if !patches.is_empty() {
+        record_step(journal, &params.repo_path, "patches", &mut emit);
+        check_patch_conflicts(&workspace, &patches, params, &mut emit)?;
+        patch_records = apply_patches(
+            &workspace,
+            &patches,
+            &params.disabled_patches,
+            |ev| match ev {
+                Event::Phase(_) => {}
+                Event::Progress(p) => emit(Event::Progress(0.02 + 0.08 * p)),
+                other => emit(other),
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("Patch application failed: {}", e))?;
+
+        // Required files (every file with `require_all`) must apply in full before compiling.
+        let failures = required_patch_failures(&patch_records, |path| {
+            params.require_all || patch_graph.is_required(path)
+        });
+        if !failures.is_empty() {
+            let count = failures.len();
+            for failure in failures {
+                emit(Event::RequiredPatchFailed(failure));
+            }
+            bail!("{} required patch(es) did not apply; not compiling", count);
+        }
+    }
+
+    // Phase 3: Compile (with autofix)
+    record_step(journal, &params.repo_path, "profile", &mut emit);
+    if options.profile == "xtreme" {
+        if let Err(e) = inject_xtreme_profile(&workspace) {
+            emit(Event::Log(format!(
+                "  ⚠ Failed to inject xtreme profile: {} (continuing)",
+                e
+            )));
+        }
+    }
+    if params.use_worktree {
+        commit_worktree(
+            &build_root,
+            &patches_commit_message(&patch_records),
+            &mut emit,
+        );
+    }
+
+    // Reuse an identical earlier build if the cache has one.
+    let (cache_key, cached) = match &params.cache_dir {
+        Some(cache_dir) => lookup_cached_build(
+            cache_dir,
+            &build_root,
+            &params.version,
+            &applied_cherry_picks,
+            &patch_records,
+            options,
+            &mut emit,
+        ),
+        None => (None, None),
+    };
+
+    record_step(journal, &params.repo_path, "build", &mut emit);
+    let (binary_path, manifest, manifest_path) = if let Some(hit) = cached {
+        // The cached binary already carries its manifest (embedded and in the entry).
+        emit(Event::Progress(0.92));
+        (hit.binary, hit.manifest, Some(hit.manifest_path))
+    } else {
+        emit(Event::Phase(Phase::Compiling));
+        emit(Event::CurrentItem(format!(
+            "Building codex-cli (profile {})...",
+            options.profile
+        )));
+
+        let mut binary_path = build_with_autofix(
+            &workspace,
+            &options.profile,
+            options.cpu_target.as_deref(),
+            &options.optimization,
+            options.cargo_jobs,
+            options.offline,
+            |ev| match ev {
+                Event::Progress(p) => emit(Event::Progress(0.10 + 0.75 * p)),
+                other => emit(other),
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("Build failed: {}", e))?;
+        emit(Event::Progress(0.85));
+
+        // Optional: BOLT
+        let mut bolted = false;
+        if options.optimization.use_bolt {
+            match run_bolt_optimization(&binary_path, |ev| match ev {
+                Event::Progress(p) => emit(Event::Progress(0.85 + 0.07 * p)),
+                other => emit(other),
+            }) {
+                Ok(bolted_path) => {
+                    binary_path = bolted_path;
+                    bolted = true;
+                    emit(Event::Log("BOLT optimization complete".to_string()));
+                }
+                Err(e) => emit(Event::Log(format!("BOLT failed: {} (continuing)", e))),
+            }
+        }
+
+        // Optional: strip
+        let mut stripped = false;
+        if options.strip_symbols {
+            emit(Event::CurrentItem("Stripping symbols...".to_string()));
+            match strip_binary(&binary_path) {
+                Ok(()) => stripped = true,
+                Err(e) => emit(Event::Log(format!("  ⚠ strip failed: {} (continuing)", e))),
+            }
+        }
+
+        // Provenance manifest (after strip/BOLT, which rewrite the binary)
+        let mut manifest = BuildManifest::new(&build_root, &params.version, options);
+        manifest.cache_key = cache_key.clone();
+        manifest.cherry_picks = applied_cherry_picks;
+        manifest.patches = patch_records;
+        manifest.bolt = bolted;
+        manifest.stripped = stripped;
+        manifest.build_secs = start_time.elapsed().as_secs_f64();
+        let manifest_path = record_manifest(&binary_path, &manifest, &mut emit);
+
+        if params.use_worktree {
+            commit_worktree(
+                &build_root,
+                "codex-xtreme: changes made during build\n\nCompiler auto-fixes and lockfile updates.",
+                &mut emit,
+            );
+        }
+        if let (Some(cache_dir), Some(key)) = (&params.cache_dir, &cache_key) {
+            store_cached_build(cache_dir, key, &binary_path, &manifest, &mut emit);
+        }
+        (binary_path, manifest, manifest_path)
+    };
+
+    // Optional: tests
+    record_step(journal, &params.repo_path, "install", &mut emit);
+    if params.run_tests {
+        if let Err(e) = run_verification_tests(
+            &workspace,
+            options.cargo_jobs,
+            options.offline,
+            |ev| match ev {
+                Event::Progress(p) => emit(Event::Progress(0.92 + 0.05 * p)),
+                other => emit(other),
+            },
+        ) {
+            emit(Event::Log(format!("  ⚠ tests errored: {} (continuing)", e)));
+        }
+    }
+
+    // Install into the versioned store; the alias targets its stable `current` path so
+    // later cargo builds/cleans in the workspace cannot break it.
+    emit(Event::Phase(Phase::Installing));
+    let installed = install_build(&binary_path, &manifest, &mut emit);
+
+    // Optional: alias setup
+    let mut alias_rc_file = None;
+    if params.setup_alias {
+        emit(Event::CurrentItem("Setting up shell alias...".to_string()));
+        let alias_target = installed
+            .as_ref()
+            .map_or_else(|| binary_path.clone(), |(_, current)| current.clone());
+        match setup_alias(&alias_target) {
+            Ok(Some(rc_file)) => {
+                emit(Event::Log(format!(
+                    "  ✓ Added/updated alias in {}",
+                    rc_file
+                )));
+                alias_rc_file = Some(rc_file);
+            }
+            Ok(None) => emit(Event::Log(format!(
+                "  ⚠ Fish shell detected: add alias manually: alias codex=\"{}\"",
+                alias_target.display()
+            ))),
+            Err(e) => emit(Event::Log(format!("  ⚠ alias setup failed: {}", e))),
+        }
+    }
+
+    emit(Event::Progress(1.0));
+    Ok(PipelineOutcome {
+        binary_path,
+        alias_rc_file,
+        manifest_path,
+        installed: installed.map(|(build, _)| build),
+    })
+}
+
+/// `patches` in the order their declared relations require (see [`crate::patch_order`]),
+/// with the relations themselves.
+fn ordered_patches(
+    patches: &[PathBuf],
+    emit: &mut impl FnMut(Event),
+) -> Result<(Vec<PathBuf>, crate::patch_order::PatchGraph)> {
+    if patches.is_empty() {
+        return Ok(Default::default());
+    }
+    let graph = crate::patch_order::PatchGraph::load(patches)
+        .map_err(|e| anyhow::anyhow!("Patch order: {:#}", e))?;
+    let ordered = graph
+        .order(patches)
+        .map_err(|e| anyhow::anyhow!("Patch order: {:#}", e))?;
+    if ordered != patches {
+        let names: Vec<String> = ordered.iter().map(|p| patch_file_name(p)).collect();
+        emit(Event::Log(format!("  patch order: {}", names.join(", "))));
+    }
+    Ok((ordered, graph))
+}
+
+/// Look for overlapping patches before applying them: an error in strict mode, a warning
+/// otherwise.
+fn check_patch_conflicts(
+    workspace: &Path,
+    patches: &[PathBuf],
+    params: &PipelineParams,
+    emit: &mut impl FnMut(Event),
+) -> Result<()> {
+    let conflicts = match find_patch_conflicts(workspace, None, patches, &params.disabled_patches) {
+        Ok(conflicts) => conflicts,
+        Err(e) if params.strict => bail!("Patch conflict check failed: {}", e),
+        Err(e) => {
+            emit(Event::Log(format!(
+                "  ⚠ patch conflict check failed: {}",
+                e
+            )));
+            return Ok(());
+        }
+    };
+    for conflict in &conflicts {
+        emit(Event::Log(format!("  ⚠ conflict: {}", conflict.describe())));
+    }
+    if params.strict && !conflicts.is_empty() {
+        bail!(
+            "{} patch conflict(s) in strict mode; deselect one file of each pair and retry",
+            conflicts.len()
+        );
+    }
+    Ok(())
+}
+
+/// Journal what the build changed so far as `step` starts (in-place builds only).
+fn record_step(
+    journal: &mut Option<BuildJournal>,
+    repo: &Path,
+    step: &str,
+    emit: &mut impl FnMut(Event),
+) {
+    if let Some(journal) = journal {
+        if let Err(e) = journal.record(repo, step) {
+            emit(Event::Log(format!("  ⚠ build journal not updated: {}", e)));
+        }
+    }
+}
+
+/// Log lines explaining retried, auto-included and skipped cherry-picks.
+pub fn cherry_pick_report(outcome: &crate::core::CherryPickOutcome) -> Vec<String> {
+    let short = |s: &str| s[..7.min(s.len())].to_string();
+    let mut lines = Vec::new();
+    for (sha, strategy) in &outcome.retried {
+        lines.push(format!(
+            "↻ {} applied with -X {}",
+            short(sha),
+            strategy.name()
+        ));
+    }
+    if !outcome.included_prerequisites.is_empty() {
+        lines.push(format!(
+            "↻ included prerequisite commit(s): {}",
+            outcome
+                .included_prerequisites
+                .iter()
+                .map(|s| short(s))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if !outcome.skipped.is_empty() {
+        lines.push(format!(
+            "⚠ skipped {} conflicting commit(s): {}",
+            outcome.skipped.len(),
+            outcome
+                .skipped
+                .iter()
+                .map(|s| short(s))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    for failure in &outcome.failures {
+        lines.extend(failure.describe().lines().map(|l| format!("  {}", l)));
+    }
+    lines
+}
+
+/// Commit message for the patches (and injected profile) in a build worktree.
+pub fn patches_commit_message(patch_records: &[PatchFileRecord]) -> String {
+    let mut message = String::from("codex-xtreme: apply patches\n");
+    if !patch_records.is_empty() {
+        message.push('\n');
+    }
+    for record in patch_records {
+        message.push_str(&format!(
+            "- {} ({})\n",
+            record.name,
+            &record.sha256[..12.min(record.sha256.len())]
+        ));
+    }
+    message
+}
+
+/// Commit pending changes in a build worktree, logging (not failing) on errors.
+pub fn commit_worktree(worktree: &Path, message: &str, mut emit: impl FnMut(Event)) {
+    match crate::core::commit_all(worktree, message) {
+        Ok(true) => emit(Event::Log(format!(
+            "  ✓ Committed: {}",
+            message.lines().next().unwrap_or_default()
+        ))),
+        Ok(false) => {}
+        Err(e) => emit(Event::Log(format!("  ⚠ worktree commit failed: {}", e))),
+    }
+}
+
+/// Compute the build cache key for the current checkout and look it up.
+///
+/// Returns the key (`None` when the commit or toolchain cannot be determined, which disables
+/// caching for this build) and the cached build on a hit.
+pub fn lookup_cached_build(
+    cache_dir: &Path,
+    repo_path: &Path,
+    tag: &str,
+    cherry_picks: &[String],
+    patches: &[PatchFileRecord],
+    options: &BuildOptions,
+    mut emit: impl FnMut(Event),
+) -> (Option<String>, Option<CachedBuild>) {
+    let Some(commit) = crate::manifest::resolve_commit(repo_path, tag) else {
+        emit(Event::Log(format!(
+            "  ⚠ build cache disabled: could not resolve {tag} to a commit"
+        )));
+        return (None, None);
+    };
+    let workspace = repo_path.join(crate::core::CODEX_RS_SUBDIR);
+    let Some(toolchain) = crate::cache::toolchain_fingerprint(&workspace) else {
+        emit(Event::Log(
+            "  ⚠ build cache disabled: could not run rustc -vV".to_string(),
+        ));
+        return (None, None);
+    };
+    let host_cpu = crate::cpu_detect::detect_cpu_target().name;
+    let key = crate::cache::cache_key(
+        &commit,
+        cherry_picks,
+        patches,
+        options,
+        &toolchain,
+        &host_cpu,
+    );
+
+    let hit = BuildCache::new(cache_dir).lookup(&key);
+    match &hit {
+        Some(hit) => emit(Event::Log(format!(
+            "  ✓ Build cache hit {} (built {}); skipping compile",
+            &key[..12],
+            crate::manifest::format_utc(hit.manifest.built_at)
+        ))),
+        None => emit(Event::Log(format!("  ○ Build cache miss {}", &key[..12]))),
+    }
+    (Some(key), hit)
+}
+
+/// Publish a finished binary to the build cache, logging (not failing) on errors.
+pub fn store_cached_build(
+    cache_dir: &Path,
+    key: &str,
+    binary_path: &Path,
+    manifest: &BuildManifest,
+    mut emit: impl FnMut(Event),
+) {
+    match BuildCache::new(cache_dir).store(key, binary_path, manifest) {
+        Ok(entry) => emit(Event::Log(format!("  ✓ Cached build: {}", entry.display()))),
+        Err(e) => emit(Event::Log(format!("  ⚠ could not cache build: {}", e))),
+    }
+}
+
+/// Copy the binary into the install store and make it current, logging (not failing) on
+/// errors. Returns the installed build and the stable `current` binary path.
+pub fn install_build(
+    binary_path: &Path,
+    manifest: &BuildManifest,
+    mut emit: impl FnMut(Event),
+) -> Option<(InstalledBuild, PathBuf)> {
+    emit(Event::CurrentItem(
+        "Installing into build store...".to_string(),
+    ));
+    let result = InstallStore::open_default().and_then(|store| {
+        let build = store.install(binary_path, manifest)?;
+        Ok((build, store.current_binary()))
+    });
+    match result {
+        Ok((build, current)) => {
+            emit(Event::Log(format!(
+                "  ✓ Installed {} ({})",
+                build.id,
+                build.binary.display()
+            )));
+            Some((build, current))
+        }
+        Err(e) => {
+            emit(Event::Log(format!(
+                "  ⚠ install failed: {} (continuing)",
+                e
+            )));
+            None
+        }
+    }
+}
+
+/// Write the sidecar manifest and embed it in the binary, logging (not failing) on errors.
+pub fn record_manifest(
+    binary_path: &Path,
+    manifest: &BuildManifest,
+    mut emit: impl FnMut(Event),
+) -> Option<PathBuf> {
+    emit(Event::CurrentItem(
+        "Recording build manifest...".to_string(),
+    ));
+    match crate::manifest::record(binary_path, manifest) {
+        Ok((sidecar, embed_err)) => {
+            emit(Event::Log(format!("  ✓ Manifest: {}", sidecar.display())));
+            if let Some(e) = embed_err {
+                emit(Event::Log(format!(
+                    "  ⚠ could not embed manifest in binary: {} (sidecar only)",
+                    e
+                )));
+            }
+            Some(sidecar)
+        }
+        Err(e) => {
+            emit(Event::Log(format!("  ⚠ manifest not written: {}", e)));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preview(id: &str, status: PatchStatus) -> PatchPreview {
+        PatchPreview {
+            record: PatchRecord {
+                id: id.to_string(),
+                status,
+                file: Some(PathBuf::from("core/src/lib.rs")),
+                reason: (status == PatchStatus::Failed).then(|| "no match".to_string()),
+            },
+            diff: String::new(),
+        }
+    }
+
+    #[test]
+    fn preview_reports_outcomes_and_refuses_paths_outside_workspace() {
+        assert!(is_inside_workspace(Path::new("core/src/lib.rs")));
+        assert!(is_inside_workspace(Path::new("./Cargo.toml")));
+        assert!(!is_inside_workspace(Path::new("../outside.rs")));
+        assert!(!is_inside_workspace(Path::new("/etc/passwd")));
+
+        let previews = [PatchFilePreview {
+            name: "privacy".to_string(),
+            path: PathBuf::from("privacy.toml"),
+            patches: vec![
+                preview("a", PatchStatus::Applied),
+                preview("b", PatchStatus::Applied),
+                preview("c", PatchStatus::AlreadyApplied),
+                preview("d", PatchStatus::Failed),
+                preview("e", PatchStatus::SkippedVersion),
+            ],
+        }];
+        assert_eq!(
+            preview_summary(&previews),
+            "2 would apply, 1 already applied, 1 skipped for version, 1 would fail"
+        );
+        assert_eq!(
+            previews[0].patches[3].summary(),
+            "✗ would fail d (core/src/lib.rs): no match"
+        );
+        assert!(previews[0].patches[3].is_problem());
+        assert_eq!(preview_summary(&[]), "no patches");
+    }
+
+    #[test]
+    fn preview_reports_switched_off_patch_ids() {
+        let dir = crate::test_support::TestRepo::new("preview-disabled");
+        let workspace = dir.join("codex-rs");
+        std::fs::create_dir_all(workspace.join("core/src")).unwrap();
+        std::fs::write(
+            workspace.join("Cargo.toml"),
+            "[workspace.package]\nversion = \"0.105.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(workspace.join("core/src/lib.rs"), "telemetry: true\n").unwrap();
+        let patch_file = dir.join("privacy.toml");
+        std::fs::write(
+            &patch_file,
+            r#"[meta]
+name = "privacy"
+workspace_relative = true
+
+[[patches]]
+id = "telemetry-off"
+file = "core/src/lib.rs"
+
+[patches.query]
+type = "text"
+search = "telemetry: true"
+
+[patches.operation]
+type = "replace"
+text = "telemetry: false"
+
+[[patches]]
+id = "statsig-endpoint"
+file = "core/src/lib.rs"
+
+[patches.query]
+type = "text"
+search = "statsig"
+
+[patches.operation]
+type = "replace"
+text = "localhost"
+"#,
+        )
+        .unwrap();
+        let disabled =
+            DisabledPatches::from([(patch_file.clone(), vec!["statsig-endpoint".to_string()])]);
+
+        let previews = preview_patches(
+            &workspace,
+            None,
+            std::slice::from_ref(&patch_file),
+            &disabled,
+        )
+        .unwrap();
+        let patches = &previews[0].patches;
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].record.id, "telemetry-off");
+        assert_eq!(patches[1].record.id, "statsig-endpoint");
+        assert_eq!(patches[1].record.status, PatchStatus::Disabled);
+        assert!(patches[1].diff.is_empty());
+        assert_eq!(patches[1].summary(), "– switched off statsig-endpoint");
+        assert!(preview_summary(&previews).contains("1 switched off"));
+    }
+
+    #[test]
+    fn overlapping_hunks_from_different_files_conflict() {
+        let diff = "\
+diff --git a/core/src/otel.rs b/core/src/otel.rs
+index 1111111..2222222 100644
+--- a/core/src/otel.rs
++++ b/core/src/otel.rs
+@@ -10,5 +10,2 @@ fn init() {
+@@ -40,0 +38,3 @@ fn shutdown() {
+diff --git a/core/src/new.rs b/core/src/new.rs
+new file mode 100644
+--- /dev/null
++++ b/core/src/new.rs
+@@ -0,0 +1,4 @@
+";
+        let hunks = diff_hunks(diff);
+        assert_eq!(
+            hunks,
+            vec![
+                (PathBuf::from("core/src/otel.rs"), (10, 14)),
+                (PathBuf::from("core/src/otel.rs"), (40, 40)),
+                (PathBuf::from("core/src/new.rs"), (0, 0)),
+            ]
+        );
+
+        let edit = |file: &str, id: &str, lines: (usize, usize)| PatchEdit {
+            patch_file: PathBuf::from(file),
+            patch_id: id.to_string(),
+            target: PathBuf::from("core/src/otel.rs"),
+            lines,
+        };
+        let edits = vec![
+            edit("a.toml", "otel-off", (10, 14)),
+            edit("a.toml", "otel-off", (12, 13)),
+            edit("a.toml", "endpoint", (12, 12)),
+            edit("b.toml", "otel-stub", (14, 20)),
+            edit("b.toml", "shutdown", (40, 40)),
+        ];
+        let conflicts = overlapping_edits(&edits);
+        // Same-file overlaps are the patcher's business; each cross-file pair is reported once.
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].describe(),
+            "otel-off (a) and otel-stub (b) both edit core/src/otel.rs (line 14)"
+        );
+    }
+
+    #[test]
+    fn required_files_report_every_patch_that_did_not_apply() {
+        let record = |name: &str, statuses: &[PatchStatus]| PatchFileRecord {
+            name: name.to_string(),
+            path: PathBuf::from(format!("patches/{}.toml", name)),
+            sha256: String::new(),
+            disabled: Vec::new(),
+            results: statuses
+                .iter()
+                .enumerate()
+                .map(|(i, status)| preview(&format!("p{}", i), *status).record)
+                .collect(),
+        };
+        let mut records = vec![
+            record(
+                "privacy",
+                &[
+                    PatchStatus::Applied,
+                    PatchStatus::Failed,
+                    PatchStatus::AlreadyApplied,
+                ],
+            ),
+            record("extras", &[PatchStatus::SkippedVersion]),
+        ];
+        records[0].disabled = vec!["p3".to_string()];
+
+        let failures = required_patch_failures(&records, |path| path.ends_with("privacy.toml"));
+        assert_eq!(
+            failures[0],
+            RequiredPatchFailure {
+                file: "privacy".to_string(),
+                id: "p1".to_string(),
+                status: PatchStatus::Failed,
+                target: Some(PathBuf::from("core/src/lib.rs")),
+                reason: Some("no match".to_string()),
+            }
+        );
+        assert_eq!(
+            failures[0].to_string(),
+            "privacy: p1 failed (core/src/lib.rs): no match"
+        );
+        // A switched-off id means the file did not apply in full.
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[1].to_string(), "privacy: p3 switched off");
+        // Strict mode: every file is required, version skips included.
+        let failures = required_patch_failures(&records, |_| true);
+        assert_eq!(failures.len(), 3);
+        assert_eq!(
+            failures[2].to_string(),
+            "extras: p0 skipped for version (core/src/lib.rs)"
+        );
+    }
+}